@@ -0,0 +1,197 @@
+//! Admin DTOs - Request/Response types for user and role management and the audit log
+
+use crate::domain::{AuditEntry, AuditQuery};
+use crate::infrastructure::supabase::{AdminUser, UserPage};
+use crate::services::Grants;
+use crate::shared::constants::admin::{
+    DEFAULT_USERS_PER_PAGE, MAX_USERS_PER_PAGE, PERMANENT_BAN_HOURS,
+};
+use crate::shared::constants::audit::{DEFAULT_AUDIT_QUERY_LIMIT, MAX_AUDIT_QUERY_LIMIT};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use validator::Validate;
+
+// ============================================================================
+// REQUEST DTOs WITH VALIDATION
+// ============================================================================
+
+/// Page of the user list, `page` starting at 1
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserListParams {
+    #[validate(range(min = 1, message = "Page starts at 1"))]
+    pub page: Option<u32>,
+
+    #[validate(range(min = 1, max = MAX_USERS_PER_PAGE, message = "Per page must be 1-1000"))]
+    pub per_page: Option<u32>,
+}
+
+impl UserListParams {
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page.unwrap_or(DEFAULT_USERS_PER_PAGE)
+    }
+}
+
+/// Keys merged into an account's metadata, at least one of the two objects
+#[derive(Debug, Deserialize)]
+pub struct UpdateMetadataRequest {
+    /// Only admins can write it - e.g. `{"roles": ["support"]}`
+    pub app_metadata: Option<Value>,
+    pub user_metadata: Option<Value>,
+}
+
+/// Ban an account, for good without `hours`
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct BanRequest {
+    #[validate(range(min = 1, max = PERMANENT_BAN_HOURS, message = "Hours must be 1-876000"))]
+    pub hours: Option<u64>,
+}
+
+impl BanRequest {
+    pub fn hours(&self) -> u64 {
+        self.hours.unwrap_or(PERMANENT_BAN_HOURS)
+    }
+}
+
+/// Audit log search - `since` / `until` are Unix timestamps, inclusive
+#[derive(Debug, Deserialize, Validate)]
+pub struct AuditQueryParams {
+    #[validate(length(min = 1, max = 128, message = "Invalid user ID"))]
+    pub user_id: Option<String>,
+
+    #[validate(email(message = "Invalid email format"))]
+    pub email: Option<String>,
+
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+
+    #[validate(range(min = 1, max = MAX_AUDIT_QUERY_LIMIT, message = "Limit must be 1-1000"))]
+    pub limit: Option<usize>,
+}
+
+impl From<AuditQueryParams> for AuditQuery {
+    fn from(p: AuditQueryParams) -> Self {
+        Self {
+            user_id: p.user_id,
+            email: p.email,
+            since: p.since,
+            until: p.until,
+            limit: p.limit.unwrap_or(DEFAULT_AUDIT_QUERY_LIMIT),
+        }
+    }
+}
+
+// ============================================================================
+// RESPONSE DTOs
+// ============================================================================
+
+/// Roles of a user and the permissions they grant
+#[derive(Serialize)]
+pub struct GrantsResponse {
+    pub roles: Vec<String>,
+    pub permissions: Vec<&'static str>,
+}
+
+impl From<Grants> for GrantsResponse {
+    fn from(g: Grants) -> Self {
+        Self {
+            permissions: g.permissions.iter().map(|p| p.as_str()).collect(),
+            roles: g.roles,
+        }
+    }
+}
+
+/// An account as the admin API sees it
+#[derive(Serialize)]
+pub struct AdminUserResponse {
+    pub id: String,
+    pub email: String,
+    pub phone: String,
+    pub role: String,
+    pub app_metadata: Value,
+    pub user_metadata: Value,
+    pub created_at: Option<String>,
+    pub email_confirmed_at: Option<String>,
+    pub last_sign_in_at: Option<String>,
+    pub banned_until: Option<String>,
+}
+
+impl From<AdminUser> for AdminUserResponse {
+    fn from(u: AdminUser) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            phone: u.phone,
+            role: u.role,
+            app_metadata: u.app_metadata,
+            user_metadata: u.user_metadata,
+            created_at: u.created_at,
+            email_confirmed_at: u.email_confirmed_at,
+            last_sign_in_at: u.last_sign_in_at,
+            banned_until: u.banned_until,
+        }
+    }
+}
+
+/// One page of accounts
+#[derive(Serialize)]
+pub struct UserListResponse {
+    pub users: Vec<AdminUserResponse>,
+    pub page: u32,
+    pub per_page: u32,
+    /// Every account in the project, when Supabase reports it
+    pub total: Option<u64>,
+}
+
+impl UserListResponse {
+    pub fn new(list: UserPage, page: u32, per_page: u32) -> Self {
+        Self {
+            users: list
+                .users
+                .into_iter()
+                .map(AdminUserResponse::from)
+                .collect(),
+            page,
+            per_page,
+            total: list.total,
+        }
+    }
+}
+
+/// Roles granted to a user through the admin API
+#[derive(Serialize)]
+pub struct UserRolesResponse {
+    pub user_id: String,
+    pub roles: Vec<String>,
+}
+
+/// One audit log entry
+#[derive(Serialize)]
+pub struct AuditEntryResponse {
+    pub at: u64,
+    pub action: &'static str,
+    pub outcome: &'static str,
+    pub user_id: Option<String>,
+    pub email: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl From<AuditEntry> for AuditEntryResponse {
+    fn from(e: AuditEntry) -> Self {
+        Self {
+            at: e.at,
+            action: e.action.as_str(),
+            outcome: e.outcome.as_str(),
+            user_id: e.user_id,
+            email: e.email,
+            ip: e.ip,
+            user_agent: e.user_agent,
+            detail: e.detail,
+        }
+    }
+}
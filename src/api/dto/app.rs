@@ -0,0 +1,77 @@
+//! App DTOs - Request/Response types for hosted apps and entitlements
+
+use crate::domain::{AppInstance, Entitlement};
+use crate::shared::constants::apps::MAX_TRIAL_DAYS;
+use crate::shared::time::now_secs;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+// ============================================================================
+// REQUEST DTOs WITH VALIDATION
+// ============================================================================
+
+/// Grant access to an app - for good, or as a trial of `trial_days`
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct GrantEntitlementRequest {
+    #[validate(range(min = 1, max = MAX_TRIAL_DAYS, message = "Trial must be 1-365 days"))]
+    pub trial_days: Option<u32>,
+}
+
+impl GrantEntitlementRequest {
+    pub fn trial_secs(&self) -> Option<u64> {
+        self.trial_days.map(|days| u64::from(days) * 24 * 60 * 60)
+    }
+}
+
+// ============================================================================
+// RESPONSE DTOs
+// ============================================================================
+
+/// A user's access to an app
+#[derive(Serialize)]
+pub struct EntitlementResponse {
+    pub app_id: String,
+    pub user_id: String,
+    /// `granted`, `trial` or `revoked`
+    pub status: &'static str,
+    /// False once revoked or the trial is over
+    pub active: bool,
+    pub expires_at: Option<u64>,
+    pub updated_by: String,
+    pub updated_at: u64,
+}
+
+impl From<&Entitlement> for EntitlementResponse {
+    fn from(e: &Entitlement) -> Self {
+        Self {
+            app_id: e.app_id.clone(),
+            user_id: e.user_id.clone(),
+            status: e.status.as_str(),
+            active: e.is_active(now_secs()),
+            expires_at: e.expires_at,
+            updated_by: e.updated_by.clone(),
+            updated_at: e.updated_at,
+        }
+    }
+}
+
+/// An app hosted on the platform and whether the current user may use it
+#[derive(Serialize)]
+pub struct AppResponse {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The current user's entitlement, `None` if they never had one
+    pub access: Option<EntitlementResponse>,
+}
+
+impl AppResponse {
+    pub fn new(app: &AppInstance, entitlement: Option<&Entitlement>) -> Self {
+        Self {
+            id: app.id,
+            name: app.name,
+            description: app.description,
+            access: entitlement.map(EntitlementResponse::from),
+        }
+    }
+}
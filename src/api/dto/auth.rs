@@ -1,6 +1,7 @@
 //! Auth DTOs - Request/Response types for authentication endpoints
 
-use crate::domain::User;
+use crate::domain::{Factor, User};
+use crate::shared::phone::PhoneNumber;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -16,6 +17,63 @@ pub struct LoginRequest {
 
     #[validate(length(min = 6, max = 128, message = "Password must be 6-128 characters"))]
     pub password: String,
+
+    /// How the session is handed back - cookie (browsers, default) or token (CLI, mobile)
+    #[serde(default)]
+    pub mode: AuthMode,
+}
+
+/// Passwordless sign-in: email a magic link / 6-digit code
+#[derive(Debug, Deserialize, Validate)]
+pub struct OtpRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[validate(length(max = 255, message = "Email too long"))]
+    pub email: String,
+
+    /// Sign up unknown emails on the fly instead of ignoring them
+    #[serde(default)]
+    pub create_user: bool,
+}
+
+/// Passwordless sign-in: the code received by email
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyOtpRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[validate(length(max = 255, message = "Email too long"))]
+    pub email: String,
+
+    #[validate(length(min = 6, max = 10, message = "Code must be 6-10 characters"))]
+    pub code: String,
+
+    #[serde(default)]
+    pub mode: AuthMode,
+}
+
+/// Second step of an MFA login: the code from the authenticator app
+#[derive(Debug, Deserialize, Validate)]
+pub struct MfaVerifyRequest {
+    /// From the `mfa_required` response; browser logins have it in the `mfa_token` cookie instead
+    #[validate(length(min = 1, max = 64, message = "Invalid MFA token"))]
+    pub mfa_token: Option<String>,
+
+    /// Defaults to the first factor listed in the `mfa_required` response
+    #[validate(length(min = 1, max = 64, message = "Invalid factor ID"))]
+    pub factor_id: Option<String>,
+
+    #[validate(length(min = 6, max = 10, message = "Code must be 6-10 characters"))]
+    pub code: String,
+
+    #[serde(default)]
+    pub mode: AuthMode,
+}
+
+/// Session delivery for login
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMode {
+    #[default]
+    Cookie,
+    Token,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -30,15 +88,108 @@ pub struct RegisterRequest {
     #[validate(length(min = 3, max = 50, message = "Username must be 3-50 characters"))]
     pub username: String,
 
-    #[validate(length(max = 5, message = "Country code too long"))]
+    /// `+33`, `0033` or `33` - checked with `phone_number` by `RegisterRequest::phone`
     pub phone_country_code: Option<String>,
 
-    #[validate(length(max = 20, message = "Phone number too long"))]
+    /// National number, separators and trunk prefix 0 allowed (`06 12 34 56 78`)
     pub phone_number: Option<String>,
 }
 
+impl RegisterRequest {
+    /// The optional phone number, validated and split for E.164
+    pub fn phone(&self) -> Result<Option<PhoneNumber>, &'static str> {
+        match (&self.phone_country_code, &self.phone_number) {
+            (None, None) => Ok(None),
+            (Some(country_code), Some(number)) => {
+                PhoneNumber::from_parts(country_code, number).map(Some)
+            }
+            _ => Err("Country code and phone number must be given together"),
+        }
+    }
+}
+
+/// SMS sign-in: text a code to a verified phone number
+#[derive(Debug, Deserialize, Validate)]
+pub struct SmsOtpRequest {
+    /// International format (`+33 6 12 34 56 78`), normalized to E.164
+    #[validate(length(min = 1, max = 32, message = "Phone number must be 1-32 characters"))]
+    pub phone: String,
+}
+
+/// SMS sign-in: the code received by text
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifySmsOtpRequest {
+    #[validate(length(min = 1, max = 32, message = "Phone number must be 1-32 characters"))]
+    pub phone: String,
+
+    #[validate(length(min = 6, max = 10, message = "Code must be 6-10 characters"))]
+    pub code: String,
+
+    #[serde(default)]
+    pub mode: AuthMode,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResendConfirmationRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[validate(length(max = 255, message = "Email too long"))]
+    pub email: String,
+}
+
+/// Query string of the email confirmation / magic link
+#[derive(Debug, Deserialize)]
+pub struct ConfirmQuery {
+    pub token_hash: String,
+    #[serde(rename = "type", default)]
+    pub kind: ConfirmKind,
+}
+
+/// `type` of the confirmation/magic link, depends on the Supabase email template
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmKind {
+    Signup,
+    Magiclink,
+    #[default]
+    Email,
+}
+
+/// Query string Supabase sends the browser back with after an OAuth sign-in
+#[derive(Debug, Deserialize)]
+pub struct OAuthCallbackQuery {
+    pub code: Option<String>,
+    /// Set instead of `code` when the user denied access or the provider failed
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[validate(length(max = 255, message = "Email too long"))]
+    pub email: String,
+}
+
+/// New password plus proof of recovery: `token_hash` from the email link,
+/// or the `email` and one-time `code` from the email
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, max = 512, message = "Invalid reset token"))]
+    pub token_hash: Option<String>,
+
+    #[validate(email(message = "Invalid email format"))]
+    #[validate(length(max = 255, message = "Email too long"))]
+    pub email: Option<String>,
+
+    #[validate(length(min = 1, max = 32, message = "Invalid reset code"))]
+    pub code: Option<String>,
+
+    #[validate(length(min = 6, max = 128, message = "Password must be 6-128 characters"))]
+    pub password: String,
+}
+
 // ============================================================================
-// RESPONSE DTO - Only safe data sent to frontend (no tokens!)
+// RESPONSE DTO - Only safe data sent to frontend (no Supabase tokens!)
 // ============================================================================
 
 #[derive(Serialize)]
@@ -46,6 +197,9 @@ pub struct AuthResponse {
     pub username: String,
     pub email: String,
     pub role: String,
+    /// LAPP session token, only in token mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
 }
 
 impl AuthResponse {
@@ -54,6 +208,49 @@ impl AuthResponse {
             username: u.username.clone(),
             email: u.email.clone(),
             role: u.role.clone(),
+            token: None,
+        }
+    }
+
+    /// Include the session token for bearer authentication
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+}
+
+/// Login answer for accounts with MFA: no session yet, send the code with `mfa_token`
+#[derive(Serialize)]
+pub struct MfaRequiredResponse {
+    pub status: &'static str,
+    pub mfa_token: String,
+    pub factors: Vec<FactorResponse>,
+}
+
+impl MfaRequiredResponse {
+    pub fn new(mfa_token: String, factors: &[Factor]) -> Self {
+        Self {
+            status: "mfa_required",
+            mfa_token,
+            factors: factors.iter().map(FactorResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FactorResponse {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub factor_type: String,
+    pub friendly_name: Option<String>,
+}
+
+impl From<&Factor> for FactorResponse {
+    fn from(f: &Factor) -> Self {
+        Self {
+            id: f.id.clone(),
+            factor_type: f.factor_type.clone(),
+            friendly_name: f.friendly_name.clone(),
         }
     }
 }
@@ -1,7 +1,30 @@
 //! Data Transfer Objects - Request/Response types for API endpoints
 
+pub mod admin;
+pub mod app;
 pub mod auth;
+pub mod passkey;
+pub mod session;
 pub mod user;
 
-pub use auth::{AuthResponse, LoginRequest, RegisterRequest};
-pub use user::UserResponse;
+pub use admin::{
+    AdminUserResponse, AuditEntryResponse, AuditQueryParams, BanRequest, GrantsResponse,
+    UpdateMetadataRequest, UserListParams, UserListResponse, UserRolesResponse,
+};
+pub use app::{AppResponse, EntitlementResponse, GrantEntitlementRequest};
+pub use auth::{
+    AuthMode, AuthResponse, ConfirmKind, ConfirmQuery, FactorResponse, ForgotPasswordRequest,
+    LoginRequest, MfaRequiredResponse, MfaVerifyRequest, OAuthCallbackQuery, OtpRequest,
+    RegisterRequest, ResendConfirmationRequest, ResetPasswordRequest, SmsOtpRequest,
+    VerifyOtpRequest, VerifySmsOtpRequest,
+};
+pub use passkey::{
+    PasskeyLoginRequest, PasskeyOptionsResponse, PasskeyProof, PasskeyRegisterRequest,
+    PasskeyResponse,
+};
+pub use session::SessionResponse;
+pub use user::{
+    ChangeEmailRequest, ChangePasswordRequest, ConfirmPhoneRequest, EnrollTotpRequest,
+    PhoneVerificationRequest, ReauthRequest, TotpEnrollmentResponse, UserResponse,
+    VerifyTotpRequest,
+};
@@ -0,0 +1,307 @@
+//! Passkey DTOs - WebAuthn ceremony options and browser responses
+//!
+//! Options use the JSON form of the WebAuthn spec, ready for
+//! `PublicKeyCredential.parseCreationOptionsFromJSON` / `parseRequestOptionsFromJSON`;
+//! credentials are what `PublicKeyCredential.toJSON()` produces. Binary fields are base64url.
+
+use super::AuthMode;
+use crate::domain::{Passkey, User};
+use crate::error::AppError;
+use crate::infrastructure::RelyingParty;
+use crate::infrastructure::webauthn::COSE_ES256;
+use crate::services::{Assertion, Attestation, Ceremony};
+use crate::shared::constants::auth::PASSKEY_CEREMONY_TTL_SECS;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+const PUBLIC_KEY: &str = "public-key";
+/// Passkeys replace the password: the authenticator must check who is using it
+const USER_VERIFICATION: &str = "required";
+
+// ============================================================================
+// REQUEST DTOs
+// ============================================================================
+
+/// Finish registering a passkey
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasskeyRegisterRequest {
+    #[validate(length(min = 1, max = 64, message = "Invalid ceremony ID"))]
+    pub ceremony_id: String,
+
+    /// Label shown in the passkey list, defaults to "Passkey"
+    #[validate(length(min = 1, max = 64, message = "Name must be 1-64 characters"))]
+    pub name: Option<String>,
+
+    pub credential: RegistrationCredential,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegistrationCredential {
+    pub response: AttestationResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+/// Decoded registration response
+pub struct DecodedAttestation {
+    client_data_json: Vec<u8>,
+    attestation_object: Vec<u8>,
+}
+
+impl PasskeyRegisterRequest {
+    pub fn decode(&self) -> Result<DecodedAttestation, AppError> {
+        let response = &self.credential.response;
+        Ok(DecodedAttestation {
+            client_data_json: decode(&response.client_data_json)?,
+            attestation_object: decode(&response.attestation_object)?,
+        })
+    }
+}
+
+impl DecodedAttestation {
+    pub fn as_attestation(&self) -> Attestation<'_> {
+        Attestation {
+            client_data_json: &self.client_data_json,
+            attestation_object: &self.attestation_object,
+        }
+    }
+}
+
+/// Log in with a passkey
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasskeyLoginRequest {
+    #[validate(length(min = 1, max = 64, message = "Invalid ceremony ID"))]
+    pub ceremony_id: String,
+
+    pub credential: AssertionCredential,
+
+    #[serde(default)]
+    pub mode: AuthMode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssertionCredential {
+    /// Credential ID, base64url
+    pub id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+}
+
+/// Decoded login response
+pub struct DecodedAssertion {
+    credential_id: String,
+    client_data_json: Vec<u8>,
+    authenticator_data: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+/// A passkey answering a login challenge as proof for a sensitive action
+#[derive(Debug, Deserialize)]
+pub struct PasskeyProof {
+    pub ceremony_id: String,
+    pub credential: AssertionCredential,
+}
+
+impl PasskeyLoginRequest {
+    pub fn decode(&self) -> Result<DecodedAssertion, AppError> {
+        self.credential.decode()
+    }
+}
+
+impl AssertionCredential {
+    pub fn decode(&self) -> Result<DecodedAssertion, AppError> {
+        let response = &self.response;
+        Ok(DecodedAssertion {
+            // Normalized so it matches the stored ID whatever the padding
+            credential_id: URL_SAFE_NO_PAD.encode(decode(&self.id)?),
+            client_data_json: decode(&response.client_data_json)?,
+            authenticator_data: decode(&response.authenticator_data)?,
+            signature: decode(&response.signature)?,
+        })
+    }
+}
+
+impl DecodedAssertion {
+    pub fn as_assertion(&self) -> Assertion<'_> {
+        Assertion {
+            credential_id: &self.credential_id,
+            client_data_json: &self.client_data_json,
+            authenticator_data: &self.authenticator_data,
+            signature: &self.signature,
+        }
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| AppError::validation("credential", "Credential fields must be base64url"))
+}
+
+// ============================================================================
+// RESPONSE DTOs
+// ============================================================================
+
+/// Options for the browser, with the ceremony ID to send back with the credential
+#[derive(Serialize)]
+pub struct PasskeyOptionsResponse<T: Serialize> {
+    pub ceremony_id: String,
+    #[serde(rename = "publicKey")]
+    pub public_key: T,
+}
+
+/// `PublicKeyCredentialCreationOptionsJSON`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreationOptions {
+    rp: RpEntity,
+    user: UserEntity,
+    challenge: String,
+    pub_key_cred_params: [CredentialParameters; 1],
+    timeout: u64,
+    attestation: &'static str,
+    authenticator_selection: AuthenticatorSelection,
+    exclude_credentials: Vec<CredentialDescriptor>,
+}
+
+/// `PublicKeyCredentialRequestOptionsJSON`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions {
+    challenge: String,
+    rp_id: String,
+    timeout: u64,
+    user_verification: &'static str,
+    /// Empty: the authenticator offers the passkeys it holds for this site
+    allow_credentials: Vec<CredentialDescriptor>,
+}
+
+#[derive(Serialize)]
+struct RpEntity {
+    id: String,
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UserEntity {
+    /// User handle, returned by the authenticator at login
+    id: String,
+    name: String,
+    display_name: String,
+}
+
+#[derive(Serialize)]
+struct CredentialParameters {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    alg: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthenticatorSelection {
+    resident_key: &'static str,
+    user_verification: &'static str,
+}
+
+#[derive(Serialize)]
+struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    id: String,
+}
+
+impl PasskeyOptionsResponse<CreationOptions> {
+    pub fn creation(
+        rp: &RelyingParty,
+        user: &User,
+        ceremony: Ceremony,
+        existing: &[Passkey],
+    ) -> Self {
+        Self {
+            ceremony_id: ceremony.id,
+            public_key: CreationOptions {
+                rp: RpEntity {
+                    id: rp.id.clone(),
+                    name: rp.name.clone(),
+                },
+                user: UserEntity {
+                    id: URL_SAFE_NO_PAD.encode(user.id.as_bytes()),
+                    name: user.email.clone(),
+                    display_name: user.username.clone(),
+                },
+                challenge: URL_SAFE_NO_PAD.encode(&ceremony.challenge),
+                pub_key_cred_params: [CredentialParameters {
+                    kind: PUBLIC_KEY,
+                    alg: COSE_ES256,
+                }],
+                timeout: PASSKEY_CEREMONY_TTL_SECS * 1000,
+                attestation: "none",
+                authenticator_selection: AuthenticatorSelection {
+                    // Discoverable, so login needs no email
+                    resident_key: "required",
+                    user_verification: USER_VERIFICATION,
+                },
+                exclude_credentials: existing
+                    .iter()
+                    .map(|p| CredentialDescriptor {
+                        kind: PUBLIC_KEY,
+                        id: p.id.clone(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl PasskeyOptionsResponse<RequestOptions> {
+    pub fn request(rp: &RelyingParty, ceremony: Ceremony) -> Self {
+        Self {
+            ceremony_id: ceremony.id,
+            public_key: RequestOptions {
+                challenge: URL_SAFE_NO_PAD.encode(&ceremony.challenge),
+                rp_id: rp.id.clone(),
+                timeout: PASSKEY_CEREMONY_TTL_SECS * 1000,
+                user_verification: USER_VERIFICATION,
+                allow_credentials: Vec::new(),
+            },
+        }
+    }
+}
+
+/// A registered passkey (the public key stays server-side)
+#[derive(Serialize)]
+pub struct PasskeyResponse {
+    pub id: String,
+    pub name: String,
+    pub created_at: u64,
+    pub last_used_at: Option<u64>,
+}
+
+impl From<&Passkey> for PasskeyResponse {
+    fn from(p: &Passkey) -> Self {
+        Self {
+            id: p.id.clone(),
+            name: p.name.clone(),
+            created_at: p.created_at,
+            last_used_at: p.last_used_at,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Session DTOs - Response types for device/session management
+
+use crate::domain::Session;
+use serde::Serialize;
+
+/// One logged-in device - exposes the public device ID, never the session cookie
+#[derive(Serialize)]
+pub struct SessionResponse {
+    pub id: String,
+    pub created_at: u64,
+    pub last_seen: u64,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// True for the session making the request
+    pub current: bool,
+    /// `aal2` once a second factor was verified on this device
+    pub aal: &'static str,
+}
+
+impl SessionResponse {
+    pub fn from_session(s: &Session, current_session_id: &str) -> Self {
+        Self {
+            id: s.device_id.clone(),
+            created_at: s.created_at,
+            last_seen: s.last_seen,
+            ip: s.client.ip.clone(),
+            user_agent: s.client.user_agent.clone(),
+            current: s.id == current_session_id,
+            aal: s.aal.as_str(),
+        }
+    }
+}
@@ -1,7 +1,113 @@
 //! User DTOs - Response types for user endpoints
 
+use super::PasskeyProof;
 use crate::domain::User;
-use serde::Serialize;
+use crate::infrastructure::supabase::TotpEnrollment;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+// ============================================================================
+// REQUEST DTOs WITH VALIDATION
+// ============================================================================
+
+/// Start phone verification, defaults to the number given at registration
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct PhoneVerificationRequest {
+    #[validate(length(min = 1, max = 32, message = "Phone number must be 1-32 characters"))]
+    pub phone: Option<String>,
+}
+
+/// Finish phone verification with the texted code
+#[derive(Debug, Deserialize, Validate)]
+pub struct ConfirmPhoneRequest {
+    /// The number returned when verification started
+    #[validate(length(min = 1, max = 32, message = "Phone number must be 1-32 characters"))]
+    pub phone: String,
+
+    #[validate(length(min = 6, max = 10, message = "Code must be 6-10 characters"))]
+    pub code: String,
+}
+
+/// Start enrolling a TOTP factor
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct EnrollTotpRequest {
+    /// Label shown in the factor list, must be unique per user
+    #[validate(length(min = 1, max = 64, message = "Name must be 1-64 characters"))]
+    pub friendly_name: Option<String>,
+}
+
+/// Code from the authenticator app: confirms an enrollment or steps the session up to aal2
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyTotpRequest {
+    #[validate(length(min = 1, max = 64, message = "Invalid factor ID"))]
+    pub factor_id: String,
+
+    #[validate(length(min = 6, max = 10, message = "Code must be 6-10 characters"))]
+    pub code: String,
+}
+
+/// New password, with the current one as proof
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, max = 128, message = "Current password is required"))]
+    pub current_password: String,
+
+    #[validate(length(min = 6, max = 128, message = "Password must be 6-128 characters"))]
+    pub new_password: String,
+}
+
+/// New email address, confirmed by following the link sent to it, with a fresh proof as for
+/// closing the account
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangeEmailRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[validate(length(max = 255, message = "Email too long"))]
+    pub email: String,
+
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub reauth: ReauthRequest,
+}
+
+/// One fresh proof that it is really the user asking, before closing the account, changing the
+/// email or adding a passkey: the password, a sign-in code emailed by `POST /auth/otp` or a
+/// passkey. None is needed from a session that passed a second factor in the last 5 minutes.
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct ReauthRequest {
+    #[validate(length(min = 1, max = 128, message = "Password is required"))]
+    pub password: Option<String>,
+
+    #[validate(length(min = 6, max = 10, message = "Code must be 6-10 characters"))]
+    pub code: Option<String>,
+
+    /// Answer to `POST /auth/passkey/options`
+    pub passkey: Option<PasskeyProof>,
+}
+
+// ============================================================================
+// RESPONSE DTOs
+// ============================================================================
+
+/// Secret of a factor being enrolled - shown once, for the authenticator app
+#[derive(Serialize)]
+pub struct TotpEnrollmentResponse {
+    pub factor_id: String,
+    /// SVG data URI
+    pub qr_code: String,
+    pub secret: String,
+    pub uri: String,
+}
+
+impl TotpEnrollmentResponse {
+    pub fn new(factor_id: String, totp: TotpEnrollment) -> Self {
+        Self {
+            factor_id,
+            qr_code: totp.qr_code,
+            secret: totp.secret,
+            uri: totp.uri,
+        }
+    }
+}
 
 /// Safe user response - NO tokens exposed
 #[derive(Serialize)]
@@ -0,0 +1,39 @@
+//! Assurance level extractor - Restrict a handler to sessions that passed a second factor
+
+use super::AuthenticatedUser;
+use crate::domain::Aal;
+use crate::error::{AppError, AuthError};
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops::Deref;
+use std::pin::Pin;
+
+/// An authenticated user whose session is at `aal2` - verify a TOTP code with
+/// `POST /user/mfa/verify` to step an `aal1` session up
+#[derive(Debug, Clone)]
+pub struct RequireAal2(AuthenticatedUser);
+
+impl Deref for RequireAal2 {
+    type Target = AuthenticatedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromRequest for RequireAal2 {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+        Box::pin(async move {
+            let user = user.await?;
+            if user.aal < Aal::Aal2 {
+                return Err(AuthError::MfaRequired.into());
+            }
+            Ok(Self(user))
+        })
+    }
+}
@@ -0,0 +1,133 @@
+//! Session extractors - Resolve the request's credentials to the logged-in user
+//!
+//! Accepted credentials, first match wins:
+//! - `Authorization: Bearer <session token>` - opaque token from a token-mode login
+//! - `Authorization: Bearer <Supabase JWT>` - access token obtained directly from Supabase
+//! - `session_id` cookie - browser login
+
+use super::app_state;
+use crate::domain::{Aal, Session, User, session_key};
+use crate::error::{AppError, AuthError};
+use crate::shared::constants::session::SESSION_COOKIE;
+use crate::shared::jwt::looks_like_jwt;
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use std::future::Future;
+use std::pin::Pin;
+use tracing::Span;
+
+/// Credential presented by the client
+enum Credential {
+    /// LAPP session ID, from the cookie or a bearer token
+    Session(String),
+    /// Supabase access token
+    Jwt(String),
+}
+
+/// The user behind a valid credential (session tokens refreshed if needed)
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user: User,
+    /// LAPP session, `None` when authenticated with a Supabase JWT
+    pub session: Option<Session>,
+    /// Assurance level of the session or JWT
+    pub aal: Aal,
+}
+
+impl AuthenticatedUser {
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// Session ID (digest of the token or cookie secret), `None` for Supabase JWT requests
+    pub fn session_id(&self) -> Option<&str> {
+        self.session.as_ref().map(|s| s.id.as_str())
+    }
+
+    /// Resolve the request's credential, recording the user on the request span
+    async fn resolve(req: HttpRequest) -> Result<Self, AppError> {
+        let app = app_state(&req)?;
+
+        let auth = match credential(&req).ok_or(AuthError::Unauthenticated)? {
+            Credential::Session(session_id) => {
+                let session = app
+                    .auth
+                    .current_session(&session_id)
+                    .await
+                    .ok_or(AuthError::Unauthenticated)?;
+                Self {
+                    user: session.user.clone(),
+                    aal: session.aal,
+                    session: Some(session),
+                }
+            }
+            Credential::Jwt(jwt) => {
+                let (user, aal) = app.auth.authenticate_jwt(&jwt).await?;
+                Self {
+                    user,
+                    session: None,
+                    aal,
+                }
+            }
+        };
+
+        Span::current().record("user_id", auth.user.id.as_str());
+        Ok(auth)
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    /// Already resolved by a middleware (`require_entitlement`) if it is in the extensions
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(auth) = req.extensions().get::<Self>().cloned() {
+            return Box::pin(async move { Ok(auth) });
+        }
+        Box::pin(Self::resolve(req.clone()))
+    }
+}
+
+/// The logged-in user if there is one - for endpoints that also serve anonymous visitors
+#[derive(Debug, Clone)]
+pub struct OptionalUser(pub Option<AuthenticatedUser>);
+
+impl FromRequest for OptionalUser {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { Ok(Self(AuthenticatedUser::resolve(req).await.ok())) })
+    }
+}
+
+/// LAPP session ID (`session_key` of a bearer token or the cookie), Supabase JWTs are not sessions
+pub fn session_token(req: &HttpRequest) -> Option<String> {
+    match credential(req)? {
+        Credential::Session(id) => Some(id),
+        Credential::Jwt(_) => None,
+    }
+}
+
+/// Credential from the Authorization header, falling back to the session cookie
+/// A malformed Authorization header is not silently replaced by the cookie
+fn credential(req: &HttpRequest) -> Option<Credential> {
+    if let Some(value) = req.headers().get(header::AUTHORIZATION) {
+        let token = value.to_str().ok()?.strip_prefix("Bearer ")?.trim();
+        if token.is_empty() {
+            return None;
+        }
+        return Some(if looks_like_jwt(token) {
+            Credential::Jwt(token.to_string())
+        } else {
+            Credential::Session(session_key(token))
+        });
+    }
+
+    req.cookie(SESSION_COOKIE)
+        .filter(|c| !c.value().is_empty())
+        .map(|c| Credential::Session(session_key(c.value())))
+}
@@ -0,0 +1,32 @@
+//! Custom Actix extractors - Authentication resolved before the handler runs
+//!
+//! - `AuthenticatedUser` - Valid session or bearer token required, 401 otherwise
+//! - `OptionalUser` - Session resolved if present, never rejects
+//! - `RequireRole<R>` - Valid session with role `R::NAME`, 403 otherwise
+//! - `RequirePermission<P>` - Valid session whose roles grant `P::PERMISSION`, 403 otherwise
+//! - `RequireAal2` - Valid session that passed a second factor, 403 otherwise
+
+mod aal;
+mod authenticated;
+mod permission;
+mod role;
+
+pub use aal::RequireAal2;
+pub use authenticated::{AuthenticatedUser, OptionalUser, session_token};
+pub use permission::{
+    CanManageEntitlements, CanManageRoles, CanManageUsers, CanReadAudit, CanReadUsers,
+    RequirePermission,
+};
+#[allow(unused_imports)]
+pub use role::{Admin, RequireRole, Role};
+
+use crate::app::App;
+use crate::error::AppError;
+use actix_web::{HttpRequest, web};
+
+/// Shared application state, an internal error if the server never registered it
+pub(crate) fn app_state(req: &HttpRequest) -> Result<web::Data<App>, AppError> {
+    req.app_data::<web::Data<App>>()
+        .cloned()
+        .ok_or(AppError::Internal("App data not registered"))
+}
@@ -0,0 +1,90 @@
+//! Permission extractor - Restrict a handler to users whose roles grant a permission
+
+use super::{AuthenticatedUser, app_state};
+use crate::domain::Permission;
+use crate::error::AppError;
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+
+/// A permission checked by `RequirePermission`
+pub trait Guarded {
+    const PERMISSION: Permission;
+}
+
+/// See other users' roles and account details
+#[derive(Debug, Clone, Copy)]
+pub struct CanReadUsers;
+
+impl Guarded for CanReadUsers {
+    const PERMISSION: Permission = Permission::UsersRead;
+}
+
+/// Edit, ban and delete accounts
+#[derive(Debug, Clone, Copy)]
+pub struct CanManageUsers;
+
+impl Guarded for CanManageUsers {
+    const PERMISSION: Permission = Permission::UsersManage;
+}
+
+/// Grant and revoke roles
+#[derive(Debug, Clone, Copy)]
+pub struct CanManageRoles;
+
+impl Guarded for CanManageRoles {
+    const PERMISSION: Permission = Permission::RolesManage;
+}
+
+/// Grant and revoke access to apps
+#[derive(Debug, Clone, Copy)]
+pub struct CanManageEntitlements;
+
+impl Guarded for CanManageEntitlements {
+    const PERMISSION: Permission = Permission::EntitlementsManage;
+}
+
+/// Search the security audit log
+#[derive(Debug, Clone, Copy)]
+pub struct CanReadAudit;
+
+impl Guarded for CanReadAudit {
+    const PERMISSION: Permission = Permission::AuditRead;
+}
+
+/// An authenticated user allowed `P::PERMISSION` - e.g. `RequirePermission<CanManageRoles>`
+#[derive(Debug, Clone)]
+pub struct RequirePermission<P: Guarded> {
+    user: AuthenticatedUser,
+    _permission: PhantomData<P>,
+}
+
+impl<P: Guarded> Deref for RequirePermission<P> {
+    type Target = AuthenticatedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+impl<P: Guarded + 'static> FromRequest for RequirePermission<P> {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+        let app = app_state(req);
+        Box::pin(async move {
+            let app = app?;
+            let user = user.await?;
+            app.authz.require(user.user(), P::PERMISSION)?;
+            Ok(Self {
+                user,
+                _permission: PhantomData,
+            })
+        })
+    }
+}
@@ -0,0 +1,69 @@
+//! Role extractor - Restrict a handler to users with a given role
+
+use super::{AuthenticatedUser, app_state};
+use crate::error::{AppError, AuthError};
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+
+/// A role name checked against the user's resolved roles (see `AuthzService::grants`)
+#[allow(dead_code)]
+pub trait Role {
+    const NAME: &'static str;
+}
+
+/// Administrators
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+/// An authenticated user whose role is `R` - e.g. `RequireRole<Admin>`
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct RequireRole<R: Role> {
+    user: AuthenticatedUser,
+    _role: PhantomData<R>,
+}
+
+#[allow(dead_code)]
+impl<R: Role> RequireRole<R> {
+    pub fn into_inner(self) -> AuthenticatedUser {
+        self.user
+    }
+}
+
+impl<R: Role> Deref for RequireRole<R> {
+    type Target = AuthenticatedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+impl<R: Role + 'static> FromRequest for RequireRole<R> {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+        let app = app_state(req);
+        Box::pin(async move {
+            let app = app?;
+            let user = user.await?;
+            if !app.authz.has_role(user.user(), R::NAME)? {
+                return Err(AuthError::InsufficientRole { required: R::NAME }.into());
+            }
+            Ok(Self {
+                user,
+                _role: PhantomData,
+            })
+        })
+    }
+}
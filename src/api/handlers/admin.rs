@@ -0,0 +1,302 @@
+//! Admin handlers - HTTP endpoints for accounts, roles, entitlements and the audit log
+
+use super::{client_info, validate_request};
+use crate::api::dto::{
+    AdminUserResponse, AuditEntryResponse, AuditQueryParams, BanRequest, EntitlementResponse,
+    GrantEntitlementRequest, UpdateMetadataRequest, UserListParams, UserListResponse,
+    UserRolesResponse,
+};
+use crate::api::extractors::{
+    CanManageEntitlements, CanManageRoles, CanManageUsers, CanReadAudit, CanReadUsers,
+    RequirePermission,
+};
+use crate::app::App;
+use crate::error::{AppError, AppResult};
+use crate::infrastructure::supabase::AdminUser;
+use actix_web::{HttpRequest, HttpResponse, delete, get, patch, put, web};
+
+// ============================================================================
+// ROUTE CONFIGURATION
+// ============================================================================
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .service(list_users_handler)
+            .service(get_user_handler)
+            .service(update_metadata_handler)
+            .service(ban_handler)
+            .service(unban_handler)
+            .service(delete_user_handler)
+            .service(list_roles_handler)
+            .service(grant_role_handler)
+            .service(revoke_role_handler)
+            .service(list_entitlements_handler)
+            .service(grant_entitlement_handler)
+            .service(revoke_entitlement_handler)
+            .service(audit_handler),
+    );
+}
+
+// ============================================================================
+// HANDLERS
+// ============================================================================
+
+/// GET /admin/users - One page of accounts (`page` from 1, `per_page` default 50)
+#[get("/users")]
+async fn list_users_handler(
+    app: web::Data<App>,
+    _auth: RequirePermission<CanReadUsers>,
+    query: web::Query<UserListParams>,
+) -> AppResult<HttpResponse> {
+    let params = query.into_inner();
+    validate_request(&params)?;
+
+    let list = app.users.list(params.page(), params.per_page()).await?;
+
+    Ok(HttpResponse::Ok().json(UserListResponse::new(
+        list,
+        params.page(),
+        params.per_page(),
+    )))
+}
+
+/// GET /admin/users/{id} - An account with its metadata and ban
+#[get("/users/{id}")]
+async fn get_user_handler(
+    app: web::Data<App>,
+    _auth: RequirePermission<CanReadUsers>,
+    path: web::Path<String>,
+) -> AppResult<HttpResponse> {
+    Ok(user_response(app.users.get(&path.into_inner()).await?))
+}
+
+/// PATCH /admin/users/{id}/metadata - Merge keys into `app_metadata` and/or `user_metadata`
+#[patch("/users/{id}/metadata")]
+async fn update_metadata_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: RequirePermission<CanManageUsers>,
+    path: web::Path<String>,
+    req: web::Json<UpdateMetadataRequest>,
+) -> AppResult<HttpResponse> {
+    if req.app_metadata.is_none() && req.user_metadata.is_none() {
+        return Err(AppError::validation("app_metadata", "Nothing to update"));
+    }
+    for (field, value) in [
+        ("app_metadata", &req.app_metadata),
+        ("user_metadata", &req.user_metadata),
+    ] {
+        if value.as_ref().is_some_and(|v| !v.is_object()) {
+            return Err(AppError::validation(field, "Must be a JSON object"));
+        }
+    }
+
+    let user = app
+        .users
+        .update_metadata(
+            &path.into_inner(),
+            req.app_metadata.as_ref(),
+            req.user_metadata.as_ref(),
+            &auth.user().id,
+            &client_info(&http),
+        )
+        .await?;
+
+    Ok(user_response(user))
+}
+
+/// PUT /admin/users/{id}/ban - Refuse the account's logins and end its sessions
+/// Without a body the ban is permanent, `{"hours": n}` lifts it after n hours
+#[put("/users/{id}/ban")]
+async fn ban_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: RequirePermission<CanManageUsers>,
+    path: web::Path<String>,
+    req: Option<web::Json<BanRequest>>,
+) -> AppResult<HttpResponse> {
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+    validate_request(&req)?;
+    let user_id = path.into_inner();
+
+    let user = app
+        .users
+        .ban(&user_id, req.hours(), &auth.user().id, &client_info(&http))
+        .await?;
+    if user.is_some() {
+        app.auth.end_sessions(&user_id, "banned").await;
+    }
+
+    Ok(user_response(user))
+}
+
+/// DELETE /admin/users/{id}/ban - Lift a ban
+#[delete("/users/{id}/ban")]
+async fn unban_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: RequirePermission<CanManageUsers>,
+    path: web::Path<String>,
+) -> AppResult<HttpResponse> {
+    let user = app
+        .users
+        .unban(&path.into_inner(), &auth.user().id, &client_info(&http))
+        .await?;
+
+    Ok(user_response(user))
+}
+
+/// DELETE /admin/users/{id} - Close an account like `DELETE /user/me` does, with either provider
+/// An unknown account still has its leftover LAPP data erased, finishing a failed closure
+#[delete("/users/{id}")]
+async fn delete_user_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: RequirePermission<CanManageUsers>,
+    path: web::Path<String>,
+) -> AppResult<HttpResponse> {
+    let user_id = path.into_inner();
+    let Some(email) = app.auth.account_email(&user_id).await? else {
+        app.erase_user_data(&user_id)?;
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    app.close_account(&user_id, &email, Some(&auth.user().id), client_info(&http))
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// GET /admin/users/{id}/roles - Roles granted to a user through this API
+#[get("/users/{id}/roles")]
+async fn list_roles_handler(
+    app: web::Data<App>,
+    _auth: RequirePermission<CanReadUsers>,
+    path: web::Path<String>,
+) -> AppResult<HttpResponse> {
+    let user_id = path.into_inner();
+    let roles = app.authz.granted_roles(&user_id)?;
+
+    Ok(HttpResponse::Ok().json(UserRolesResponse { user_id, roles }))
+}
+
+/// PUT /admin/users/{id}/roles/{role} - Grant a built-in role
+#[put("/users/{id}/roles/{role}")]
+async fn grant_role_handler(
+    app: web::Data<App>,
+    auth: RequirePermission<CanManageRoles>,
+    path: web::Path<(String, String)>,
+) -> AppResult<HttpResponse> {
+    let (user_id, role) = path.into_inner();
+    app.authz.grant(&user_id, &role, &auth.user().id)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// DELETE /admin/users/{id}/roles/{role} - Revoke a role granted through this API
+/// Roles from Supabase `app_metadata` are managed in Supabase
+#[delete("/users/{id}/roles/{role}")]
+async fn revoke_role_handler(
+    app: web::Data<App>,
+    _auth: RequirePermission<CanManageRoles>,
+    path: web::Path<(String, String)>,
+) -> AppResult<HttpResponse> {
+    let (user_id, role) = path.into_inner();
+
+    if app.authz.revoke(&user_id, &role)? {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// GET /admin/apps/{app_id}/entitlements - Who has, had or is trialing access to an app
+#[get("/apps/{app_id}/entitlements")]
+async fn list_entitlements_handler(
+    app: web::Data<App>,
+    _auth: RequirePermission<CanReadUsers>,
+    path: web::Path<String>,
+) -> AppResult<HttpResponse> {
+    let entitlements: Vec<EntitlementResponse> = app
+        .entitlements
+        .list(&path.into_inner())?
+        .iter()
+        .map(EntitlementResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(entitlements))
+}
+
+/// PUT /admin/apps/{app_id}/entitlements/{user_id} - Give a user access to an app
+/// Without a body access is permanent, `{"trial_days": n}` makes it a trial
+#[put("/apps/{app_id}/entitlements/{user_id}")]
+async fn grant_entitlement_handler(
+    app: web::Data<App>,
+    auth: RequirePermission<CanManageEntitlements>,
+    path: web::Path<(String, String)>,
+    req: Option<web::Json<GrantEntitlementRequest>>,
+) -> AppResult<HttpResponse> {
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+    validate_request(&req)?;
+    let (app_id, user_id) = path.into_inner();
+
+    let entitlement =
+        app.entitlements
+            .grant(&app_id, &user_id, req.trial_secs(), &auth.user().id)?;
+
+    Ok(HttpResponse::Ok().json(EntitlementResponse::from(&entitlement)))
+}
+
+/// DELETE /admin/apps/{app_id}/entitlements/{user_id} - Take away a user's access to an app
+#[delete("/apps/{app_id}/entitlements/{user_id}")]
+async fn revoke_entitlement_handler(
+    app: web::Data<App>,
+    auth: RequirePermission<CanManageEntitlements>,
+    path: web::Path<(String, String)>,
+) -> AppResult<HttpResponse> {
+    let (app_id, user_id) = path.into_inner();
+
+    if app
+        .entitlements
+        .revoke(&app_id, &user_id, &auth.user().id)?
+    {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// GET /admin/audit - Authentication events, newest first
+/// Filters: `user_id`, `email`, `since` / `until` (Unix timestamps), `limit` (default 100)
+#[get("/audit")]
+async fn audit_handler(
+    app: web::Data<App>,
+    _auth: RequirePermission<CanReadAudit>,
+    query: web::Query<AuditQueryParams>,
+) -> AppResult<HttpResponse> {
+    let params = query.into_inner();
+    validate_request(&params)?;
+
+    let entries: Vec<AuditEntryResponse> = app
+        .auth
+        .audit()
+        .query(&params.into())?
+        .into_iter()
+        .map(AuditEntryResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// The account, or 404 when there is none with the requested ID
+fn user_response(user: Option<AdminUser>) -> HttpResponse {
+    match user {
+        Some(user) => HttpResponse::Ok().json(AdminUserResponse::from(user)),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
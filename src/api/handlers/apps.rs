@@ -0,0 +1,65 @@
+//! App handlers - The app catalog and the routes of each hosted app
+//!
+//! Every app gets its own scope under `/apps/{app_id}`, wrapped in `require_entitlement`
+//! so none of its routes run for users without access to it.
+
+use crate::api::dto::{AppResponse, EntitlementResponse};
+use crate::api::extractors::OptionalUser;
+use crate::api::middleware::require_entitlement;
+use crate::app::App;
+use crate::apps::CollectionApp;
+use crate::domain::{AppModule, Entitlement};
+use crate::error::AppResult;
+use actix_web::middleware::from_fn;
+use actix_web::{HttpResponse, get, web};
+
+// ============================================================================
+// ROUTE CONFIGURATION
+// ============================================================================
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/apps").service(list_apps_handler).service(
+            web::scope(CollectionApp::ID)
+                .wrap(from_fn(|req, next| {
+                    require_entitlement(CollectionApp::ID, req, next)
+                }))
+                .service(collection_handler),
+        ),
+    );
+}
+
+// ============================================================================
+// HANDLERS
+// ============================================================================
+
+/// GET /apps - Apps hosted on the platform and the current user's access to each
+/// Anonymous visitors get the catalog without any `access`
+#[get("")]
+async fn list_apps_handler(app: web::Data<App>, auth: OptionalUser) -> AppResult<HttpResponse> {
+    let entitlements = match &auth.0 {
+        Some(auth) => app.entitlements.for_user(&auth.user().id)?,
+        None => Vec::new(),
+    };
+    let apps: Vec<AppResponse> = app
+        .entitlements
+        .apps()
+        .iter()
+        .map(|info| AppResponse::new(info, entitlements.iter().find(|e| e.app_id == info.id)))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(apps))
+}
+
+/// GET /apps/collection - Collection app entry point
+/// `require_entitlement` already checked the user's access and left it in the request
+#[get("")]
+async fn collection_handler(
+    app: web::Data<App>,
+    entitlement: web::ReqData<Entitlement>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(AppResponse {
+        access: Some(EntitlementResponse::from(&*entitlement)),
+        ..AppResponse::new(app.collection.info(), None)
+    })
+}
@@ -1,12 +1,26 @@
 //! Auth handlers - HTTP endpoints for authentication
 
-use crate::api::dto::{AuthResponse, LoginRequest, RegisterRequest};
+use super::{client_info, validate_request};
+use crate::api::dto::{
+    AuthMode, AuthResponse, ConfirmKind, ConfirmQuery, ForgotPasswordRequest, LoginRequest,
+    MfaRequiredResponse, MfaVerifyRequest, OAuthCallbackQuery, OtpRequest, PasskeyLoginRequest,
+    PasskeyOptionsResponse, RegisterRequest, ResendConfirmationRequest, ResetPasswordRequest,
+    SmsOtpRequest, VerifyOtpRequest, VerifySmsOtpRequest,
+};
+use crate::api::extractors::session_token;
 use crate::app::App;
 use crate::error::{AppError, AppResult};
+use crate::infrastructure::supabase::{OtpProof, OtpType};
+use crate::services::{LoginOutcome, Registration};
+use crate::shared::constants::auth::{
+    MFA_PENDING_TTL_SECS, MFA_TOKEN_COOKIE, MFA_VERIFY_PATH, OAUTH_FLOW_COOKIE, OAUTH_FLOW_TTL_SECS,
+};
+use crate::shared::constants::session::SESSION_COOKIE;
+use crate::shared::phone::normalize_e164;
 use actix_web::cookie::{Cookie, SameSite};
-use actix_web::{HttpResponse, post, web};
-use tracing::{info, instrument};
-use validator::Validate;
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse, get, post, web};
+use tracing::{info, instrument, warn};
 
 // ============================================================================
 // ROUTE CONFIGURATION
@@ -17,99 +31,222 @@ pub fn init(cfg: &mut web::ServiceConfig) {
         web::scope("/auth")
             .service(login_handler)
             .service(register_handler)
-            .service(logout_handler),
+            .service(logout_handler)
+            .service(otp_handler)
+            .service(verify_otp_handler)
+            .service(sms_otp_handler)
+            .service(verify_sms_otp_handler)
+            .service(mfa_verify_handler)
+            .service(passkey_options_handler)
+            .service(passkey_login_handler)
+            .service(resend_confirmation_handler)
+            .service(confirm_handler)
+            .service(oauth_handler)
+            .service(oauth_callback_handler)
+            .service(forgot_password_handler)
+            .service(reset_password_handler),
     );
 }
 
-// ============================================================================
-// VALIDATION HELPER
-// ============================================================================
-
-fn validate_request<T: Validate>(req: &T) -> AppResult<()> {
-    if let Err(errors) = req.validate() {
-        for (field, field_errors) in errors.field_errors() {
-            if let Some(err) = field_errors.first() {
-                let message = err
-                    .message
-                    .as_ref()
-                    .map(|m| m.to_string())
-                    .unwrap_or_else(|| "Validation failed".to_string());
-
-                let static_field: &'static str = match field {
-                    "email" => "email",
-                    "password" => "password",
-                    "username" => "username",
-                    _ => "unknown",
-                };
-
-                return Err(AppError::validation(static_field, message));
-            }
-        }
-    }
-    Ok(())
-}
-
 // ============================================================================
 // HANDLERS
 // ============================================================================
 
 /// POST /auth/login
 #[post("/login")]
-#[instrument(skip(app, req), fields(email = %req.email))]
+#[instrument(skip(app, http, req), fields(email = %req.email))]
 async fn login_handler(
     app: web::Data<App>,
+    http: HttpRequest,
     req: web::Json<LoginRequest>,
 ) -> AppResult<HttpResponse> {
     validate_request(&req.0)?;
 
-    let user = app.auth.login(&req.email, &req.password).await?;
-    let session_id = app.auth.sessions().create_session(user.clone());
+    let outcome = app
+        .auth
+        .login(&req.email, &req.password, client_info(&http))
+        .await?;
+
+    Ok(login_response(&app, outcome, req.mode))
+}
 
-    let session_cookie = Cookie::build("session_id", session_id.clone())
-        .http_only(true)
-        .secure(app.config.secure_http.parse().unwrap()) // Set true in production with HTTPS via .env
-        .same_site(SameSite::Lax)
-        .path("/")
+/// POST /auth/otp - email a magic link / sign-in code
+/// Same response whether or not the email is registered
+#[post("/otp")]
+#[instrument(skip(app, req))]
+async fn otp_handler(app: web::Data<App>, req: web::Json<OtpRequest>) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+
+    app.auth.send_login_otp(&req.email, req.create_user).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "If this email can sign in, a link and code have been sent"
+    })))
+}
+
+/// POST /auth/verify-otp - log in with the code from the email
+#[post("/verify-otp")]
+#[instrument(skip(app, http, req))]
+async fn verify_otp_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    req: web::Json<VerifyOtpRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+
+    let proof = OtpProof::EmailCode {
+        email: &req.email,
+        code: &req.code,
+    };
+    let outcome = app
+        .auth
+        .login_with_otp(OtpType::Email, proof, client_info(&http))
+        .await?;
+
+    Ok(login_response(&app, outcome, req.mode))
+}
+
+/// POST /auth/mfa/verify - second step of a login that answered `mfa_required`
+#[post("/mfa/verify")]
+#[instrument(skip(app, http, req))]
+async fn mfa_verify_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    req: web::Json<MfaVerifyRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+
+    let mfa_token = req
+        .mfa_token
+        .clone()
+        .or_else(|| http.cookie(MFA_TOKEN_COOKIE).map(|c| c.value().to_string()))
+        .ok_or_else(|| AppError::validation("mfa_token", "MFA token required"))?;
+    let session = app
+        .auth
+        .complete_mfa_login(&mfa_token, req.factor_id.as_deref(), &req.code)
+        .await?;
+
+    let mut response = login_response(&app, LoginOutcome::Session(Box::new(session)), req.mode);
+    let mfa_cookie = Cookie::build(MFA_TOKEN_COOKIE, "")
+        .path(MFA_VERIFY_PATH)
         .finish();
+    // Only fails on a malformed cookie, which this one is not
+    let _ = response.add_removal_cookie(&mfa_cookie);
+    Ok(response)
+}
+
+/// POST /auth/passkey/options - challenge for `navigator.credentials.get()`
+#[post("/passkey/options")]
+async fn passkey_options_handler(app: web::Data<App>) -> HttpResponse {
+    let ceremony = app.passkeys.start_login();
+    HttpResponse::Ok().json(PasskeyOptionsResponse::request(
+        app.passkeys.relying_party(),
+        ceremony,
+    ))
+}
+
+/// POST /auth/passkey/login - log in with the signed challenge
+#[post("/passkey/login")]
+#[instrument(skip(app, http, req))]
+async fn passkey_login_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    req: web::Json<PasskeyLoginRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+    let assertion = req.decode()?;
 
-    let response = AuthResponse::from_user(&user);
+    let user_id = app
+        .passkeys
+        .finish_login(&req.ceremony_id, assertion.as_assertion())?;
+    let outcome = app
+        .auth
+        .login_with_passkey(&user_id, client_info(&http))
+        .await?;
 
-    info!(session_id = %session_id, "Login successful");
-    Ok(HttpResponse::Ok().cookie(session_cookie).json(response))
+    Ok(login_response(&app, outcome, req.mode))
+}
+
+/// POST /auth/sms-otp - text a sign-in code to a verified phone number
+/// Same response whether or not the number is registered
+#[post("/sms-otp")]
+#[instrument(skip(app, req))]
+async fn sms_otp_handler(
+    app: web::Data<App>,
+    req: web::Json<SmsOtpRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+    let phone = normalize_e164(&req.phone).map_err(|e| AppError::validation("phone", e))?;
+
+    app.auth.send_sms_otp(&phone).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "If this number can sign in, a code has been sent"
+    })))
+}
+
+/// POST /auth/verify-sms-otp - log in with the code from the text message
+#[post("/verify-sms-otp")]
+#[instrument(skip(app, http, req))]
+async fn verify_sms_otp_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    req: web::Json<VerifySmsOtpRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+    let phone = normalize_e164(&req.phone).map_err(|e| AppError::validation("phone", e))?;
+
+    let proof = OtpProof::PhoneCode {
+        phone: &phone,
+        code: &req.code,
+    };
+    let outcome = app
+        .auth
+        .login_with_otp(OtpType::Sms, proof, client_info(&http))
+        .await?;
+
+    Ok(login_response(&app, outcome, req.mode))
 }
 
 /// POST /auth/register
 #[post("/register")]
-#[instrument(skip(app, req), fields(email = %req.email, username = %req.username))]
+#[instrument(skip(app, http, req), fields(email = %req.email, username = %req.username))]
 async fn register_handler(
     app: web::Data<App>,
+    http: HttpRequest,
     req: web::Json<RegisterRequest>,
 ) -> AppResult<HttpResponse> {
     validate_request(&req.0)?;
+    let phone = req
+        .phone()
+        .map_err(|e| AppError::validation("phone_number", e))?;
 
-    let user = app
+    let registration = app
         .auth
         .register(
             &req.email,
             &req.password,
             &req.username,
-            req.phone_country_code.as_deref(),
-            req.phone_number.as_deref(),
+            phone.as_ref(),
+            client_info(&http),
         )
         .await?;
 
-    let session_id = app.auth.sessions().create_session(user.clone());
-
-    let session_cookie = Cookie::build("session_id", session_id.clone())
-        .http_only(true)
-        .secure(app.config.secure_http.parse().unwrap())
-        .same_site(SameSite::Lax)
-        .path("/")
-        .finish();
-
-    let response = AuthResponse::from_user(&user);
+    let session = match registration {
+        Registration::Session(session) => *session,
+        // No session yet: the account is activated from the emailed link
+        Registration::PendingConfirmation { email } => {
+            info!("Registration pending email confirmation");
+            return Ok(HttpResponse::Accepted().json(serde_json::json!({
+                "message": "Check your email to confirm your account",
+                "email": email,
+            })));
+        }
+    };
+    let session_cookie = build_session_cookie(&app, &session.secret);
+    let response = AuthResponse::from_user(&session.user);
 
-    info!(session_id = %session_id, "Registration successful");
+    info!(device_id = %session.device_id, "Registration successful");
     Ok(HttpResponse::Created()
         .cookie(session_cookie)
         .json(response))
@@ -118,16 +255,16 @@ async fn register_handler(
 /// POST /auth/logout
 #[post("/logout")]
 #[instrument(skip(app, req))]
-async fn logout_handler(app: web::Data<App>, req: actix_web::HttpRequest) -> HttpResponse {
-    let session_id = extract_session_id(&req);
+async fn logout_handler(app: web::Data<App>, req: HttpRequest) -> HttpResponse {
+    let session_id = session_token(&req);
 
     if let Some(ref sid) = session_id {
-        app.auth.logout(sid).await;
+        app.auth.logout(sid, client_info(&req)).await;
     } else {
         info!("Logout called without session cookie");
     }
 
-    let session_cookie = Cookie::build("session_id", "")
+    let session_cookie = Cookie::build(SESSION_COOKIE, "")
         .http_only(true)
         .path("/")
         .max_age(actix_web::cookie::time::Duration::ZERO)
@@ -138,14 +275,251 @@ async fn logout_handler(app: web::Data<App>, req: actix_web::HttpRequest) -> Htt
         .json(serde_json::json!({"message": "Logged out successfully"}))
 }
 
+/// POST /auth/resend-confirmation
+/// Same response whether or not the email is registered
+#[post("/resend-confirmation")]
+#[instrument(skip(app, req))]
+async fn resend_confirmation_handler(
+    app: web::Data<App>,
+    req: web::Json<ResendConfirmationRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+
+    app.auth.resend_confirmation(&req.email).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "If this email is awaiting confirmation, a new link has been sent"
+    })))
+}
+
+/// GET /auth/confirm?token_hash=...&type=email - target of confirmation and magic links
+/// Logs the user in; redirects to `EMAIL_CONFIRM_REDIRECT` when configured, JSON otherwise
+#[get("/confirm")]
+#[instrument(skip(app, http, query))]
+async fn confirm_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    query: web::Query<ConfirmQuery>,
+) -> AppResult<HttpResponse> {
+    let kind = match query.kind {
+        ConfirmKind::Signup => OtpType::Signup,
+        ConfirmKind::Magiclink => OtpType::Magiclink,
+        ConfirmKind::Email => OtpType::Email,
+    };
+    let confirmed = app
+        .auth
+        .login_with_otp(
+            kind,
+            OtpProof::TokenHash(&query.token_hash),
+            client_info(&http),
+        )
+        .await;
+
+    browser_login_response(&app, confirmed, &app.config.email_confirm_redirect)
+}
+
+/// GET /auth/oauth/{provider} - redirect to the provider through Supabase (PKCE)
+#[get("/oauth/{provider}")]
+#[instrument(skip(app, http))]
+async fn oauth_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    provider: web::Path<String>,
+) -> AppResult<HttpResponse> {
+    let callback_url = match app.config.oauth_callback_url.as_str() {
+        "" => {
+            let conn = http.connection_info();
+            format!("{}://{}/auth/callback", conn.scheme(), conn.host())
+        }
+        url => url.to_string(),
+    };
+
+    let (flow_id, authorize_url) = app.auth.start_oauth(&provider, &callback_url)?;
+
+    // Ties the callback to this browser: a callback link replayed elsewhere has no flow
+    let flow_cookie = Cookie::build(OAUTH_FLOW_COOKIE, flow_id)
+        .http_only(true)
+        .secure(app.config.secure_http.parse().unwrap())
+        .same_site(SameSite::Lax)
+        .path("/auth")
+        .max_age(actix_web::cookie::time::Duration::seconds(
+            OAUTH_FLOW_TTL_SECS as i64,
+        ))
+        .finish();
+
+    Ok(HttpResponse::Found()
+        .cookie(flow_cookie)
+        .insert_header((header::LOCATION, authorize_url))
+        .finish())
+}
+
+/// GET /auth/callback?code=... - where Supabase sends the browser back after OAuth
+/// Logs the user in; redirects to `OAUTH_REDIRECT` when configured, JSON otherwise
+#[get("/callback")]
+#[instrument(skip(app, http, query))]
+async fn oauth_callback_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    query: web::Query<OAuthCallbackQuery>,
+) -> AppResult<HttpResponse> {
+    if let Some(error) = &query.error {
+        warn!(
+            error = %error,
+            description = query.error_description.as_deref().unwrap_or_default(),
+            "OAuth provider returned an error"
+        );
+    }
+
+    let flow_id = http
+        .cookie(OAUTH_FLOW_COOKIE)
+        .map(|c| c.value().to_string());
+    let signed_in = app
+        .auth
+        .finish_oauth(
+            flow_id.as_deref(),
+            query.code.as_deref(),
+            client_info(&http),
+        )
+        .await;
+
+    let mut response = browser_login_response(&app, signed_in, &app.config.oauth_redirect)?;
+    let flow_cookie = Cookie::build(OAUTH_FLOW_COOKIE, "").path("/auth").finish();
+    // Only fails on a malformed cookie, which this one is not
+    let _ = response.add_removal_cookie(&flow_cookie);
+    Ok(response)
+}
+
+/// POST /auth/forgot-password
+/// Same response whether or not the email is registered
+#[post("/forgot-password")]
+#[instrument(skip(app, req))]
+async fn forgot_password_handler(
+    app: web::Data<App>,
+    req: web::Json<ForgotPasswordRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+
+    app.auth.forgot_password(&req.email).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "If an account exists for this email, a password reset link has been sent"
+    })))
+}
+
+/// POST /auth/reset-password
+#[post("/reset-password")]
+#[instrument(skip(app, req))]
+async fn reset_password_handler(
+    app: web::Data<App>,
+    req: web::Json<ResetPasswordRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+
+    let proof = match (&req.token_hash, &req.email, &req.code) {
+        (Some(hash), _, _) => OtpProof::TokenHash(hash),
+        (None, Some(email), Some(code)) => OtpProof::EmailCode { email, code },
+        _ => {
+            return Err(AppError::validation(
+                "token_hash",
+                "Either token_hash or email and code are required",
+            ));
+        }
+    };
+
+    app.auth.reset_password(proof, &req.password).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Password has been reset, please log in again"
+    })))
+}
+
 // ============================================================================
 // HELPERS
 // ============================================================================
 
-fn extract_session_id(req: &actix_web::HttpRequest) -> Option<String> {
-    let cookies = req.cookies().ok()?;
-    cookies
-        .iter()
-        .find(|c| c.name() == "session_id")
-        .map(|c| c.value().to_string())
+/// Finish a login reached by following a link (email, OAuth callback) in a browser
+/// With a `redirect` URL the browser is sent back to the app, with `?error=CODE` on failure
+/// and `?mfa=required` when a TOTP code is needed; without one the result is a JSON response
+/// like the other endpoints
+fn browser_login_response(
+    app: &App,
+    result: AppResult<LoginOutcome>,
+    redirect: &str,
+) -> AppResult<HttpResponse> {
+    let redirect = Some(redirect).filter(|r| !r.is_empty());
+    match (result, redirect) {
+        (Ok(LoginOutcome::Session(session)), Some(url)) => Ok(HttpResponse::SeeOther()
+            .cookie(build_session_cookie(app, &session.secret))
+            .insert_header((header::LOCATION, url))
+            .finish()),
+        // The app asks for the TOTP code and posts it to /auth/mfa/verify, which gets the
+        // token from this cookie: kept out of the URL, where history and Referer would leak it
+        (Ok(LoginOutcome::MfaRequired { mfa_token, .. }), Some(url)) => {
+            let mfa_cookie = Cookie::build(MFA_TOKEN_COOKIE, mfa_token)
+                .http_only(true)
+                .secure(app.config.secure_http.parse().unwrap())
+                .same_site(SameSite::Strict)
+                .path(MFA_VERIFY_PATH)
+                .max_age(actix_web::cookie::time::Duration::seconds(
+                    MFA_PENDING_TTL_SECS as i64,
+                ))
+                .finish();
+            let location = format!(
+                "{}{}mfa=required",
+                url,
+                if url.contains('?') { '&' } else { '?' }
+            );
+            Ok(HttpResponse::SeeOther()
+                .cookie(mfa_cookie)
+                .insert_header((header::LOCATION, location))
+                .finish())
+        }
+        (Ok(outcome), None) => Ok(login_response(app, outcome, AuthMode::Cookie)),
+        // Send the browser to the app with the reason, not a JSON error
+        (Err(e), Some(url)) => {
+            warn!(error = %e, "Browser login failed");
+            let location = format!(
+                "{}{}error={}",
+                url,
+                if url.contains('?') { '&' } else { '?' },
+                e.code().as_str()
+            );
+            Ok(HttpResponse::SeeOther()
+                .insert_header((header::LOCATION, location))
+                .finish())
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Hand a new session to the client: bearer token in the body (token mode) or cookie
+/// Accounts with MFA get the `mfa_required` challenge instead, and no session yet
+fn login_response(app: &App, outcome: LoginOutcome, mode: AuthMode) -> HttpResponse {
+    let session = match outcome {
+        LoginOutcome::Session(session) => session,
+        LoginOutcome::MfaRequired { mfa_token, factors } => {
+            info!("Login pending second factor");
+            return HttpResponse::Ok().json(MfaRequiredResponse::new(mfa_token, &factors));
+        }
+    };
+    info!(device_id = %session.device_id, aal = %session.aal, "Login successful");
+    let response = AuthResponse::from_user(&session.user);
+
+    match mode {
+        // Non-browser clients send the token back as `Authorization: Bearer`, no cookie involved
+        AuthMode::Token => HttpResponse::Ok().json(response.with_token(session.secret.clone())),
+        AuthMode::Cookie => HttpResponse::Ok()
+            .cookie(build_session_cookie(app, &session.secret))
+            .json(response),
+    }
+}
+
+/// HttpOnly cookie carrying the session secret
+fn build_session_cookie(app: &App, secret: &str) -> Cookie<'static> {
+    Cookie::build(SESSION_COOKIE, secret.to_string())
+        .http_only(true)
+        .secure(app.config.secure_http.parse().unwrap()) // Set true in production with HTTPS via .env
+        .same_site(SameSite::Lax)
+        .path("/")
+        .finish()
 }
@@ -1,12 +1,80 @@
 //! HTTP handlers - Route handlers organized by feature
 
+pub mod admin;
+pub mod apps;
 pub mod auth;
 pub mod user;
 
-use actix_web::web;
+use crate::domain::ClientInfo;
+use crate::error::{AppError, AppResult};
+use actix_web::http::header;
+use actix_web::{HttpRequest, web};
+use validator::Validate;
 
 /// Initialize all API routes
 pub fn init(cfg: &mut web::ServiceConfig) {
     auth::init(cfg);
     user::init(cfg);
+    apps::init(cfg);
+    admin::init(cfg);
+}
+
+// ============================================================================
+// VALIDATION HELPER
+// ============================================================================
+
+fn validate_request<T: Validate>(req: &T) -> AppResult<()> {
+    if let Err(errors) = req.validate() {
+        for (field, field_errors) in errors.field_errors() {
+            if let Some(err) = field_errors.first() {
+                let message = err
+                    .message
+                    .as_ref()
+                    .map(|m| m.to_string())
+                    .unwrap_or_else(|| "Validation failed".to_string());
+
+                let static_field: &'static str = match field {
+                    "email" => "email",
+                    "password" => "password",
+                    "current_password" => "current_password",
+                    "new_password" => "new_password",
+                    "username" => "username",
+                    "token_hash" => "token_hash",
+                    "code" => "code",
+                    "phone" => "phone",
+                    "mfa_token" => "mfa_token",
+                    "factor_id" => "factor_id",
+                    "friendly_name" => "friendly_name",
+                    "ceremony_id" => "ceremony_id",
+                    "name" => "name",
+                    "trial_days" => "trial_days",
+                    "user_id" => "user_id",
+                    "limit" => "limit",
+                    "page" => "page",
+                    "per_page" => "per_page",
+                    "hours" => "hours",
+                    _ => "unknown",
+                };
+
+                return Err(AppError::validation(static_field, message));
+            }
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// CLIENT INFO
+// ============================================================================
+
+/// Peer IP (not forwarded headers, which clients can spoof) and user agent
+fn client_info(req: &HttpRequest) -> ClientInfo {
+    ClientInfo {
+        ip: req.peer_addr().map(|addr| addr.ip().to_string()),
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from),
+    }
 }
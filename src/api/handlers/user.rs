@@ -1,15 +1,48 @@
 //! User handlers - HTTP endpoints for user operations
 
-use crate::api::dto::UserResponse;
+use super::{client_info, validate_request};
+use crate::api::dto::{
+    ChangeEmailRequest, ChangePasswordRequest, ConfirmPhoneRequest, EnrollTotpRequest,
+    FactorResponse, GrantsResponse, PasskeyOptionsResponse, PasskeyRegisterRequest,
+    PasskeyResponse, PhoneVerificationRequest, ReauthRequest, SessionResponse,
+    TotpEnrollmentResponse, UserResponse, VerifyTotpRequest,
+};
+use crate::api::extractors::{AuthenticatedUser, RequireAal2};
 use crate::app::App;
-use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use crate::domain::{AuditAction, AuditEntry, AuditOutcome, ClientInfo};
+use crate::error::{AppError, AppResult};
+use crate::shared::constants::auth::REAUTH_MAX_AGE_SECS;
+use crate::shared::constants::session::SESSION_COOKIE;
+use crate::shared::phone::normalize_e164;
+use crate::shared::time::now_secs;
+use actix_web::cookie::Cookie;
+use actix_web::{HttpRequest, HttpResponse, Responder, delete, get, post, put, web};
 
 // ============================================================================
 // ROUTE CONFIGURATION
 // ============================================================================
 
 pub fn init(cfg: &mut web::ServiceConfig) {
-    cfg.service(web::scope("/user").service(me_handler));
+    cfg.service(
+        web::scope("/user")
+            .service(me_handler)
+            .service(delete_account_handler)
+            .service(permissions_handler)
+            .service(list_sessions_handler)
+            .service(revoke_session_handler)
+            .service(change_password_handler)
+            .service(change_email_handler)
+            .service(verify_phone_handler)
+            .service(confirm_phone_handler)
+            .service(list_factors_handler)
+            .service(enroll_totp_handler)
+            .service(verify_totp_handler)
+            .service(unenroll_factor_handler)
+            .service(list_passkeys_handler)
+            .service(passkey_options_handler)
+            .service(register_passkey_handler)
+            .service(remove_passkey_handler),
+    );
 }
 
 // ============================================================================
@@ -18,26 +51,398 @@ pub fn init(cfg: &mut web::ServiceConfig) {
 
 /// GET /user/me - Get current user from session
 #[get("/me")]
-async fn me_handler(app: web::Data<App>, req: HttpRequest) -> impl Responder {
-    let session_id = match extract_session_id(&req) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().finish(),
-    };
-
-    match app.auth.sessions().get_user(&session_id) {
-        Some(user) => HttpResponse::Ok().json(UserResponse::from(&user)),
-        None => HttpResponse::Unauthorized().finish(),
+async fn me_handler(auth: AuthenticatedUser) -> impl Responder {
+    HttpResponse::Ok().json(UserResponse::from(auth.user()))
+}
+
+/// DELETE /user/me - Close the account: the account and all its sessions are deleted, then
+/// every app erases the user's data
+#[delete("/me")]
+async fn delete_account_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: AuthenticatedUser,
+    req: web::Json<ReauthRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+    let user = auth.user();
+    let client = client_info(&http);
+
+    reauthenticate(&app, &auth, &req, AuditAction::AccountDeleted, &client).await?;
+    app.close_account(&user.id, &user.email, None, client)
+        .await?;
+
+    let session_cookie = Cookie::build(SESSION_COOKIE, "")
+        .http_only(true)
+        .path("/")
+        .max_age(actix_web::cookie::time::Duration::ZERO)
+        .finish();
+
+    Ok(HttpResponse::NoContent().cookie(session_cookie).finish())
+}
+
+/// Check the fresh proof `action` asks for: whichever of the password, emailed code or passkey
+/// was sent, none from a session that just passed a second factor
+/// A passkey registered by the current session is no proof: it may be the attacker's own
+async fn reauthenticate(
+    app: &App,
+    auth: &AuthenticatedUser,
+    req: &ReauthRequest,
+    action: AuditAction,
+    client: &ClientInfo,
+) -> AppResult<()> {
+    let user = auth.user();
+
+    if let Some(password) = &req.password {
+        if !app
+            .auth
+            .check_password(user, password, action, client)
+            .await?
+        {
+            return Err(AppError::validation("password", "Password is incorrect"));
+        }
+    } else if let Some(code) = &req.code {
+        if !app
+            .auth
+            .check_email_code(user, code, action, client)
+            .await?
+        {
+            return Err(AppError::validation("code", "Code is incorrect or expired"));
+        }
+    } else if let Some(passkey) = &req.passkey {
+        let assertion = passkey.credential.decode()?;
+        let proof = assertion.as_assertion();
+        if !app
+            .passkeys
+            .confirm(&user.id, device_id(auth), &passkey.ceremony_id, proof)?
+        {
+            app.auth.audit().record(
+                AuditEntry::new(action, AuditOutcome::Failure, client, now_secs())
+                    .user(&user.id)
+                    .email(&user.email)
+                    .detail("passkey_rejected"),
+            );
+            return Err(AppError::validation("passkey", "Passkey was not accepted"));
+        }
+    } else {
+        let fresh = auth
+            .session
+            .as_ref()
+            .is_some_and(|s| s.recently_verified_aal2(REAUTH_MAX_AGE_SECS, now_secs()));
+        if !fresh {
+            return Err(AppError::validation(
+                "password",
+                "Password, emailed code or passkey required",
+            ));
+        }
     }
+    Ok(())
 }
 
-// ============================================================================
-// HELPERS
-// ============================================================================
+/// Device ID of the caller's session, `None` for bearer tokens
+fn device_id(auth: &AuthenticatedUser) -> Option<&str> {
+    auth.session.as_ref().map(|s| s.device_id.as_str())
+}
+
+/// GET /user/permissions - Roles of the current user and what they allow
+#[get("/permissions")]
+async fn permissions_handler(
+    app: web::Data<App>,
+    auth: AuthenticatedUser,
+) -> AppResult<HttpResponse> {
+    let grants = app.authz.grants(auth.user())?;
+    Ok(HttpResponse::Ok().json(GrantsResponse::from(grants)))
+}
+
+/// GET /user/sessions - List every device the current user is logged in on
+#[get("/sessions")]
+async fn list_sessions_handler(app: web::Data<App>, auth: AuthenticatedUser) -> impl Responder {
+    let sessions: Vec<SessionResponse> = app
+        .auth
+        .sessions()
+        .list_user_sessions(&auth.user().id)
+        .iter()
+        .map(|s| SessionResponse::from_session(s, auth.session_id().unwrap_or_default()))
+        .collect();
+
+    HttpResponse::Ok().json(sessions)
+}
+
+/// DELETE /user/sessions/{id} - Log out one of the current user's devices
+#[delete("/sessions/{id}")]
+async fn revoke_session_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: AuthenticatedUser,
+    path: web::Path<String>,
+) -> impl Responder {
+    if app
+        .auth
+        .revoke_session(&auth.user().id, &path.into_inner(), client_info(&http))
+        .await
+    {
+        HttpResponse::NoContent().finish()
+    } else {
+        HttpResponse::NotFound().finish()
+    }
+}
+
+/// PUT /user/password - Set a new password, logging out every other device
+#[put("/password")]
+async fn change_password_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: AuthenticatedUser,
+    req: web::Json<ChangePasswordRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+
+    let revoked = app
+        .auth
+        .change_password(
+            auth.session_id(),
+            auth.user(),
+            &req.current_password,
+            &req.new_password,
+            client_info(&http),
+        )
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Password changed",
+        "revoked_sessions": revoked,
+    })))
+}
+
+/// PUT /user/email - Move to a new email address once the link sent to it is followed
+/// Needs the same fresh proof as closing the account
+#[put("/email")]
+async fn change_email_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: AuthenticatedUser,
+    req: web::Json<ChangeEmailRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+    let client = client_info(&http);
+
+    app.auth.require_supabase("Email change")?;
+    reauthenticate(&app, &auth, &req.reauth, AuditAction::EmailChange, &client).await?;
+    app.auth
+        .change_email(auth.user(), &req.email, client)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Confirmation email sent to the new address",
+    })))
+}
+
+/// POST /user/phone - text a verification code to the user's phone number
+/// Without a body, the number given at registration is used
+#[post("/phone")]
+async fn verify_phone_handler(
+    app: web::Data<App>,
+    auth: AuthenticatedUser,
+    req: Option<web::Json<PhoneVerificationRequest>>,
+) -> AppResult<HttpResponse> {
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+    validate_request(&req)?;
+    let phone = req
+        .phone
+        .as_deref()
+        .map(normalize_e164)
+        .transpose()
+        .map_err(|e| AppError::validation("phone", e))?;
+
+    let phone = app
+        .auth
+        .start_phone_verification(auth.user(), phone)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Verification code sent",
+        "phone": phone,
+    })))
+}
+
+/// POST /user/phone/verify - confirm the number with the texted code, enabling SMS login
+#[post("/phone/verify")]
+async fn confirm_phone_handler(
+    app: web::Data<App>,
+    auth: AuthenticatedUser,
+    req: web::Json<ConfirmPhoneRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+    let phone = normalize_e164(&req.phone).map_err(|e| AppError::validation("phone", e))?;
+
+    app.auth
+        .confirm_phone(auth.session_id(), auth.user(), &phone, &req.code)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Phone number verified",
+        "phone": phone,
+    })))
+}
+
+/// GET /user/mfa - Verified factors and the assurance level of the current session
+#[get("/mfa")]
+async fn list_factors_handler(
+    app: web::Data<App>,
+    auth: AuthenticatedUser,
+) -> AppResult<HttpResponse> {
+    let factors = app.auth.list_factors(auth.user()).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "aal": auth.aal.as_str(),
+        "factors": factors.iter().map(FactorResponse::from).collect::<Vec<_>>(),
+    })))
+}
+
+/// POST /user/mfa/enroll - Start enrolling a TOTP factor
+/// The factor is only active once confirmed with a first code on /user/mfa/verify
+#[post("/mfa/enroll")]
+async fn enroll_totp_handler(
+    app: web::Data<App>,
+    auth: AuthenticatedUser,
+    req: Option<web::Json<EnrollTotpRequest>>,
+) -> AppResult<HttpResponse> {
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+    validate_request(&req)?;
+
+    let (factor_id, totp) = app
+        .auth
+        .enroll_totp(auth.user(), req.friendly_name.as_deref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(TotpEnrollmentResponse::new(factor_id, totp)))
+}
+
+/// POST /user/mfa/verify - Check a TOTP code: confirms an enrollment and raises the session to aal2
+#[post("/mfa/verify")]
+async fn verify_totp_handler(
+    app: web::Data<App>,
+    auth: AuthenticatedUser,
+    req: web::Json<VerifyTotpRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+
+    app.auth
+        .verify_totp(auth.session_id(), auth.user(), &req.factor_id, &req.code)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Code verified",
+        "aal": "aal2",
+    })))
+}
 
-fn extract_session_id(req: &HttpRequest) -> Option<String> {
-    let cookies = req.cookies().ok()?;
-    cookies
+/// DELETE /user/mfa/{factor_id} - Remove a factor (requires an aal2 session)
+#[delete("/mfa/{factor_id}")]
+async fn unenroll_factor_handler(
+    app: web::Data<App>,
+    auth: RequireAal2,
+    path: web::Path<String>,
+) -> AppResult<HttpResponse> {
+    app.auth
+        .unenroll_factor(auth.user(), &path.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// GET /user/passkeys - Passkeys registered by the current user
+#[get("/passkeys")]
+async fn list_passkeys_handler(
+    app: web::Data<App>,
+    auth: AuthenticatedUser,
+) -> AppResult<HttpResponse> {
+    let passkeys: Vec<PasskeyResponse> = app
+        .passkeys
+        .list(&auth.user().id)?
         .iter()
-        .find(|c| c.name() == "session_id")
-        .map(|c| c.value().to_string())
+        .map(PasskeyResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(passkeys))
+}
+
+/// POST /user/passkeys/options - Challenge for `navigator.credentials.create()`
+/// Needs the same fresh proof as closing the account; only this session may finish the ceremony
+#[post("/passkeys/options")]
+async fn passkey_options_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: AuthenticatedUser,
+    req: Option<web::Json<ReauthRequest>>,
+) -> AppResult<HttpResponse> {
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+    validate_request(&req)?;
+
+    reauthenticate(
+        &app,
+        &auth,
+        &req,
+        AuditAction::PasskeyAdded,
+        &client_info(&http),
+    )
+    .await?;
+    let (ceremony, existing) = app
+        .passkeys
+        .start_registration(auth.user(), device_id(&auth))?;
+
+    Ok(HttpResponse::Ok().json(PasskeyOptionsResponse::creation(
+        app.passkeys.relying_party(),
+        auth.user(),
+        ceremony,
+        &existing,
+    )))
+}
+
+/// POST /user/passkeys - Register the credential created by the authenticator
+#[post("/passkeys")]
+async fn register_passkey_handler(
+    app: web::Data<App>,
+    http: HttpRequest,
+    auth: AuthenticatedUser,
+    req: web::Json<PasskeyRegisterRequest>,
+) -> AppResult<HttpResponse> {
+    validate_request(&req.0)?;
+    let attestation = req.decode()?;
+    let user = auth.user();
+
+    let passkey = app.passkeys.finish_registration(
+        &req.ceremony_id,
+        user,
+        device_id(&auth),
+        req.name.as_deref().unwrap_or("Passkey"),
+        attestation.as_attestation(),
+    )?;
+    app.auth.audit().record(
+        AuditEntry::new(
+            AuditAction::PasskeyAdded,
+            AuditOutcome::Success,
+            &client_info(&http),
+            now_secs(),
+        )
+        .user(&user.id)
+        .email(&user.email)
+        .detail(&passkey.id),
+    );
+
+    Ok(HttpResponse::Created().json(PasskeyResponse::from(&passkey)))
+}
+
+/// DELETE /user/passkeys/{id} - Remove one of the current user's passkeys
+#[delete("/passkeys/{id}")]
+async fn remove_passkey_handler(
+    app: web::Data<App>,
+    auth: AuthenticatedUser,
+    path: web::Path<String>,
+) -> AppResult<HttpResponse> {
+    if app.passkeys.remove(&auth.user().id, &path.into_inner())? {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
 }
@@ -0,0 +1,36 @@
+//! Entitlement guard - An app's routes only run for users entitled to the app
+
+use crate::api::extractors::{AuthenticatedUser, app_state};
+use crate::domain::AppId;
+use crate::error::AppError;
+use actix_web::HttpMessage;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+
+/// Wrap an app's scope: `.wrap(from_fn(|req, next| require_entitlement(ID, req, next)))`
+/// 401 without a valid session, 403 `APP_ACCESS_DENIED` / `APP_TRIAL_EXPIRED` without access
+///
+/// The user and their `Entitlement` are left in the request extensions: handlers take
+/// `AuthenticatedUser` and `web::ReqData<Entitlement>` without authenticating again
+pub async fn require_entitlement(
+    app_id: AppId,
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    if let Err(e) = check(app_id, &mut req).await {
+        return Ok(req.error_response(e).map_into_right_body());
+    }
+    Ok(next.call(req).await?.map_into_left_body())
+}
+
+async fn check(app_id: AppId, req: &mut ServiceRequest) -> Result<(), AppError> {
+    let auth = req.extract::<AuthenticatedUser>().await?;
+    let app = app_state(req.request())?;
+    let entitlement = app.entitlements.check(&auth.user().id, app_id)?;
+
+    let mut extensions = req.extensions_mut();
+    extensions.insert(auth);
+    extensions.insert(entitlement);
+    Ok(())
+}
@@ -0,0 +1,7 @@
+//! Custom middleware
+
+mod entitlement;
+mod request_span;
+
+pub use entitlement::require_entitlement;
+pub use request_span::request_span;
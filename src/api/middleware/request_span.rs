@@ -0,0 +1,20 @@
+//! Request span - One tracing span per request, tagged with the user once authenticated
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use tracing::{Instrument, field, info_span};
+
+/// Wrap the request in a span; extractors fill in `user_id` when they resolve a session
+pub async fn request_span(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let span = info_span!(
+        "request",
+        method = %req.method(),
+        path = %req.path(),
+        user_id = field::Empty,
+    );
+    next.call(req).instrument(span).await
+}
@@ -6,10 +6,12 @@
 //! # Structure
 //! - `handlers/` - Route handlers organized by feature
 //! - `dto/` - Request/Response data transfer objects
-//! - `extractors/` - Custom Actix extractors (future)
-//! - `middleware/` - Custom middleware (future)
+//! - `extractors/` - Custom Actix extractors (authentication)
+//! - `middleware/` - Custom middleware (request tracing)
 
 pub mod dto;
+pub mod extractors;
 pub mod handlers;
+pub mod middleware;
 
 pub use handlers::init;
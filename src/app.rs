@@ -2,9 +2,14 @@
 
 use crate::apps::CollectionApp;
 use crate::config::Config;
-use crate::domain::{AppModule, SessionStore};
-use crate::services::AuthService;
-use tracing::info;
+use crate::domain::{AppModule, ClientInfo, SessionPolicy, SessionStore, WriteBehind};
+use crate::error::AppResult;
+use crate::infrastructure::backend_from_config;
+use crate::services::{
+    AuthService, AuthzService, EntitlementService, PasskeyService, UserAdminService,
+};
+use std::time::Duration;
+use tracing::{error, info};
 
 /// Main application struct - holds all services and shared state
 #[derive(Debug, Clone)]
@@ -13,6 +18,10 @@ pub struct App {
     pub version: String,
     pub config: Config,
     pub auth: AuthService,
+    pub passkeys: PasskeyService,
+    pub authz: AuthzService,
+    pub entitlements: EntitlementService,
+    pub users: UserAdminService,
     // Apps
     pub collection: CollectionApp,
 }
@@ -20,12 +29,38 @@ pub struct App {
 impl App {
     pub fn new() -> Self {
         let cfg = Config::from_env();
-        let sessions = SessionStore::new();
+        let sessions = SessionStore::new(
+            SessionPolicy {
+                idle_ttl: cfg.session_idle_ttl,
+                absolute_ttl: cfg.session_absolute_ttl,
+                limit: cfg.session_limit,
+            },
+            backend_from_config(&cfg),
+            WriteBehind {
+                flush_interval: Duration::from_secs(cfg.session_flush_interval.max(1)),
+                batch_size: cfg.session_flush_batch.max(1),
+                compact_after: cfg.session_compact_after.max(1),
+            },
+        );
         let auth = AuthService::new(&cfg, sessions);
+        let passkeys = PasskeyService::from_config(&cfg).unwrap_or_else(|e| {
+            panic!("Failed to open passkey database {}: {}", cfg.passkey_db, e)
+        });
+        let authz = AuthzService::from_config(&cfg)
+            .unwrap_or_else(|e| panic!("Failed to open role database {}: {}", cfg.roles_db, e));
+        let users = UserAdminService::from_config(&cfg, auth.audit().clone());
         let collection = CollectionApp::new();
+        let entitlements = EntitlementService::from_config(&cfg, vec![collection.info().clone()])
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Failed to open entitlement database {}: {}",
+                    cfg.entitlements_db, e
+                )
+            });
 
         info!(
             collection_app = %collection.name(),
+            collection_id = %collection.id(),
             "Apps initialized"
         );
 
@@ -34,9 +69,51 @@ impl App {
             version: "0.1.0".to_string(),
             config: cfg,
             auth,
+            passkeys,
+            authz,
+            entitlements,
+            users,
             collection,
         }
     }
+
+    /// Every app hosted on the platform
+    pub fn modules(&self) -> Vec<&dyn AppModule> {
+        vec![&self.collection]
+    }
+
+    /// Close an account, by its owner or by the admin `deleted_by`: the account and its
+    /// sessions go first, so nobody can sign in and recreate data while it is erased, then
+    /// `erase_user_data`. If erasing fails, `DELETE /admin/users/{id}` finishes the job.
+    pub async fn close_account(
+        &self,
+        user_id: &str,
+        email: &str,
+        deleted_by: Option<&str>,
+        client: ClientInfo,
+    ) -> AppResult<()> {
+        self.auth
+            .delete_account(user_id, email, deleted_by, client)
+            .await?;
+        if let Err(e) = self.erase_user_data(user_id) {
+            error!(error = %e, "Account deleted but its LAPP data remains");
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Erase what LAPP and its apps hold about a user whose account was deleted: app data,
+    /// then passkeys, entitlements, roles. Nothing happens if there is nothing left.
+    pub fn erase_user_data(&self, user_id: &str) -> AppResult<()> {
+        for module in self.modules() {
+            module.delete_user_data(user_id)?;
+            info!(app = module.id(), "App data of closed account erased");
+        }
+        self.passkeys.forget_user(user_id)?;
+        self.entitlements.forget_user(user_id)?;
+        self.authz.forget_user(user_id)?;
+        Ok(())
+    }
 }
 
 impl Default for App {
@@ -1,10 +1,11 @@
 //! CollectionApp - App instance for managing collections
 
-use crate::domain::{AppInstance, AppModule};
+use crate::domain::{AppId, AppInstance, AppModule};
+use crate::error::AppResult;
 
 /// Collection app metadata
 const COLLECTION_APP: AppInstance = AppInstance::new(
-    "collection",
+    CollectionApp::ID,
     "Collection",
     "Create and manage collections of items, bookmarks, and resources",
 );
@@ -16,6 +17,9 @@ pub struct CollectionApp {
 }
 
 impl CollectionApp {
+    /// Routes live under `/apps/collection`
+    pub const ID: AppId = "collection";
+
     pub fn new() -> Self {
         Self {
             info: COLLECTION_APP,
@@ -33,4 +37,9 @@ impl AppModule for CollectionApp {
     fn info(&self) -> &AppInstance {
         &self.info
     }
+
+    /// Collections are not stored yet, nothing to erase
+    fn delete_user_data(&self, _user_id: &str) -> AppResult<()> {
+        Ok(())
+    }
 }
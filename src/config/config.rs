@@ -1,8 +1,25 @@
 //! Application configuration - Environment variables
 
+use crate::domain::SessionLimit;
+use crate::infrastructure::{AuthProviderKind, SessionBackendKind};
+use crate::shared::constants::apps::DEFAULT_ENTITLEMENTS_DB;
+use crate::shared::constants::audit::DEFAULT_AUDIT_LOG;
+use crate::shared::constants::auth::{
+    DEFAULT_JWT_AUDIENCE, DEFAULT_JWT_ROLES, DEFAULT_LOCAL_AUTH_DB, DEFAULT_LOGIN_ACCOUNT_ATTEMPTS,
+    DEFAULT_LOGIN_DELAY_AFTER, DEFAULT_LOGIN_IP_ATTEMPTS, DEFAULT_LOGIN_LOCKOUT_SECS,
+    DEFAULT_LOGIN_THROTTLE_DB, DEFAULT_LOGIN_WINDOW_SECS, DEFAULT_OAUTH_PROVIDERS,
+    DEFAULT_PASSKEY_DB, DEFAULT_ROLES_DB, DEFAULT_WEBAUTHN_RP_ID, DEFAULT_WEBAUTHN_RP_NAME,
+};
+use crate::shared::constants::session::{
+    DEFAULT_ABSOLUTE_TTL_SECS, DEFAULT_COMPACT_AFTER_OPS, DEFAULT_FLUSH_BATCH_SIZE,
+    DEFAULT_FLUSH_INTERVAL_SECS, DEFAULT_IDLE_TTL_SECS, DEFAULT_MAX_SESSIONS_PER_USER,
+    DEFAULT_REAP_INTERVAL_SECS, DEFAULT_REFRESH_INTERVAL_SECS, DEFAULT_REFRESH_MARGIN_SECS,
+    DEFAULT_SESSION_DB, DEFAULT_SESSION_FILE,
+};
 use dotenv::dotenv;
 use std::env;
 use std::fmt;
+use std::str::FromStr;
 use tracing::info;
 
 /// Application configuration loaded from environment
@@ -10,13 +27,66 @@ use tracing::info;
 pub struct Config {
     pub ip: String,
     pub port: String,
+    // Who checks passwords: Supabase (SP_* required) or the local user database
+    pub auth_provider: AuthProviderKind,
+    pub local_auth_db: String,
     #[allow(dead_code)]
     pub sp_id: String,
     pub sp_url: String,
     pub sp_anon: String,
-    #[allow(dead_code)]
+    // Admin API only (`SupabaseAdmin`), never sent along with calls made for a user
     pub sp_service_role: String,
     pub secure_http: String,
+    // Local JWT verification (optional): HS256 secret, JWKS file/URL, accepted aud and roles
+    pub sp_jwt_secret: String,
+    pub sp_jwks: String,
+    pub sp_jwt_audience: String,
+    pub sp_jwt_roles: String,
+    // Where recovery emails link to (optional, must be allowed in Supabase redirect URLs)
+    pub password_reset_redirect: String,
+    // Where the email confirmation callback sends the browser (optional)
+    pub email_confirm_redirect: String,
+    // OAuth: enabled providers, public URL of `/auth/callback` (derived from the request when
+    // empty) and where the callback sends the browser (optional)
+    pub oauth_providers: String,
+    pub oauth_callback_url: String,
+    pub oauth_redirect: String,
+    // Passkeys: relying party (domain the credentials are bound to), allowed origins
+    // (comma-separated, default `https://<rp id>`) and credential storage
+    pub webauthn_rp_id: String,
+    pub webauthn_rp_name: String,
+    pub webauthn_origins: String,
+    pub passkey_db: String,
+    // Roles granted through the admin API (Supabase `app_metadata` roles need no storage)
+    pub roles_db: String,
+    // Which users may use which app
+    pub entitlements_db: String,
+    // Login throttling: failures counted per account and per IP over a window (seconds),
+    // growing delays then a lockout; state kept across restarts
+    pub login_window: u64,
+    pub login_account_attempts: u32,
+    pub login_ip_attempts: u32,
+    pub login_lockout: u64,
+    pub login_delay_after: u32,
+    pub login_throttle_db: String,
+    // Append-only JSONL log of authentication events
+    pub audit_log: String,
+    // Sessions (seconds)
+    pub session_idle_ttl: u64,
+    pub session_absolute_ttl: u64,
+    pub session_reap_interval: u64,
+    pub session_refresh_margin: u64,
+    pub session_refresh_interval: u64,
+    pub session_limit: SessionLimit,
+    pub session_backend: SessionBackendKind,
+    pub session_file: String,
+    pub session_db: String,
+    pub session_flush_interval: u64,
+    pub session_flush_batch: usize,
+    pub session_compact_after: usize,
+    // Token encryption at rest: `id:base64key,...` and the ID used for new writes
+    pub session_encryption_keys: String,
+    pub session_encryption_key_id: String,
 }
 
 impl Config {
@@ -25,27 +95,114 @@ impl Config {
     pub fn from_env() -> Self {
         dotenv().ok();
 
+        let auth_provider = Self::env_or("AUTH_PROVIDER", AuthProviderKind::Supabase);
+        // Supabase settings are only required when Supabase is the provider
+        let supabase = |key: &str| match auth_provider {
+            AuthProviderKind::Supabase => {
+                env::var(key).unwrap_or_else(|_| panic!("{} {}", key, Self::DEF_ERR))
+            }
+            AuthProviderKind::Local => Self::env_or(key, String::new()),
+        };
+
         let config = Self {
             ip: env::var("IP").unwrap_or_else(|_| panic!("IP {}", Self::DEF_ERR)),
             port: env::var("PORT").unwrap_or_else(|_| panic!("PORT {}", Self::DEF_ERR)),
-            sp_id: env::var("SP_ID").unwrap_or_else(|_| panic!("SP_ID {}", Self::DEF_ERR)),
-            sp_url: env::var("SP_URL").unwrap_or_else(|_| panic!("SP_URL {}", Self::DEF_ERR)),
-            sp_anon: env::var("SP_ANON").unwrap_or_else(|_| panic!("SP_ANON {}", Self::DEF_ERR)),
-            sp_service_role: env::var("SP_SERVICE_ROLE")
-                .unwrap_or_else(|_| panic!("SP_SERVICE_ROLE {}", Self::DEF_ERR)),
+            auth_provider,
+            local_auth_db: Self::env_or("LOCAL_AUTH_DB", DEFAULT_LOCAL_AUTH_DB.to_string()),
+            sp_id: supabase("SP_ID"),
+            sp_url: supabase("SP_URL"),
+            sp_anon: supabase("SP_ANON"),
+            sp_service_role: supabase("SP_SERVICE_ROLE"),
             secure_http: env::var("SECURE_HTTP")
                 .unwrap_or_else(|_| panic!("SECURE_HTTP {}", Self::DEF_ERR)),
+            sp_jwt_secret: Self::env_or("SP_JWT_SECRET", String::new()),
+            sp_jwks: Self::env_or("SP_JWKS", String::new()),
+            sp_jwt_audience: Self::env_or("SP_JWT_AUDIENCE", DEFAULT_JWT_AUDIENCE.to_string()),
+            sp_jwt_roles: Self::env_or("SP_JWT_ROLES", DEFAULT_JWT_ROLES.to_string()),
+            password_reset_redirect: Self::env_or("PASSWORD_RESET_REDIRECT", String::new()),
+            email_confirm_redirect: Self::env_or("EMAIL_CONFIRM_REDIRECT", String::new()),
+            oauth_providers: Self::env_or("OAUTH_PROVIDERS", DEFAULT_OAUTH_PROVIDERS.to_string()),
+            oauth_callback_url: Self::env_or("OAUTH_CALLBACK_URL", String::new()),
+            oauth_redirect: Self::env_or("OAUTH_REDIRECT", String::new()),
+            webauthn_rp_id: Self::env_or("WEBAUTHN_RP_ID", DEFAULT_WEBAUTHN_RP_ID.to_string()),
+            webauthn_rp_name: Self::env_or(
+                "WEBAUTHN_RP_NAME",
+                DEFAULT_WEBAUTHN_RP_NAME.to_string(),
+            ),
+            webauthn_origins: Self::env_or("WEBAUTHN_ORIGINS", String::new()),
+            passkey_db: Self::env_or("PASSKEY_DB", DEFAULT_PASSKEY_DB.to_string()),
+            roles_db: Self::env_or("ROLES_DB", DEFAULT_ROLES_DB.to_string()),
+            entitlements_db: Self::env_or("ENTITLEMENTS_DB", DEFAULT_ENTITLEMENTS_DB.to_string()),
+            login_window: Self::env_or("LOGIN_WINDOW", DEFAULT_LOGIN_WINDOW_SECS),
+            login_account_attempts: Self::env_or(
+                "LOGIN_ACCOUNT_ATTEMPTS",
+                DEFAULT_LOGIN_ACCOUNT_ATTEMPTS,
+            ),
+            login_ip_attempts: Self::env_or("LOGIN_IP_ATTEMPTS", DEFAULT_LOGIN_IP_ATTEMPTS),
+            login_lockout: Self::env_or("LOGIN_LOCKOUT", DEFAULT_LOGIN_LOCKOUT_SECS),
+            login_delay_after: Self::env_or("LOGIN_DELAY_AFTER", DEFAULT_LOGIN_DELAY_AFTER),
+            login_throttle_db: Self::env_or(
+                "LOGIN_THROTTLE_DB",
+                DEFAULT_LOGIN_THROTTLE_DB.to_string(),
+            ),
+            audit_log: Self::env_or("AUDIT_LOG", DEFAULT_AUDIT_LOG.to_string()),
+            session_idle_ttl: Self::env_or("SESSION_IDLE_TTL", DEFAULT_IDLE_TTL_SECS),
+            session_absolute_ttl: Self::env_or("SESSION_ABSOLUTE_TTL", DEFAULT_ABSOLUTE_TTL_SECS),
+            session_reap_interval: Self::env_or(
+                "SESSION_REAP_INTERVAL",
+                DEFAULT_REAP_INTERVAL_SECS,
+            ),
+            session_refresh_margin: Self::env_or(
+                "SESSION_REFRESH_MARGIN",
+                DEFAULT_REFRESH_MARGIN_SECS,
+            ),
+            session_refresh_interval: Self::env_or(
+                "SESSION_REFRESH_INTERVAL",
+                DEFAULT_REFRESH_INTERVAL_SECS,
+            ),
+            session_limit: Self::env_or(
+                "SESSION_LIMIT",
+                SessionLimit::MostRecent(DEFAULT_MAX_SESSIONS_PER_USER),
+            ),
+            session_backend: Self::env_or("SESSION_BACKEND", SessionBackendKind::File),
+            session_file: Self::env_or("SESSION_FILE", DEFAULT_SESSION_FILE.to_string()),
+            session_db: Self::env_or("SESSION_DB", DEFAULT_SESSION_DB.to_string()),
+            session_flush_interval: Self::env_or(
+                "SESSION_FLUSH_INTERVAL",
+                DEFAULT_FLUSH_INTERVAL_SECS,
+            ),
+            session_flush_batch: Self::env_or("SESSION_FLUSH_BATCH", DEFAULT_FLUSH_BATCH_SIZE),
+            session_compact_after: Self::env_or("SESSION_COMPACT_AFTER", DEFAULT_COMPACT_AFTER_OPS),
+            session_encryption_keys: Self::env_or("SESSION_ENCRYPTION_KEYS", String::new()),
+            session_encryption_key_id: Self::env_or("SESSION_ENCRYPTION_KEY_ID", String::new()),
         };
 
         info!(
             ip = %config.ip,
             port = %config.port,
+            auth_provider = ?config.auth_provider,
             supabase_url = %config.sp_url,
+            session_idle_ttl = config.session_idle_ttl,
+            session_absolute_ttl = config.session_absolute_ttl,
+            session_limit = ?config.session_limit,
+            session_backend = ?config.session_backend,
+            session_encryption = !config.session_encryption_keys.is_empty(),
             "Configuration loaded"
         );
 
         config
     }
+
+    /// Optional variable parsed as `T`, falling back to `default` when unset or empty
+    fn env_or<T: FromStr>(key: &str, default: T) -> T {
+        match env::var(key) {
+            Ok(value) if !value.trim().is_empty() => value
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("{} has an invalid value: {}", key, value)),
+            _ => default,
+        }
+    }
 }
 
 impl fmt::Display for Config {
@@ -1,5 +1,7 @@
 //! AppInstance - Base representation for all apps in the platform
 
+use crate::error::AppResult;
+
 /// Unique identifier for an app
 pub type AppId = &'static str;
 
@@ -14,7 +16,11 @@ pub struct AppInstance {
 impl AppInstance {
     /// Create a new app instance
     pub const fn new(id: AppId, name: &'static str, description: &'static str) -> Self {
-        Self { id, name, description }
+        Self {
+            id,
+            name,
+            description,
+        }
     }
 }
 
@@ -22,14 +28,19 @@ impl AppInstance {
 pub trait AppModule: Send + Sync {
     /// Get app metadata
     fn info(&self) -> &AppInstance;
-    
+
     /// Get the app ID
     fn id(&self) -> AppId {
         self.info().id
     }
-    
+
     /// Get the app name
     fn name(&self) -> &'static str {
         self.info().name
     }
+
+    /// Erase everything the app stores about a user whose account is being closed
+    /// Called once the account itself is deleted, and again when a failed erase is retried:
+    /// must succeed when there is nothing to erase
+    fn delete_user_data(&self, user_id: &str) -> AppResult<()>;
 }
@@ -0,0 +1,174 @@
+//! Audit domain entity - Security-relevant authentication events
+
+use super::{ClientInfo, UserId};
+use crate::error::StorageError;
+use std::fmt;
+use std::str::FromStr;
+
+/// What happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Login,
+    Register,
+    Logout,
+    SessionRevoked,
+    TokenRefresh,
+    PasswordChange,
+    EmailChange,
+    AccountDeleted,
+    /// Tokens issued by LAPP on the user's behalf (passkey login), no password involved
+    ServiceSignIn,
+    PasskeyAdded,
+    AdminUpdate,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Login => "login",
+            Self::Register => "register",
+            Self::Logout => "logout",
+            Self::SessionRevoked => "session_revoked",
+            Self::TokenRefresh => "token_refresh",
+            Self::PasswordChange => "password_change",
+            Self::EmailChange => "email_change",
+            Self::AccountDeleted => "account_deleted",
+            Self::ServiceSignIn => "service_sign_in",
+            Self::PasskeyAdded => "passkey_added",
+            Self::AdminUpdate => "admin_update",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "login" => Ok(Self::Login),
+            "register" => Ok(Self::Register),
+            "logout" => Ok(Self::Logout),
+            "session_revoked" => Ok(Self::SessionRevoked),
+            "token_refresh" => Ok(Self::TokenRefresh),
+            "password_change" => Ok(Self::PasswordChange),
+            "email_change" => Ok(Self::EmailChange),
+            "account_deleted" => Ok(Self::AccountDeleted),
+            "service_sign_in" => Ok(Self::ServiceSignIn),
+            "passkey_added" => Ok(Self::PasskeyAdded),
+            "admin_update" => Ok(Self::AdminUpdate),
+            other => Err(format!("unknown audit action: {}", other)),
+        }
+    }
+}
+
+/// Whether it worked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+        }
+    }
+}
+
+impl FromStr for AuditOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "success" => Ok(Self::Success),
+            "failure" => Ok(Self::Failure),
+            other => Err(format!("unknown audit outcome: {}", other)),
+        }
+    }
+}
+
+/// One audit log entry - never updated or deleted once written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub at: u64,
+    pub action: AuditAction,
+    pub outcome: AuditOutcome,
+    /// `None` when the user is unknown (e.g. a wrong password)
+    pub user_id: Option<UserId>,
+    /// Email the attempt was made with, for events before the user is known
+    pub email: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// Login method, reason of a failure... (`password`, `account_locked`)
+    pub detail: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction, outcome: AuditOutcome, client: &ClientInfo, at: u64) -> Self {
+        Self {
+            at,
+            action,
+            outcome,
+            user_id: None,
+            email: None,
+            ip: client.ip.clone(),
+            user_agent: client.user_agent.clone(),
+            detail: None,
+        }
+    }
+
+    pub fn user(mut self, user_id: &str) -> Self {
+        self.user_id = Some(user_id.to_string());
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = Some(email.trim().to_lowercase());
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Audit log search - every filter is optional, times are inclusive
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub user_id: Option<String>,
+    pub email: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Most entries returned, newest first
+    pub limit: usize,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.user_id
+            .as_ref()
+            .is_none_or(|id| entry.user_id.as_ref() == Some(id))
+            && self.email.as_ref().is_none_or(|email| {
+                entry.email.as_deref() == Some(email.trim().to_lowercase().as_str())
+            })
+            && self.since.is_none_or(|since| entry.at >= since)
+            && self.until.is_none_or(|until| entry.at <= until)
+    }
+}
+
+/// Persistence port for the audit log - append-only, implementations live in infrastructure
+pub trait AuditLog: Send + Sync + fmt::Debug {
+    fn append(&self, entry: &AuditEntry) -> Result<(), StorageError>;
+
+    /// Entries matching `query`, newest first
+    fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, StorageError>;
+}
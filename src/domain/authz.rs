@@ -0,0 +1,98 @@
+//! Authorization - Application roles and the permissions they grant
+//!
+//! A user's roles are the union of their Supabase role claim (`authenticated` for everyone
+//! signed in), the roles listed in their Supabase `app_metadata`, and roles granted locally.
+
+use super::UserId;
+use crate::error::StorageError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Something a user may be allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Permission {
+    /// See other users' roles and account details
+    UsersRead,
+    /// Edit, ban and delete accounts
+    UsersManage,
+    /// Grant and revoke roles
+    RolesManage,
+    /// Grant and revoke access to apps
+    EntitlementsManage,
+    /// Search the security audit log
+    AuditRead,
+}
+
+impl Permission {
+    pub const ALL: &'static [Permission] = &[
+        Self::UsersRead,
+        Self::UsersManage,
+        Self::RolesManage,
+        Self::EntitlementsManage,
+        Self::AuditRead,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UsersRead => "users:read",
+            Self::UsersManage => "users:manage",
+            Self::RolesManage => "roles:manage",
+            Self::EntitlementsManage => "entitlements:manage",
+            Self::AuditRead => "audit:read",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|p| p.as_str() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown permission: {}", s))
+    }
+}
+
+/// Built-in roles: name and what it grants
+/// Any other role name (e.g. `authenticated`) grants nothing by itself
+pub const ROLES: &[(&str, &[Permission])] = &[
+    ("admin", Permission::ALL),
+    ("support", &[Permission::UsersRead]),
+];
+
+/// Permissions granted by a role, empty for roles that are not built in
+pub fn role_permissions(role: &str) -> &'static [Permission] {
+    ROLES
+        .iter()
+        .find(|(name, _)| *name == role)
+        .map(|(_, permissions)| *permissions)
+        .unwrap_or_default()
+}
+
+/// Persistence port for locally granted roles - implementations live in infrastructure
+pub trait RoleStore: Send + Sync + fmt::Debug {
+    /// Roles granted to a user, sorted
+    fn roles(&self, user_id: &str) -> Result<Vec<String>, StorageError>;
+
+    /// Grant a role, `false` if the user already had it
+    fn grant(
+        &self,
+        user_id: &UserId,
+        role: &str,
+        granted_by: &UserId,
+    ) -> Result<bool, StorageError>;
+
+    /// Revoke a role, `false` if the user did not have it
+    fn revoke(&self, user_id: &str, role: &str) -> Result<bool, StorageError>;
+
+    /// Revoke every role of a user, returns how many there were
+    fn revoke_all(&self, user_id: &str) -> Result<usize, StorageError>;
+}
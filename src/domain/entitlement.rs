@@ -0,0 +1,88 @@
+//! Entitlement domain entity - Which users may use which app
+
+use super::{AppId, UserId};
+use crate::error::StorageError;
+use std::fmt;
+use std::str::FromStr;
+
+/// State of a user's access to an app
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntitlementStatus {
+    /// Access until revoked
+    Granted,
+    /// Access until `expires_at`
+    Trial,
+    /// No access, kept to show who revoked it and when
+    Revoked,
+}
+
+impl EntitlementStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Granted => "granted",
+            Self::Trial => "trial",
+            Self::Revoked => "revoked",
+        }
+    }
+}
+
+impl fmt::Display for EntitlementStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EntitlementStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "granted" => Ok(Self::Granted),
+            "trial" => Ok(Self::Trial),
+            "revoked" => Ok(Self::Revoked),
+            other => Err(format!("unknown entitlement status: {}", other)),
+        }
+    }
+}
+
+/// A user's access to an app - one per (app, user)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entitlement {
+    /// `AppId` of the app
+    pub app_id: String,
+    pub user_id: UserId,
+    pub status: EntitlementStatus,
+    /// End of a trial, `None` otherwise
+    pub expires_at: Option<u64>,
+    /// Admin who made the last change
+    pub updated_by: UserId,
+    pub updated_at: u64,
+}
+
+impl Entitlement {
+    /// Whether the user may use the app at `now`
+    pub fn is_active(&self, now: u64) -> bool {
+        match self.status {
+            EntitlementStatus::Granted => true,
+            EntitlementStatus::Trial => self.expires_at.is_some_and(|exp| now < exp),
+            EntitlementStatus::Revoked => false,
+        }
+    }
+}
+
+/// Persistence port for entitlements - implementations live in infrastructure
+pub trait EntitlementStore: Send + Sync + fmt::Debug {
+    fn get(&self, app_id: AppId, user_id: &str) -> Result<Option<Entitlement>, StorageError>;
+
+    /// Entitlements to an app, most recently changed first
+    fn list_app(&self, app_id: AppId) -> Result<Vec<Entitlement>, StorageError>;
+
+    /// Entitlements of a user to any app
+    fn list_user(&self, user_id: &str) -> Result<Vec<Entitlement>, StorageError>;
+
+    /// Insert or replace the entitlement of `(app_id, user_id)`
+    fn put(&self, entitlement: &Entitlement) -> Result<(), StorageError>;
+
+    /// Delete every entitlement of a user, returns how many there were
+    fn remove_user(&self, user_id: &str) -> Result<usize, StorageError>;
+}
@@ -4,9 +4,24 @@
 //! These types should be framework-agnostic and contain no HTTP, database, or external service logic.
 
 mod app_instance;
+mod audit;
+mod authz;
+mod entitlement;
+mod passkey;
+mod provider;
 mod session;
+mod throttle;
 mod user;
 
 pub use app_instance::{AppId, AppInstance, AppModule};
-pub use session::SessionStore;
+pub use audit::{AuditAction, AuditEntry, AuditLog, AuditOutcome, AuditQuery};
+pub use authz::{Permission, ROLES, RoleStore, role_permissions};
+pub use entitlement::{Entitlement, EntitlementStatus, EntitlementStore};
+pub use passkey::{Passkey, PasskeyBackend};
+pub use provider::{AuthProvider, Factor, SignIn, SignupOutcome};
+pub use session::{
+    Aal, ClientInfo, Session, SessionBackend, SessionId, SessionLimit, SessionOp, SessionPolicy,
+    SessionStore, WriteBehind, session_key,
+};
+pub use throttle::{AttemptStore, Reserved, ThrottleKey, ThrottlePolicy};
 pub use user::{User, UserId};
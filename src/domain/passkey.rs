@@ -0,0 +1,43 @@
+//! Passkey domain entity - WebAuthn credentials registered by users
+
+use super::UserId;
+use crate::error::StorageError;
+use std::fmt;
+
+/// Public half of a credential kept by the user's authenticator
+#[derive(Debug, Clone)]
+pub struct Passkey {
+    /// Credential ID, base64url
+    pub id: String,
+    pub user_id: UserId,
+    /// Label chosen by the user ("MacBook", "YubiKey")
+    pub name: String,
+    /// SEC1 uncompressed P-256 point
+    pub public_key: Vec<u8>,
+    /// Last signature counter seen, 0 for authenticators without one
+    pub sign_count: u32,
+    pub created_at: u64,
+    pub last_used_at: Option<u64>,
+    /// Device ID of the session that registered it, which may not use it to re-authenticate
+    pub registered_by: Option<String>,
+}
+
+/// Persistence port for passkeys - implementations live in infrastructure
+pub trait PasskeyBackend: Send + Sync + fmt::Debug {
+    /// Store a new passkey; fails if the credential ID is already registered
+    fn insert(&self, passkey: &Passkey) -> Result<(), StorageError>;
+
+    fn get(&self, id: &str) -> Result<Option<Passkey>, StorageError>;
+
+    /// Passkeys of a user, oldest first
+    fn list(&self, user_id: &str) -> Result<Vec<Passkey>, StorageError>;
+
+    /// Record a login: new signature counter and time of use
+    fn touch(&self, id: &str, sign_count: u32, used_at: u64) -> Result<(), StorageError>;
+
+    /// Delete one of the user's passkeys, `false` if they have none with this ID
+    fn remove(&self, user_id: &str, id: &str) -> Result<bool, StorageError>;
+
+    /// Delete every passkey of a user, returns how many there were
+    fn remove_all(&self, user_id: &str) -> Result<usize, StorageError>;
+}
@@ -0,0 +1,73 @@
+//! Auth provider port - Where credentials are checked and tokens issued
+
+use super::{User, UserId};
+use crate::error::AppResult;
+use crate::shared::phone::PhoneNumber;
+use async_trait::async_trait;
+use std::fmt;
+
+/// A verified MFA factor of the user
+#[derive(Debug, Clone)]
+pub struct Factor {
+    pub id: String,
+    /// `totp` (the only type LAPP challenges)
+    pub factor_type: String,
+    pub friendly_name: Option<String>,
+}
+
+/// A first-factor login: the user and the factors still to verify before aal2
+#[derive(Debug)]
+pub struct SignIn {
+    pub user: User,
+    pub factors: Vec<Factor>,
+}
+
+/// Result of a signup
+#[derive(Debug)]
+pub enum SignupOutcome {
+    /// Account usable immediately
+    Session(User),
+    /// A confirmation link was emailed; no session until it is followed
+    PendingConfirmation { user_id: UserId, email: String },
+}
+
+/// Identity provider behind `AuthService` - implementations live in infrastructure
+///
+/// Errors are already in domain terms (`AuthError::InvalidCredentials`, validation errors...);
+/// transport failures surface as `AuthError::External` or `AppError::Storage`.
+#[async_trait]
+pub trait AuthProvider: Send + Sync + fmt::Debug {
+    /// Short name for logs (`supabase`, `local`)
+    fn name(&self) -> &'static str;
+
+    /// Check an email and password
+    async fn login(&self, email: &str, password: &str) -> AppResult<SignIn>;
+
+    /// Create an account with profile data
+    async fn register(
+        &self,
+        email: &str,
+        password: &str,
+        username: &str,
+        phone: Option<&PhoneNumber>,
+    ) -> AppResult<SignupOutcome>;
+
+    /// Exchange a refresh token for new tokens; the one passed in is spent
+    /// `AuthError::Unauthenticated` means the token is refused for good, any other error is
+    /// worth retrying
+    async fn refresh(&self, refresh_token: &str) -> AppResult<User>;
+
+    /// Issue tokens for a user LAPP authenticated itself (passkey), without their password
+    /// `AuthError::Unauthenticated` if the account is gone or disabled
+    async fn login_as(&self, user_id: &str) -> AppResult<SignIn>;
+
+    /// Invalidate an access token - best-effort, failures are only logged
+    async fn logout(&self, access_token: &str);
+
+    /// Email address of an account, `None` if there is none with this ID
+    async fn account_email(&self, user_id: &str) -> AppResult<Option<String>>;
+
+    /// Delete an account for good, with every token issued to it
+    /// An account that is already gone is not an error
+    async fn delete_user(&self, user_id: &str) -> AppResult<()>;
+}
@@ -1,220 +1,732 @@
-//! Session management - Server-side session storage with CSV persistence
+//! Session management - Server-side session storage with pluggable persistence
 
 use super::{User, UserId};
+use crate::error::StorageError;
+use crate::shared::constants::session::{
+    DEFAULT_ABSOLUTE_TTL_SECS, DEFAULT_COMPACT_AFTER_OPS, DEFAULT_FLUSH_BATCH_SIZE,
+    DEFAULT_FLUSH_INTERVAL_SECS, DEFAULT_IDLE_TTL_SECS, DEFAULT_MAX_SESSIONS_PER_USER,
+};
+use crate::shared::time::now_secs;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
-use std::sync::{Arc, RwLock};
-use tracing::{info, warn};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-const SESSION_FILE: &str = "data/sessions.csv";
-
-/// Type alias for session IDs
+/// Type alias for session IDs - the SHA-256 digest of the cookie secret
 pub type SessionId = String;
 
+/// Session ID for a cookie secret
+/// Only the digest is kept in memory and persisted, a leaked store can't be replayed as cookies.
+pub fn session_key(secret: &str) -> SessionId {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Whether `id` already is a session key rather than a raw secret from an older store
+fn is_session_key(id: &str) -> bool {
+    URL_SAFE_NO_PAD
+        .decode(id)
+        .is_ok_and(|digest| digest.len() == Sha256::output_size())
+}
+
+/// How many concurrent sessions a single user may hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionLimit {
+    /// A new login evicts every other session of the user
+    Single,
+    /// Keep the N most recently active sessions
+    MostRecent(usize),
+    /// Never evict on login
+    Unlimited,
+}
+
+impl SessionLimit {
+    /// Maximum sessions allowed per user, `None` when unbounded
+    fn max(&self) -> Option<usize> {
+        match self {
+            Self::Single => Some(1),
+            Self::MostRecent(n) => Some((*n).max(1)),
+            Self::Unlimited => None,
+        }
+    }
+}
+
+impl FromStr for SessionLimit {
+    type Err = String;
+
+    /// Parses `single`, `unlimited` or a positive number
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "single" => Ok(Self::Single),
+            "unlimited" => Ok(Self::Unlimited),
+            n => match n.parse::<usize>() {
+                Ok(0) | Err(_) => Err(format!("invalid session limit: {}", s)),
+                Ok(1) => Ok(Self::Single),
+                Ok(n) => Ok(Self::MostRecent(n)),
+            },
+        }
+    }
+}
+
+/// Session lifetime rules (all durations in seconds)
+#[derive(Debug, Clone, Copy)]
+pub struct SessionPolicy {
+    /// Sliding timeout, reset on every successful lookup
+    pub idle_ttl: u64,
+    /// Hard limit counted from session creation
+    pub absolute_ttl: u64,
+    /// Concurrent sessions allowed per user
+    pub limit: SessionLimit,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            idle_ttl: DEFAULT_IDLE_TTL_SECS,
+            absolute_ttl: DEFAULT_ABSOLUTE_TTL_SECS,
+            limit: SessionLimit::MostRecent(DEFAULT_MAX_SESSIONS_PER_USER),
+        }
+    }
+}
+
+/// Authenticator assurance level reached by a session (NIST SP 800-63 / Supabase `aal`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Aal {
+    /// One factor: password, email/SMS code, magic link or OAuth
+    #[default]
+    Aal1,
+    /// Second factor verified (TOTP)
+    Aal2,
+}
+
+impl Aal {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Aal1 => "aal1",
+            Self::Aal2 => "aal2",
+        }
+    }
+}
+
+impl FromStr for Aal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "aal1" => Ok(Self::Aal1),
+            "aal2" => Ok(Self::Aal2),
+            _ => Err(format!("invalid assurance level: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Aal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Client metadata captured when a session is created
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
 /// Session - Links a session ID to a user with their tokens
 #[derive(Debug, Clone)]
 pub struct Session {
+    /// Digest of the cookie secret, used for lookup and storage
     pub id: SessionId,
+    /// Cookie value handed to the client - only known at creation, never persisted
+    pub secret: String,
+    /// Public handle used to list and revoke the session
+    pub device_id: String,
     pub user: User,
+    pub created_at: u64,
+    pub last_seen: u64,
+    pub client: ClientInfo,
+    /// Factors verified for this login, handlers may demand `Aal2`
+    pub aal: Aal,
+    /// When the user last proved who they are (login, second factor), 0 if unknown
+    pub verified_at: u64,
 }
 
 impl Session {
     /// Create a new session for a user
-    pub fn new(user: User) -> Self {
+    pub fn new(user: User, client: ClientInfo, aal: Aal) -> Self {
+        let now = now_secs();
+        let secret = Uuid::new_v4().to_string();
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: session_key(&secret),
+            secret,
+            device_id: Uuid::new_v4().to_string(),
             user,
+            created_at: now,
+            last_seen: now,
+            client,
+            aal,
+            verified_at: now,
+        }
+    }
+
+    /// Whether the session is dead at `now` (idle or too old)
+    /// Access token expiry is not fatal: the token is refreshed by `AuthService`
+    pub fn is_expired(&self, policy: &SessionPolicy, now: u64) -> bool {
+        now.saturating_sub(self.last_seen) >= policy.idle_ttl
+            || now.saturating_sub(self.created_at) >= policy.absolute_ttl
+    }
+
+    /// Whether the session passed a second factor less than `max_age` seconds before `now`
+    pub fn recently_verified_aal2(&self, max_age: u64, now: u64) -> bool {
+        self.aal >= Aal::Aal2 && now.saturating_sub(self.verified_at) < max_age
+    }
+
+    /// Whether the access token expires within `margin` seconds of `now`
+    pub fn needs_refresh(&self, margin: u64, now: u64) -> bool {
+        self.user.expires_at <= now.saturating_add(margin)
+    }
+}
+
+/// A single session mutation, journaled by the backend
+#[derive(Debug, Clone)]
+pub enum SessionOp {
+    /// Insert or replace the session
+    Upsert(Box<Session>),
+    /// Delete the session with this ID
+    Remove(SessionId),
+}
+
+impl SessionOp {
+    fn session_id(&self) -> &str {
+        match self {
+            Self::Upsert(s) => &s.id,
+            Self::Remove(id) => id,
         }
     }
 }
 
-/// SessionStore - In-memory + CSV persistence
-/// Keyed by session_id for fast lookup from cookie
+/// Persistence port for sessions - implementations live in infrastructure
+pub trait SessionBackend: Send + Sync + fmt::Debug {
+    /// Short name for logs
+    fn name(&self) -> &'static str;
+
+    /// Read every persisted session (snapshot plus replayed journal)
+    fn load(&self) -> Result<Vec<Session>, StorageError>;
+
+    /// Durably append a batch of mutations
+    fn apply(&self, ops: &[SessionOp]) -> Result<(), StorageError>;
+
+    /// Rewrite storage from a full snapshot, discarding the journal
+    fn compact(&self, sessions: &[Session]) -> Result<(), StorageError>;
+}
+
+/// Write-behind tuning: mutations are queued and flushed in batches
+#[derive(Debug, Clone, Copy)]
+pub struct WriteBehind {
+    /// Maximum delay before queued mutations reach the backend
+    pub flush_interval: Duration,
+    /// Flush early once this many sessions have pending mutations
+    pub batch_size: usize,
+    /// Compact the backend after this many journaled mutations
+    pub compact_after: usize,
+}
+
+impl Default for WriteBehind {
+    fn default() -> Self {
+        Self {
+            flush_interval: Duration::from_secs(DEFAULT_FLUSH_INTERVAL_SECS),
+            batch_size: DEFAULT_FLUSH_BATCH_SIZE,
+            compact_after: DEFAULT_COMPACT_AFTER_OPS,
+        }
+    }
+}
+
+/// SessionStore - In-memory index backed by a SessionBackend
+/// Keyed by session_id (`session_key` of the cookie) for fast lookup.
+/// Mutations are applied in memory immediately and persisted write-behind.
 #[derive(Debug, Clone)]
 pub struct SessionStore {
+    policy: SessionPolicy,
+    backend: Arc<dyn SessionBackend>,
+    write_behind: WriteBehind,
+    // Pending mutations, coalesced per session (latest wins)
+    pending: Arc<Mutex<HashMap<SessionId, SessionOp>>>,
+    // Wakes the flusher when a batch is full
+    flush_signal: Arc<Notify>,
+    // Mutations journaled since the last compaction
+    journaled: Arc<AtomicUsize>,
+    // Serializes flush/compact so batches reach the backend in order
+    persist_lock: Arc<Mutex<()>>,
     // session_id -> Session
     by_session: Arc<RwLock<HashMap<SessionId, Session>>>,
-    // user_id -> session_id (for lookup by Supabase ID)
-    user_to_session: Arc<RwLock<HashMap<UserId, SessionId>>>,
+    // user_id -> session_ids (every device the user is logged in on)
+    user_sessions: Arc<RwLock<HashMap<UserId, Vec<SessionId>>>>,
 }
 
 impl SessionStore {
-    /// Create new store and load existing sessions from CSV
-    pub fn new() -> Self {
+    /// Create new store, load existing sessions and compact the backend
+    pub fn new(
+        policy: SessionPolicy,
+        backend: Arc<dyn SessionBackend>,
+        write_behind: WriteBehind,
+    ) -> Self {
         let store = Self {
+            policy,
+            backend,
+            write_behind,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            flush_signal: Arc::new(Notify::new()),
+            journaled: Arc::new(AtomicUsize::new(0)),
+            persist_lock: Arc::new(Mutex::new(())),
             by_session: Arc::new(RwLock::new(HashMap::new())),
-            user_to_session: Arc::new(RwLock::new(HashMap::new())),
+            user_sessions: Arc::new(RwLock::new(HashMap::new())),
         };
-        store.load_from_csv();
+        // Fold the replayed journal (and dropped expired sessions) into a fresh snapshot.
+        // Never compact after a failed load: it would overwrite unread data with nothing.
+        if store.load() {
+            store.compact();
+        }
         store
     }
 
+    /// Insert user with new single-factor session, evict sessions over the limit, persist
+    pub fn create_session(&self, user: User, client: ClientInfo) -> Session {
+        self.create_session_with_aal(user, client, Aal::Aal1)
+    }
 
-    /// Insert user with new session, persist to CSV
-    pub fn create_session(&self, user: User) -> SessionId {
-        let session = Session::new(user);
-        let session_id = session.id.clone();
+    /// Same as `create_session`, for a login that already verified `aal`
+    pub fn create_session_with_aal(&self, user: User, client: ClientInfo, aal: Aal) -> Session {
+        let session = Session::new(user, client, aal);
         let user_id = session.user.id.clone();
 
-        {
+        let evicted = {
             let mut sessions = self.by_session.write().unwrap();
-            let mut user_map = self.user_to_session.write().unwrap();
+            let mut user_map = self.user_sessions.write().unwrap();
 
-            // Remove old session if user already logged in
-            if let Some(old_session) = user_map.get(&user_id) {
-                sessions.remove(old_session);
-            }
+            let ids = user_map.entry(user_id).or_default();
+            let evicted = Self::evict_over_limit(&mut sessions, ids, self.policy.limit);
+
+            ids.push(session.id.clone());
+            sessions.insert(session.id.clone(), session.clone());
+
+            let count = evicted.len();
+            self.enqueue(
+                evicted
+                    .into_iter()
+                    .map(SessionOp::Remove)
+                    .chain([SessionOp::Upsert(Box::new(session.clone()))]),
+            );
+            count
+        };
 
-            user_map.insert(user_id, session_id.clone());
-            sessions.insert(session_id.clone(), session);
+        info!(
+            device_id = %session.device_id,
+            evicted = evicted,
+            "Session created"
+        );
+        session
+    }
+
+    /// Get session by session_id (`session_key` of the cookie)
+    /// Expired sessions are removed on sight; live ones get their idle timer reset
+    pub fn get_session(&self, session_id: &str) -> Option<Session> {
+        let now = now_secs();
+        let mut sessions = self.by_session.write().unwrap();
+
+        let session = sessions.get_mut(session_id)?;
+        if !session.is_expired(&self.policy, now) {
+            session.last_seen = now;
+            let session = session.clone();
+            // Coalesced with other pending writes, so frequent lookups stay cheap
+            self.enqueue([SessionOp::Upsert(Box::new(session.clone()))]);
+            return Some(session);
         }
 
-        self.save_to_csv();
-        info!(session_id = %session_id, "Session created");
-        session_id
+        let expired = sessions.remove(session_id)?;
+        Self::unlink(&mut self.user_sessions.write().unwrap(), &expired);
+        self.enqueue([SessionOp::Remove(expired.id)]);
+        drop(sessions);
+
+        info!(device_id = %expired.device_id, "Session expired");
+        None
+    }
+
+    /// Get a live session without resetting its idle timer
+    /// For background work (token refresh) that must not count as client activity
+    pub fn peek_session(&self, session_id: &str) -> Option<Session> {
+        let now = now_secs();
+        self.by_session
+            .read()
+            .unwrap()
+            .get(session_id)
+            .filter(|s| !s.is_expired(&self.policy, now))
+            .cloned()
+    }
+
+    /// Replace the user (new tokens after a refresh) of a live session, idle timer untouched
+    /// Returns the updated session, `None` if it was removed meanwhile
+    pub fn update_user(&self, session_id: &str, user: User) -> Option<Session> {
+        let mut sessions = self.by_session.write().unwrap();
+
+        let session = sessions.get_mut(session_id)?;
+        session.user = user;
+        let session = session.clone();
+        self.enqueue([SessionOp::Upsert(Box::new(session.clone()))]);
+        Some(session)
+    }
+
+    /// Record a second factor on a live session, with the tokens Supabase issued for it
+    pub fn elevate(&self, session_id: &str, user: User, aal: Aal) -> Option<Session> {
+        let mut sessions = self.by_session.write().unwrap();
+
+        let session = sessions.get_mut(session_id)?;
+        session.user = user;
+        session.aal = session.aal.max(aal);
+        session.verified_at = now_secs();
+        let session = session.clone();
+        self.enqueue([SessionOp::Upsert(Box::new(session.clone()))]);
+        info!(session_id = %session_id, aal = %session.aal, "Session elevated");
+        Some(session)
     }
 
-    /// Get user by session_id (from cookie)
-    pub fn get_user(&self, session_id: &str) -> Option<User> {
+    /// Live sessions whose access token expires within `margin` seconds
+    pub fn sessions_needing_refresh(&self, margin: u64) -> Vec<Session> {
+        let now = now_secs();
+        self.by_session
+            .read()
+            .unwrap()
+            .values()
+            .filter(|s| !s.is_expired(&self.policy, now) && s.needs_refresh(margin, now))
+            .cloned()
+            .collect()
+    }
+
+    /// Live sessions of a user, most recently active first
+    pub fn list_user_sessions(&self, user_id: &str) -> Vec<Session> {
+        let now = now_secs();
         let sessions = self.by_session.read().unwrap();
-        sessions.get(session_id).map(|s| s.user.clone())
+        let user_map = self.user_sessions.read().unwrap();
+
+        let mut list: Vec<Session> = user_map
+            .get(user_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|sid| sessions.get(sid))
+            .filter(|s| !s.is_expired(&self.policy, now))
+            .cloned()
+            .collect();
+
+        list.sort_by_key(|s| std::cmp::Reverse(s.last_seen));
+        list
     }
 
-    /// Remove session (logout)
-    pub fn delete_session(&self, session_id: &str) -> Option<User> {
-        let user = {
-            let mut sessions = self.by_session.write().unwrap();
-            let mut user_map = self.user_to_session.write().unwrap();
+    /// Remove one of the user's sessions by its public device ID
+    /// Returns the removed session, `None` if it doesn't belong to the user
+    pub fn delete_user_session(&self, user_id: &str, device_id: &str) -> Option<Session> {
+        let session_id = {
+            let sessions = self.by_session.read().unwrap();
+            let user_map = self.user_sessions.read().unwrap();
+
+            user_map
+                .get(user_id)?
+                .iter()
+                .find(|sid| sessions.get(*sid).is_some_and(|s| s.device_id == device_id))?
+                .clone()
+        };
 
-            if let Some(session) = sessions.remove(session_id) {
-                user_map.remove(&session.user.id);
-                Some(session.user)
-            } else {
-                None
-            }
+        self.remove(&session_id)
+    }
+
+    /// Remove every session of a user (e.g. after a password reset), returns them
+    pub fn delete_user_sessions(&self, user_id: &str) -> Vec<Session> {
+        let removed: Vec<Session> = {
+            let mut sessions = self.by_session.write().unwrap();
+            let ids = self
+                .user_sessions
+                .write()
+                .unwrap()
+                .remove(user_id)
+                .unwrap_or_default();
+
+            let removed: Vec<Session> = ids.iter().filter_map(|sid| sessions.remove(sid)).collect();
+            self.enqueue(removed.iter().map(|s| SessionOp::Remove(s.id.clone())));
+            removed
         };
 
-        if user.is_some() {
-            self.save_to_csv();
-            info!(session_id = %session_id, "Session deleted");
+        info!(user_id = %user_id, count = removed.len(), "User sessions deleted");
+        removed
+    }
+
+    /// Remove session (logout)
+    pub fn delete_session(&self, session_id: &str) -> Option<User> {
+        self.remove(session_id).map(|s| s.user)
+    }
+
+    /// Remove every expired session, returns how many were purged
+    pub fn purge_expired(&self) -> usize {
+        let now = now_secs();
+        let mut sessions = self.by_session.write().unwrap();
+        let mut user_map = self.user_sessions.write().unwrap();
+
+        let expired: Vec<SessionId> = sessions
+            .values()
+            .filter(|s| s.is_expired(&self.policy, now))
+            .map(|s| s.id.clone())
+            .collect();
+        for sid in &expired {
+            sessions.remove(sid);
         }
-        user
+        user_map.retain(|_, ids| {
+            ids.retain(|sid| sessions.contains_key(sid));
+            !ids.is_empty()
+        });
+
+        let count = expired.len();
+        self.enqueue(expired.into_iter().map(SessionOp::Remove));
+        count
     }
 
-    /// Load sessions from CSV file
-    fn load_from_csv(&self) {
-        let path = Path::new(SESSION_FILE);
-        if !path.exists() {
-            info!("No session file found, starting fresh");
-            return;
+    /// Write pending mutations to the backend, compacting when the journal grows too long.
+    /// Returns how many mutations were written.
+    pub fn flush(&self) -> usize {
+        let _guard = self.persist_lock.lock().unwrap();
+
+        let ops: Vec<SessionOp> = self
+            .pending
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, op)| op)
+            .collect();
+        if ops.is_empty() {
+            return 0;
         }
 
-        let file = match File::open(path) {
-            Ok(f) => f,
-            Err(e) => {
-                warn!(error = %e, "Failed to open session file");
-                return;
+        if let Err(e) = self.backend.apply(&ops) {
+            warn!(backend = self.backend.name(), error = %e, count = ops.len(), "Failed to flush sessions, will retry");
+            self.requeue(ops);
+            return 0;
+        }
+
+        debug!(
+            backend = self.backend.name(),
+            count = ops.len(),
+            "Flushed session mutations"
+        );
+        let journaled = self.journaled.fetch_add(ops.len(), Ordering::Relaxed) + ops.len();
+        if journaled >= self.write_behind.compact_after {
+            self.compact_locked();
+        }
+        ops.len()
+    }
+
+    /// Flush everything and compact - call on graceful shutdown
+    pub fn shutdown(&self) {
+        let _guard = self.persist_lock.lock().unwrap();
+        self.compact_locked();
+        info!(
+            backend = self.backend.name(),
+            "Session store flushed for shutdown"
+        );
+    }
+
+    /// Spawn the write-behind task: flushes every interval, or early when a batch fills up
+    pub fn spawn_flusher(&self) -> JoinHandle<()> {
+        let store = self.clone();
+        info!(
+            interval_ms = self.write_behind.flush_interval.as_millis() as u64,
+            batch_size = self.write_behind.batch_size,
+            "Session flusher started"
+        );
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(store.write_behind.flush_interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = store.flush_signal.notified() => {}
+                }
+
+                let s = store.clone();
+                if let Err(e) = tokio::task::spawn_blocking(move || s.flush()).await {
+                    warn!(error = %e, "Session flusher task failed");
+                }
             }
-        };
+        })
+    }
 
-        let reader = BufReader::new(file);
-        let mut sessions = self.by_session.write().unwrap();
-        let mut user_map = self.user_to_session.write().unwrap();
-        let mut count = 0;
+    /// Spawn a background task purging expired sessions every `every`
+    pub fn spawn_reaper(&self, every: Duration) -> JoinHandle<()> {
+        let store = self.clone();
+        info!(interval_secs = every.as_secs(), "Session reaper started");
 
-        for (i, line) in reader.lines().enumerate() {
-            if i == 0 {
-                continue; // Skip header
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                ticker.tick().await;
+
+                match store.purge_expired() {
+                    0 => debug!("Session reaper: nothing to purge"),
+                    count => info!(count = count, "Session reaper purged expired sessions"),
+                }
             }
+        })
+    }
 
-            let line = match line {
-                Ok(l) => l,
-                Err(_) => continue,
-            };
+    /// Remove a session by ID and queue the deletion
+    fn remove(&self, session_id: &str) -> Option<Session> {
+        let session = {
+            let mut sessions = self.by_session.write().unwrap();
+            let session = sessions.remove(session_id)?;
+            Self::unlink(&mut self.user_sessions.write().unwrap(), &session);
+            self.enqueue([SessionOp::Remove(session.id.clone())]);
+            session
+        };
 
-            let parts: Vec<&str> = line.split(',').collect();
-            if parts.len() < 8 {
-                continue;
+        info!(device_id = %session.device_id, "Session deleted");
+        Some(session)
+    }
+
+    /// Drop a session from the user index
+    fn unlink(user_map: &mut HashMap<UserId, Vec<SessionId>>, session: &Session) {
+        if let Some(ids) = user_map.get_mut(&session.user.id) {
+            ids.retain(|sid| sid != &session.id);
+            if ids.is_empty() {
+                user_map.remove(&session.user.id);
             }
+        }
+    }
 
-            let user = User {
-                id: parts[1].to_string(),
-                email: parts[2].to_string(),
-                username: parts[3].to_string(),
-                role: parts[4].to_string(),
-                access_token: parts[5].to_string(),
-                refresh_token: parts[6].to_string(),
-                expires_at: parts[7].parse().unwrap_or(0),
-            };
-
-            let session = Session {
-                id: parts[0].to_string(),
-                user,
-            };
-
-            user_map.insert(session.user.id.clone(), session.id.clone());
-            sessions.insert(session.id.clone(), session);
-            count += 1;
+    /// Make room for one more session, evicting the least recently active ones
+    /// Returns the evicted session IDs
+    fn evict_over_limit(
+        sessions: &mut HashMap<SessionId, Session>,
+        ids: &mut Vec<SessionId>,
+        limit: SessionLimit,
+    ) -> Vec<SessionId> {
+        let Some(max) = limit.max() else {
+            return Vec::new();
+        };
+
+        ids.retain(|sid| sessions.contains_key(sid));
+        ids.sort_by_key(|sid| sessions[sid].last_seen);
+
+        let excess = (ids.len() + 1).saturating_sub(max);
+        let evicted: Vec<SessionId> = ids.drain(..excess).collect();
+        for sid in &evicted {
+            sessions.remove(sid);
+        }
+        evicted
+    }
+
+    /// Queue mutations for the flusher, waking it when the batch is full
+    /// Callers hold the `by_session` lock so queue order matches memory order
+    fn enqueue(&self, ops: impl IntoIterator<Item = SessionOp>) {
+        let mut pending = self.pending.lock().unwrap();
+        for op in ops {
+            pending.insert(op.session_id().to_string(), op);
         }
 
-        info!(count = count, "Loaded sessions from CSV");
+        if pending.len() >= self.write_behind.batch_size {
+            self.flush_signal.notify_one();
+        }
     }
 
-    /// Save all sessions to CSV file
-    fn save_to_csv(&self) {
-        if let Err(e) = std::fs::create_dir_all("data") {
-            warn!(error = %e, "Failed to create data directory");
-            return;
+    /// Put back a batch that failed to flush, without overwriting newer mutations
+    fn requeue(&self, ops: Vec<SessionOp>) {
+        let mut pending = self.pending.lock().unwrap();
+        for op in ops {
+            pending.entry(op.session_id().to_string()).or_insert(op);
         }
+    }
 
-        let file = match OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(SESSION_FILE)
-        {
-            Ok(f) => f,
+    /// Rewrite the backend from the in-memory state
+    fn compact(&self) {
+        let _guard = self.persist_lock.lock().unwrap();
+        self.compact_locked();
+    }
+
+    /// Compaction body, caller holds `persist_lock`
+    /// Pending mutations are already reflected in memory, so the snapshot supersedes them
+    fn compact_locked(&self) {
+        let drained: Vec<SessionOp> = self
+            .pending
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, op)| op)
+            .collect();
+        let snapshot: Vec<Session> = self.by_session.read().unwrap().values().cloned().collect();
+
+        match self.backend.compact(&snapshot) {
+            Ok(()) => {
+                self.journaled.store(0, Ordering::Relaxed);
+                debug!(
+                    backend = self.backend.name(),
+                    count = snapshot.len(),
+                    "Compacted sessions"
+                );
+            }
+            Err(e) => {
+                warn!(backend = self.backend.name(), error = %e, "Failed to compact sessions");
+                self.requeue(drained);
+            }
+        }
+    }
+
+    /// Load persisted sessions from the backend, dropping expired ones
+    /// Returns false if the backend could not be read
+    fn load(&self) -> bool {
+        let loaded = match self.backend.load() {
+            Ok(list) => list,
             Err(e) => {
-                warn!(error = %e, "Failed to open session file for writing");
-                return;
+                warn!(backend = self.backend.name(), error = %e, "Failed to load sessions");
+                return false;
             }
         };
 
-        let mut writer = std::io::BufWriter::new(file);
-        let sessions = self.by_session.read().unwrap();
+        let now = now_secs();
+        let mut sessions = self.by_session.write().unwrap();
+        let mut user_map = self.user_sessions.write().unwrap();
+        let mut expired = 0;
 
-        // Write header
-        let _ = writeln!(
-            writer,
-            "session_id,user_id,email,username,role,access_token,refresh_token,expires_at"
-        );
+        for mut session in loaded {
+            if session.is_expired(&self.policy, now) {
+                expired += 1;
+                continue;
+            }
+            // Stores written before hashing hold the raw secret, the compaction after load rewrites them
+            if !is_session_key(&session.id) {
+                session.id = session_key(&session.id);
+            }
 
-        // Write each session
-        for session in sessions.values() {
-            let _ = writeln!(
-                writer,
-                "{},{},{},{},{},{},{},{}",
-                session.id,
-                session.user.id,
-                session.user.email,
-                session.user.username,
-                session.user.role,
-                session.user.access_token,
-                session.user.refresh_token,
-                session.user.expires_at
-            );
+            user_map
+                .entry(session.user.id.clone())
+                .or_default()
+                .push(session.id.clone());
+            sessions.insert(session.id.clone(), session);
         }
 
-        let _ = writer.flush();
-        info!(count = sessions.len(), "Saved sessions to CSV");
-    }
-}
-
-impl Default for SessionStore {
-    fn default() -> Self {
-        Self::new()
+        info!(
+            backend = self.backend.name(),
+            count = sessions.len(),
+            expired = expired,
+            "Loaded sessions"
+        );
+        true
     }
 }
@@ -0,0 +1,86 @@
+//! Login throttling - Failed attempts per account and per client IP
+
+use crate::error::StorageError;
+use std::fmt;
+
+/// Limits on failed logins, all durations in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottlePolicy {
+    /// Sliding window failures are counted over
+    pub window: u64,
+    /// Failures on one account within the window before it is locked
+    pub account_attempts: u32,
+    /// Failures from one IP within the window, any account, before it is rate limited
+    pub ip_attempts: u32,
+    /// How long a locked account refuses logins
+    pub lockout: u64,
+    /// Failures on one account before each new attempt has to wait, doubling every time;
+    /// 0 disables delays
+    pub delay_after: u32,
+}
+
+impl ThrottlePolicy {
+    /// Wait imposed after the `failures`-th failure on an account: 2s, 4s, 8s... up to `max`
+    pub fn delay(&self, failures: u32, max: u64) -> u64 {
+        if self.delay_after == 0 || failures < self.delay_after {
+            return 0;
+        }
+        let doublings = (failures - self.delay_after + 1).min(63);
+        1u64.checked_shl(doublings).unwrap_or(u64::MAX).min(max)
+    }
+}
+
+/// What is being throttled: an account (by email, or E.164 phone for SMS codes) or a client IP
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ThrottleKey {
+    Account(String),
+    Ip(String),
+}
+
+impl ThrottleKey {
+    /// Emails are matched case-insensitively, as by the auth providers
+    pub fn account(email: &str) -> Self {
+        Self::Account(email.trim().to_lowercase())
+    }
+}
+
+impl fmt::Display for ThrottleKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Account(email) => write!(f, "account:{}", email),
+            Self::Ip(ip) => write!(f, "ip:{}", ip),
+        }
+    }
+}
+
+/// A key as a new attempt found it, see `AttemptStore::reserve`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reserved {
+    /// The failure recorded in advance for the attempt
+    pub id: i64,
+    /// Failures since the start of the window, oldest first, attempts still in flight included
+    pub failures: Vec<u64>,
+    /// End of the key's lockout, if it was ever locked
+    pub locked_until: Option<u64>,
+}
+
+/// Persistence port for failed attempts and lockouts - implementations live in infrastructure
+/// Keys are `ThrottleKey` strings
+pub trait AttemptStore: Send + Sync + fmt::Debug {
+    /// Times of the failures recorded for `key` since `since`, oldest first
+    fn failures(&self, key: &str, since: u64) -> Result<Vec<u64>, StorageError>;
+
+    /// Read each key's failures (since `since`) and lockout, and record a failure on it, all in
+    /// one transaction: concurrent attempts see each other. Failures of any key older than
+    /// `since` are forgotten. One `Reserved` per key, in order.
+    fn reserve(&self, keys: &[String], at: u64, since: u64) -> Result<Vec<Reserved>, StorageError>;
+
+    /// Drop failures recorded by `reserve` for attempts that did not fail
+    fn release(&self, ids: &[i64]) -> Result<(), StorageError>;
+
+    /// Forget the failures of `key`
+    fn clear_failures(&self, key: &str) -> Result<(), StorageError>;
+
+    /// Lock `key` until `until` and forget its failures
+    fn lock(&self, key: &str, until: u64) -> Result<(), StorageError>;
+}
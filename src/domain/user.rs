@@ -9,7 +9,10 @@ pub struct User {
     pub id: UserId,
     pub email: String,
     pub username: String,
+    /// Postgres role from Supabase (`authenticated`)
     pub role: String,
+    /// Application roles from the provider (Supabase `app_metadata`), see `authz`
+    pub roles: Vec<String>,
     pub access_token: String,  // JWT - stored server-side only
     pub refresh_token: String, // Refresh token - stored server-side only
     pub expires_at: u64,
@@ -1,16 +1,23 @@
 //! App error - Top-level application error with ResponseError impl
 
-use super::{AuthError, ErrorCode, ErrorResponse, SupabaseError};
-use actix_web::http::StatusCode;
+use super::{AuthError, ErrorCode, ErrorResponse, StorageError, SupabaseError};
+use actix_web::http::{StatusCode, header};
 use actix_web::{HttpResponse, ResponseError};
 use std::fmt;
-use tracing::{error, warn};
+use tracing::{error, info, warn};
 
 /// Main application error type - all errors bubble up to this
 #[derive(Debug)]
 pub enum AppError {
     Auth(AuthError),
-    Validation { field: &'static str, message: String },
+    Validation {
+        field: &'static str,
+        message: String,
+    },
+    /// Server misconfiguration (missing app data...) - logged, never sent to the client
+    Internal(&'static str),
+    /// Local persistence failed - details are logged, never sent to the client
+    Storage(StorageError),
 }
 
 impl AppError {
@@ -18,6 +25,17 @@ impl AppError {
         match self {
             Self::Auth(e) => e.code(),
             Self::Validation { .. } => ErrorCode::ValidationFailed,
+            Self::Internal(_) => ErrorCode::Internal,
+            Self::Storage(_) => ErrorCode::StorageFailed,
+        }
+    }
+
+    /// Seconds the client should wait before retrying, sent as `Retry-After`
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::Auth(AuthError::RateLimited { retry_after })
+            | Self::Auth(AuthError::AccountLocked { retry_after }) => Some(*retry_after),
+            _ => None,
         }
     }
 
@@ -34,12 +52,60 @@ impl AppError {
             Self::Auth(AuthError::InvalidCredentials) => {
                 warn!(error_code = %self.code().as_str(), "Authentication failed: invalid credentials");
             }
+            Self::Auth(AuthError::RateLimited { retry_after }) => {
+                warn!(error_code = %self.code().as_str(), retry_after, "Login rate limited");
+            }
+            Self::Auth(AuthError::AccountLocked { retry_after }) => {
+                warn!(error_code = %self.code().as_str(), retry_after, "Login to a locked account");
+            }
+            Self::Auth(AuthError::Unauthenticated) => {
+                info!(error_code = %self.code().as_str(), "Request without a valid session");
+            }
+            Self::Auth(AuthError::InsufficientRole { required }) => {
+                warn!(error_code = %self.code().as_str(), required_role = %required, "Access denied: insufficient role");
+            }
+            Self::Auth(AuthError::Forbidden { permission }) => {
+                warn!(error_code = %self.code().as_str(), permission = %permission, "Access denied: missing permission");
+            }
+            Self::Auth(AuthError::NotEntitled { app }) => {
+                info!(error_code = %self.code().as_str(), app = %app, "Access denied: no entitlement to app");
+            }
+            Self::Auth(AuthError::TrialExpired { app }) => {
+                info!(error_code = %self.code().as_str(), app = %app, "Access denied: app trial expired");
+            }
+            Self::Auth(AuthError::InvalidResetToken) => {
+                warn!(error_code = %self.code().as_str(), "Password reset with invalid token");
+            }
+            Self::Auth(AuthError::InvalidOtp) => {
+                warn!(error_code = %self.code().as_str(), "Invalid one-time token");
+            }
+            Self::Auth(AuthError::OAuthState) => {
+                warn!(error_code = %self.code().as_str(), "OAuth callback without a matching flow");
+            }
+            Self::Auth(AuthError::OAuthFailed) => {
+                warn!(error_code = %self.code().as_str(), "OAuth sign-in refused");
+            }
+            Self::Auth(AuthError::MfaRequired) => {
+                info!(error_code = %self.code().as_str(), "Second factor required");
+            }
+            Self::Auth(AuthError::PasskeyRejected) => {
+                warn!(error_code = %self.code().as_str(), "Passkey ceremony rejected");
+            }
+            Self::Auth(AuthError::Unsupported { feature }) => {
+                info!(error_code = %self.code().as_str(), feature = %feature, "Feature unavailable with this auth provider");
+            }
             Self::Auth(AuthError::External(e)) => {
                 error!(error_code = %self.code().as_str(), supabase_error = %e, "External auth service error");
             }
             Self::Validation { field, message } => {
                 warn!(error_code = %self.code().as_str(), field = %field, message = %message, "Validation error");
             }
+            Self::Internal(reason) => {
+                error!(error_code = %self.code().as_str(), reason = %reason, "Internal error");
+            }
+            Self::Storage(e) => {
+                error!(error_code = %self.code().as_str(), storage_error = %e, "Storage error");
+            }
         }
     }
 }
@@ -51,6 +117,8 @@ impl fmt::Display for AppError {
             Self::Validation { field, message } => {
                 write!(f, "Validation error on '{}': {}", field, message)
             }
+            Self::Internal(reason) => write!(f, "Internal error: {}", reason),
+            Self::Storage(e) => write!(f, "{}", e),
         }
     }
 }
@@ -59,7 +127,8 @@ impl std::error::Error for AppError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::Auth(e) => Some(e),
-            Self::Validation { .. } => None,
+            Self::Validation { .. } | Self::Internal(_) => None,
+            Self::Storage(e) => Some(e),
         }
     }
 }
@@ -70,6 +139,12 @@ impl From<AuthError> for AppError {
     }
 }
 
+impl From<StorageError> for AppError {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err)
+    }
+}
+
 impl From<SupabaseError> for AppError {
     fn from(err: SupabaseError) -> Self {
         Self::Auth(AuthError::from(err))
@@ -85,7 +160,11 @@ impl ResponseError for AppError {
         self.log();
 
         let code = self.code();
-        HttpResponse::build(code.status()).json(ErrorResponse {
+        let mut response = HttpResponse::build(code.status());
+        if let Some(secs) = self.retry_after() {
+            response.insert_header((header::RETRY_AFTER, secs.to_string()));
+        }
+        response.json(ErrorResponse {
             code: code.as_str(),
             message: code.message(),
             field: match self {
@@ -8,6 +8,48 @@ use std::fmt;
 #[derive(Debug)]
 pub enum AuthError {
     InvalidCredentials,
+    /// Too many failed logins from this client or on this account, retry in `retry_after` seconds
+    RateLimited {
+        retry_after: u64,
+    },
+    /// Account locked after repeated failed logins, unlocked in `retry_after` seconds
+    AccountLocked {
+        retry_after: u64,
+    },
+    /// No session, or the session is expired or revoked
+    Unauthenticated,
+    /// Authenticated, but the user's role is not the one required
+    InsufficientRole {
+        required: &'static str,
+    },
+    /// Authenticated, but none of the user's roles grants the permission
+    Forbidden {
+        permission: &'static str,
+    },
+    /// Authenticated, but without an entitlement to the app (never granted or revoked)
+    NotEntitled {
+        app: &'static str,
+    },
+    /// Authenticated, but the trial entitlement to the app has expired
+    TrialExpired {
+        app: &'static str,
+    },
+    /// Password recovery token unknown, used or expired
+    InvalidResetToken,
+    /// Confirmation/sign-in link or code unknown, used or expired
+    InvalidOtp,
+    /// OAuth callback without a matching, unexpired sign-in attempt (CSRF or stale tab)
+    OAuthState,
+    /// The provider or Supabase refused the OAuth sign-in
+    OAuthFailed,
+    /// Authenticated with one factor where a verified second factor (aal2) is required
+    MfaRequired,
+    /// Passkey registration or assertion failed verification (reason logged where it happened)
+    PasskeyRejected,
+    /// The flow needs Supabase, and another auth provider is configured
+    Unsupported {
+        feature: &'static str,
+    },
     External(SupabaseError),
 }
 
@@ -15,6 +57,20 @@ impl AuthError {
     pub fn code(&self) -> ErrorCode {
         match self {
             Self::InvalidCredentials => ErrorCode::InvalidCredentials,
+            Self::RateLimited { .. } => ErrorCode::RateLimited,
+            Self::AccountLocked { .. } => ErrorCode::AccountLocked,
+            Self::Unauthenticated => ErrorCode::Unauthenticated,
+            Self::InsufficientRole { .. } => ErrorCode::InsufficientRole,
+            Self::Forbidden { .. } => ErrorCode::Forbidden,
+            Self::NotEntitled { .. } => ErrorCode::AppAccessDenied,
+            Self::TrialExpired { .. } => ErrorCode::AppTrialExpired,
+            Self::InvalidResetToken => ErrorCode::InvalidResetToken,
+            Self::InvalidOtp => ErrorCode::InvalidOtp,
+            Self::OAuthState => ErrorCode::OAuthState,
+            Self::OAuthFailed => ErrorCode::OAuthFailed,
+            Self::MfaRequired => ErrorCode::MfaRequired,
+            Self::PasskeyRejected => ErrorCode::PasskeyRejected,
+            Self::Unsupported { .. } => ErrorCode::ProviderUnsupported,
             Self::External(e) => e.code(),
         }
     }
@@ -24,6 +80,22 @@ impl fmt::Display for AuthError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::InvalidCredentials => write!(f, "Invalid credentials"),
+            Self::RateLimited { retry_after } => write!(f, "Rate limited for {}s", retry_after),
+            Self::AccountLocked { retry_after } => write!(f, "Account locked for {}s", retry_after),
+            Self::Unauthenticated => write!(f, "Not authenticated"),
+            Self::InsufficientRole { required } => write!(f, "Role '{}' required", required),
+            Self::Forbidden { permission } => write!(f, "Permission '{}' required", permission),
+            Self::NotEntitled { app } => write!(f, "No access to app '{}'", app),
+            Self::TrialExpired { app } => write!(f, "Trial of app '{}' expired", app),
+            Self::InvalidResetToken => write!(f, "Invalid or expired reset token"),
+            Self::InvalidOtp => write!(f, "Invalid or expired one-time token"),
+            Self::OAuthState => write!(f, "Unknown or expired OAuth flow"),
+            Self::OAuthFailed => write!(f, "OAuth sign-in refused"),
+            Self::MfaRequired => write!(f, "Second factor required"),
+            Self::PasskeyRejected => write!(f, "Passkey rejected"),
+            Self::Unsupported { feature } => {
+                write!(f, "{} needs the Supabase auth provider", feature)
+            }
             Self::External(e) => write!(f, "External auth error: {}", e),
         }
     }
@@ -33,7 +105,21 @@ impl std::error::Error for AuthError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::External(e) => Some(e),
-            Self::InvalidCredentials => None,
+            Self::InvalidCredentials
+            | Self::RateLimited { .. }
+            | Self::AccountLocked { .. }
+            | Self::Unauthenticated
+            | Self::InsufficientRole { .. }
+            | Self::Forbidden { .. }
+            | Self::NotEntitled { .. }
+            | Self::TrialExpired { .. }
+            | Self::InvalidResetToken
+            | Self::InvalidOtp
+            | Self::OAuthState
+            | Self::OAuthFailed
+            | Self::MfaRequired
+            | Self::PasskeyRejected
+            | Self::Unsupported { .. } => None,
         }
     }
 }
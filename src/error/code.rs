@@ -8,6 +8,20 @@ use actix_web::http::StatusCode;
 pub enum ErrorCode {
     // Auth
     InvalidCredentials,
+    RateLimited,
+    AccountLocked,
+    Unauthenticated,
+    InsufficientRole,
+    Forbidden,
+    AppAccessDenied,
+    AppTrialExpired,
+    InvalidResetToken,
+    InvalidOtp,
+    OAuthState,
+    OAuthFailed,
+    MfaRequired,
+    PasskeyRejected,
+    ProviderUnsupported,
     // Supabase
     SupabaseHttpError,
     SupabaseNetworkError,
@@ -15,39 +29,90 @@ pub enum ErrorCode {
     SupabaseTimeout,
     // Validation
     ValidationFailed,
+    // Internal
+    Internal,
+    StorageFailed,
 }
 
 impl ErrorCode {
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::InvalidCredentials => codes::AUTH_INVALID_CREDENTIALS,
+            Self::RateLimited => codes::RATE_LIMITED,
+            Self::AccountLocked => codes::ACCOUNT_LOCKED,
+            Self::Unauthenticated => codes::AUTH_UNAUTHENTICATED,
+            Self::InsufficientRole => codes::AUTH_INSUFFICIENT_ROLE,
+            Self::Forbidden => codes::FORBIDDEN,
+            Self::AppAccessDenied => codes::APP_ACCESS_DENIED,
+            Self::AppTrialExpired => codes::APP_TRIAL_EXPIRED,
+            Self::InvalidResetToken => codes::AUTH_INVALID_RESET_TOKEN,
+            Self::InvalidOtp => codes::AUTH_INVALID_OTP,
+            Self::OAuthState => codes::AUTH_OAUTH_STATE,
+            Self::OAuthFailed => codes::AUTH_OAUTH_FAILED,
+            Self::MfaRequired => codes::AUTH_MFA_REQUIRED,
+            Self::PasskeyRejected => codes::AUTH_PASSKEY_REJECTED,
+            Self::ProviderUnsupported => codes::AUTH_PROVIDER_UNSUPPORTED,
             Self::SupabaseHttpError => codes::SUPABASE_HTTP_ERROR,
             Self::SupabaseNetworkError => codes::SUPABASE_NETWORK_ERROR,
             Self::SupabaseParseError => codes::SUPABASE_PARSE_ERROR,
             Self::SupabaseTimeout => codes::SUPABASE_TIMEOUT,
             Self::ValidationFailed => codes::VALIDATION_FAILED,
+            Self::Internal => codes::INTERNAL_ERROR,
+            Self::StorageFailed => codes::STORAGE_ERROR,
         }
     }
 
     pub fn message(&self) -> &'static str {
         match self {
             Self::InvalidCredentials => messages::AUTH_INVALID_CREDENTIALS,
+            Self::RateLimited => messages::RATE_LIMITED,
+            Self::AccountLocked => messages::ACCOUNT_LOCKED,
+            Self::Unauthenticated => messages::AUTH_UNAUTHENTICATED,
+            Self::InsufficientRole => messages::AUTH_INSUFFICIENT_ROLE,
+            Self::Forbidden => messages::FORBIDDEN,
+            Self::AppAccessDenied => messages::APP_ACCESS_DENIED,
+            Self::AppTrialExpired => messages::APP_TRIAL_EXPIRED,
+            Self::InvalidResetToken => messages::AUTH_INVALID_RESET_TOKEN,
+            Self::InvalidOtp => messages::AUTH_INVALID_OTP,
+            Self::OAuthState => messages::AUTH_OAUTH_STATE,
+            Self::OAuthFailed => messages::AUTH_OAUTH_FAILED,
+            Self::MfaRequired => messages::AUTH_MFA_REQUIRED,
+            Self::PasskeyRejected => messages::AUTH_PASSKEY_REJECTED,
+            Self::ProviderUnsupported => messages::AUTH_PROVIDER_UNSUPPORTED,
             Self::SupabaseHttpError => messages::SUPABASE_HTTP_ERROR,
             Self::SupabaseNetworkError => messages::SUPABASE_NETWORK_ERROR,
             Self::SupabaseParseError => messages::SUPABASE_PARSE_ERROR,
             Self::SupabaseTimeout => messages::SUPABASE_TIMEOUT,
             Self::ValidationFailed => messages::VALIDATION_FAILED,
+            Self::Internal => messages::INTERNAL_ERROR,
+            Self::StorageFailed => messages::STORAGE_ERROR,
         }
     }
 
     pub fn status(&self) -> StatusCode {
         match self {
             Self::InvalidCredentials => status::AUTH_INVALID_CREDENTIALS,
+            Self::RateLimited => status::RATE_LIMITED,
+            Self::AccountLocked => status::ACCOUNT_LOCKED,
+            Self::Unauthenticated => status::AUTH_UNAUTHENTICATED,
+            Self::InsufficientRole => status::AUTH_INSUFFICIENT_ROLE,
+            Self::Forbidden => status::FORBIDDEN,
+            Self::AppAccessDenied => status::APP_ACCESS_DENIED,
+            Self::AppTrialExpired => status::APP_TRIAL_EXPIRED,
+            Self::InvalidResetToken => status::AUTH_INVALID_RESET_TOKEN,
+            Self::InvalidOtp => status::AUTH_INVALID_OTP,
+            Self::OAuthState => status::AUTH_OAUTH_STATE,
+            Self::OAuthFailed => status::AUTH_OAUTH_FAILED,
+            Self::MfaRequired => status::AUTH_MFA_REQUIRED,
+            Self::PasskeyRejected => status::AUTH_PASSKEY_REJECTED,
+            Self::ProviderUnsupported => status::AUTH_PROVIDER_UNSUPPORTED,
             Self::SupabaseHttpError => status::SUPABASE_HTTP_ERROR,
             Self::SupabaseNetworkError => status::SUPABASE_NETWORK_ERROR,
             Self::SupabaseParseError => status::SUPABASE_PARSE_ERROR,
             Self::SupabaseTimeout => status::SUPABASE_TIMEOUT,
             Self::ValidationFailed => status::VALIDATION_FAILED,
+            Self::Internal => status::INTERNAL_ERROR,
+            Self::StorageFailed => status::STORAGE_ERROR,
         }
     }
 }
@@ -0,0 +1,43 @@
+//! JWT error - Bearer token verification failures (not exposed to clients)
+
+use std::fmt;
+
+/// Why a JWT was not accepted
+#[derive(Debug)]
+pub enum JwtError {
+    /// Bad signature, expired, wrong audience, malformed...
+    Invalid(jsonwebtoken::errors::Error),
+    /// No configured key matches the token (`kid` or algorithm)
+    UnknownKey(Option<String>),
+    /// Token is valid but carries a role we don't accept
+    RoleNotAllowed(String),
+    /// JWKS document could not be loaded
+    Jwks(String),
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(e) => write!(f, "Invalid JWT: {}", e),
+            Self::UnknownKey(Some(kid)) => write!(f, "No verification key for kid '{}'", kid),
+            Self::UnknownKey(None) => write!(f, "No verification key for this JWT"),
+            Self::RoleNotAllowed(role) => write!(f, "JWT role '{}' not allowed", role),
+            Self::Jwks(msg) => write!(f, "JWKS error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for JwtError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Invalid(e) => Some(e),
+            Self::UnknownKey(_) | Self::RoleNotAllowed(_) | Self::Jwks(_) => None,
+        }
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for JwtError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        Self::Invalid(err)
+    }
+}
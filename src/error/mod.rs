@@ -9,16 +9,25 @@
 //! - `auth.rs` - Service layer errors
 //! - `app.rs` - Application layer errors + ResponseError
 //! - `response.rs` - JSON error response structure
+//! - `storage.rs` - Persistence layer errors (not exposed to clients)
+//! - `jwt.rs` - Bearer token verification errors (not exposed to clients)
+//! - `webauthn.rs` - Passkey ceremony verification errors (not exposed to clients)
 
 mod app;
 mod auth;
 mod code;
+mod jwt;
 mod response;
+mod storage;
 mod supabase;
+mod webauthn;
 
 // Re-export all public types
 pub use app::{AppError, AppResult};
 pub use auth::AuthError;
 pub use code::ErrorCode;
+pub use jwt::JwtError;
 pub use response::ErrorResponse;
+pub use storage::StorageError;
 pub use supabase::SupabaseError;
+pub use webauthn::WebAuthnError;
@@ -0,0 +1,46 @@
+//! Storage error - Persistence layer errors (files, embedded databases)
+
+use std::fmt;
+
+/// Errors from local persistence backends
+#[derive(Debug)]
+pub enum StorageError {
+    /// Filesystem error
+    Io(std::io::Error),
+    /// Embedded SQLite error
+    Sqlite(rusqlite::Error),
+    /// File exists but its format is unknown or unsupported
+    Format(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Storage I/O error: {}", e),
+            Self::Sqlite(e) => write!(f, "Storage SQLite error: {}", e),
+            Self::Format(msg) => write!(f, "Storage format error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Sqlite(e) => Some(e),
+            Self::Format(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Sqlite(err)
+    }
+}
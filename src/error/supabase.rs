@@ -28,7 +28,9 @@ impl SupabaseError {
             return Self::Network(err);
         }
         if err.is_decode() {
-            return Self::Parse { body: String::new() };
+            return Self::Parse {
+                body: String::new(),
+            };
         }
         if let Some(s) = err.status() {
             return Self::Http {
@@ -67,6 +69,24 @@ impl SupabaseError {
         }
     }
 
+    /// Supabase definitively refused the request (e.g. revoked or reused refresh token),
+    /// as opposed to a transient failure worth retrying
+    pub fn is_rejection(&self) -> bool {
+        matches!(self, Self::Http { status, .. }
+            if status.is_client_error() && *status != StatusCode::TOO_MANY_REQUESTS)
+    }
+
+    /// Check a reqwest Response for success, discarding its body
+    pub async fn check_response(response: reqwest::Response) -> Result<(), SupabaseError> {
+        let status = response.status();
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Self::http(status, body));
+        }
+        Ok(())
+    }
+
     /// Parse a reqwest Response into the expected type T
     pub async fn parse_response<T: DeserializeOwned>(
         response: reqwest::Response,
@@ -0,0 +1,49 @@
+//! WebAuthn error - Passkey ceremony verification failures (not exposed to clients)
+
+use std::fmt;
+
+/// Why a passkey registration or assertion was not accepted
+#[derive(Debug)]
+pub enum WebAuthnError {
+    /// Client data, attestation object or authenticator data could not be decoded
+    Malformed(&'static str),
+    /// `clientDataJSON.type` is not the one of this ceremony
+    Type { expected: &'static str },
+    /// The signed challenge is not the one issued for this ceremony
+    Challenge,
+    /// The browser ran the ceremony on an origin we don't serve
+    Origin(String),
+    /// The credential is scoped to another relying party
+    RpId,
+    /// The authenticator did not check for the user's presence or identity
+    UserVerification,
+    /// Only ES256 (P-256) credentials are supported
+    UnsupportedKey,
+    /// Assertion signature does not match the stored public key
+    Signature,
+    /// Signature counter went backwards: the authenticator may have been cloned
+    Counter { stored: u32, received: u32 },
+    /// No passkey with this credential ID
+    UnknownCredential,
+}
+
+impl fmt::Display for WebAuthnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(what) => write!(f, "Malformed {}", what),
+            Self::Type { expected } => write!(f, "Client data type is not '{}'", expected),
+            Self::Challenge => write!(f, "Challenge mismatch"),
+            Self::Origin(origin) => write!(f, "Origin '{}' not allowed", origin),
+            Self::RpId => write!(f, "RP ID hash mismatch"),
+            Self::UserVerification => write!(f, "User presence or verification missing"),
+            Self::UnsupportedKey => write!(f, "Unsupported credential key (ES256 only)"),
+            Self::Signature => write!(f, "Invalid assertion signature"),
+            Self::Counter { stored, received } => {
+                write!(f, "Signature counter went from {} to {}", stored, received)
+            }
+            Self::UnknownCredential => write!(f, "Unknown credential"),
+        }
+    }
+}
+
+impl std::error::Error for WebAuthnError {}
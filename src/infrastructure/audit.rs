@@ -0,0 +1,230 @@
+//! JSONL audit log - One JSON object per line, only ever appended to
+//!
+//! Queries scan the whole file: fine for the volume of auth events, rotate the file
+//! (e.g. with logrotate `copytruncate`) to keep them fast.
+//!
+//! Appends are handed to a writer thread, so request handlers never wait on `fsync`; queries
+//! read the file through their own handle and never block appends.
+
+use crate::domain::{AuditEntry, AuditLog, AuditQuery};
+use crate::error::StorageError;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use tracing::{error, info, warn};
+
+/// One line of the log
+#[derive(Debug, Serialize, Deserialize)]
+struct AuditRecord {
+    at: u64,
+    action: String,
+    outcome: String,
+    user_id: Option<String>,
+    email: Option<String>,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    detail: Option<String>,
+}
+
+impl From<&AuditEntry> for AuditRecord {
+    fn from(e: &AuditEntry) -> Self {
+        Self {
+            at: e.at,
+            action: e.action.as_str().to_string(),
+            outcome: e.outcome.as_str().to_string(),
+            user_id: e.user_id.clone(),
+            email: e.email.clone(),
+            ip: e.ip.clone(),
+            user_agent: e.user_agent.clone(),
+            detail: e.detail.clone(),
+        }
+    }
+}
+
+impl TryFrom<AuditRecord> for AuditEntry {
+    type Error = String;
+
+    fn try_from(r: AuditRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            at: r.at,
+            action: r.action.parse()?,
+            outcome: r.outcome.parse()?,
+            user_id: r.user_id,
+            email: r.email,
+            ip: r.ip,
+            user_agent: r.user_agent,
+            detail: r.detail,
+        })
+    }
+}
+
+/// Where the lines go
+#[derive(Debug)]
+enum Sink {
+    File {
+        path: PathBuf,
+        writer: Writer,
+    },
+    /// `:memory:` - lost on exit, for tests
+    Memory(Mutex<Vec<u8>>),
+}
+
+/// Work for the writer thread
+#[derive(Debug)]
+enum Op {
+    Append(Vec<u8>),
+    /// Answered once every line queued before it is on disk
+    Flush(Sender<()>),
+}
+
+/// Handle on the thread that owns the file, joined on drop so no queued line is lost
+#[derive(Debug)]
+struct Writer {
+    queue: Option<Sender<Op>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Writer {
+    fn spawn(file: File) -> Result<Self, StorageError> {
+        let (queue, ops) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name("audit-writer".to_string())
+            .spawn(move || write_loop(file, ops))?;
+        Ok(Self {
+            queue: Some(queue),
+            thread: Some(thread),
+        })
+    }
+
+    fn send(&self, op: Op) -> Result<(), StorageError> {
+        self.queue
+            .as_ref()
+            .and_then(|queue| queue.send(op).ok())
+            .ok_or_else(|| std::io::Error::other("Audit log writer stopped").into())
+    }
+
+    /// Wait until every line appended so far is on disk
+    fn flush(&self) -> Result<(), StorageError> {
+        let (done, wait) = mpsc::channel();
+        self.send(Op::Flush(done))?;
+        wait.recv()
+            .map_err(|_| std::io::Error::other("Audit log writer stopped").into())
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        // Closing the queue lets the thread write what is left and return
+        drop(self.queue.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Write queued lines, one `fsync` per batch of lines that arrived together
+fn write_loop(mut file: File, ops: Receiver<Op>) {
+    while let Ok(op) = ops.recv() {
+        let mut waiting = Vec::new();
+        let mut written = false;
+        for op in std::iter::once(op).chain(ops.try_iter()) {
+            match op {
+                // One write per entry so lines never interleave, even with other writers
+                Op::Append(line) => match file.write_all(&line) {
+                    Ok(()) => written = true,
+                    Err(e) => error!(error = %e, "Failed to write audit entry"),
+                },
+                Op::Flush(done) => waiting.push(done),
+            }
+        }
+        if written && let Err(e) = file.sync_data() {
+            error!(error = %e, "Failed to sync audit log");
+        }
+        for done in waiting {
+            let _ = done.send(());
+        }
+    }
+}
+
+/// Append-only JSONL audit log
+#[derive(Debug)]
+pub struct JsonlAuditLog {
+    sink: Sink,
+}
+
+impl JsonlAuditLog {
+    /// Open (or create) the log at `path`, `:memory:` for a private in-memory log
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        if path == ":memory:" {
+            return Ok(Self {
+                sink: Sink::Memory(Mutex::new(Vec::new())),
+            });
+        }
+
+        let path = PathBuf::from(path);
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let writer = Writer::spawn(file)?;
+
+        info!(path = %path.display(), "Audit log opened");
+        Ok(Self {
+            sink: Sink::File { path, writer },
+        })
+    }
+}
+
+impl AuditLog for JsonlAuditLog {
+    fn append(&self, entry: &AuditEntry) -> Result<(), StorageError> {
+        let mut line =
+            serde_json::to_vec(&AuditRecord::from(entry)).map_err(std::io::Error::other)?;
+        line.push(b'\n');
+
+        match &self.sink {
+            Sink::File { writer, .. } => writer.send(Op::Append(line)),
+            Sink::Memory(buf) => {
+                buf.lock().unwrap().extend_from_slice(&line);
+                Ok(())
+            }
+        }
+    }
+
+    /// Sees every entry appended before the call
+    fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, StorageError> {
+        let lines: Box<dyn Iterator<Item = std::io::Result<String>>> = match &self.sink {
+            Sink::File { path, writer } => {
+                writer.flush()?;
+                Box::new(BufReader::new(File::open(path)?).lines())
+            }
+            Sink::Memory(buf) => Box::new(Cursor::new(buf.lock().unwrap().clone()).lines()),
+        };
+
+        let mut entries = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str::<AuditRecord>(&line)
+                .map_err(|e| e.to_string())
+                .and_then(AuditEntry::try_from);
+            match entry {
+                Ok(entry) if query.matches(&entry) => entries.push(entry),
+                Ok(_) => {}
+                // A torn last line (crash mid-append, write in progress) must not hide the rest of the log
+                Err(reason) => {
+                    warn!(line = i + 1, reason = %reason, "Skipping corrupt audit entry")
+                }
+            }
+        }
+
+        entries.reverse();
+        entries.truncate(query.limit);
+        Ok(entries)
+    }
+}
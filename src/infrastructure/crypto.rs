@@ -0,0 +1,145 @@
+//! Token cipher - AES-256-GCM encryption of secrets at rest, with key-id tagging for rotation
+//!
+//! Sealed format: `enc:v1:<key_id>:<base64(nonce || ciphertext || tag)>`.
+//! The key ID selects the decryption key, so old keys can stay configured for reading
+//! while every new write uses the active key.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as B64;
+use std::collections::HashMap;
+use std::fmt;
+
+const PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+/// Result of opening a stored value
+#[derive(Debug)]
+pub struct Opened {
+    pub plaintext: String,
+    /// Stored in plaintext or under a non-active key - should be re-sealed
+    pub stale: bool,
+}
+
+/// Authenticated encryption keyed by named 256-bit keys
+#[derive(Clone)]
+pub struct TokenCipher {
+    active: String,
+    keys: HashMap<String, Aes256Gcm>,
+}
+
+impl TokenCipher {
+    /// Build from `id:base64key` pairs separated by commas; `active` must be one of the IDs
+    pub fn from_spec(spec: &str, active: &str) -> Result<Self, String> {
+        let mut keys = HashMap::new();
+
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, encoded) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("key entry '{}' is not id:base64", entry))?;
+            if id.is_empty() || id.contains(':') {
+                return Err(format!("invalid key id '{}'", id));
+            }
+
+            let raw = B64
+                .decode(encoded)
+                .map_err(|_| format!("key '{}' is not valid base64", id))?;
+            let cipher = Aes256Gcm::new_from_slice(&raw)
+                .map_err(|_| format!("key '{}' must be 32 bytes, got {}", id, raw.len()))?;
+
+            keys.insert(id.to_string(), cipher);
+        }
+
+        if !keys.contains_key(active) {
+            return Err(format!(
+                "active key '{}' is not among the configured keys",
+                active
+            ));
+        }
+
+        Ok(Self {
+            active: active.to_string(),
+            keys,
+        })
+    }
+
+    /// Encrypt with the active key. `context` is bound as associated data
+    /// (e.g. session ID + field name) so ciphertexts can't be swapped between rows.
+    pub fn seal(&self, plaintext: &str, context: &str) -> String {
+        let cipher = &self.keys[&self.active];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: plaintext.as_bytes(),
+            aad: context.as_bytes(),
+        };
+
+        // Only fails on inputs larger than GCM's ~64 GiB limit
+        let ciphertext = cipher
+            .encrypt(&nonce, payload)
+            .expect("AES-GCM encryption failed");
+
+        let mut blob = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&ciphertext);
+
+        format!("{}{}:{}", PREFIX, self.active, B64.encode(blob))
+    }
+
+    /// Decrypt a sealed value. Unsealed values are passed through as stale plaintext
+    /// so sessions written before encryption was enabled can be migrated.
+    pub fn open(&self, value: &str, context: &str) -> Result<Opened, String> {
+        let Some(rest) = value.strip_prefix(PREFIX) else {
+            return Ok(Opened {
+                plaintext: value.to_string(),
+                stale: true,
+            });
+        };
+
+        let (key_id, encoded) = rest.split_once(':').ok_or("malformed sealed value")?;
+        let cipher = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| format!("unknown key id '{}'", key_id))?;
+
+        let blob = B64
+            .decode(encoded)
+            .map_err(|_| "sealed value is not valid base64")?;
+        if blob.len() < NONCE_LEN {
+            return Err("sealed value too short".to_string());
+        }
+        let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: context.as_bytes(),
+                },
+            )
+            .map_err(|_| format!("authentication failed with key '{}'", key_id))?;
+
+        Ok(Opened {
+            plaintext: String::from_utf8(plaintext).map_err(|_| "decrypted value is not UTF-8")?,
+            stale: key_id != self.active,
+        })
+    }
+
+    /// ID of the key used for new encryptions
+    pub fn active_key_id(&self) -> &str {
+        &self.active
+    }
+}
+
+// Never print key material
+impl fmt::Debug for TokenCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids: Vec<&String> = self.keys.keys().collect();
+        ids.sort();
+        f.debug_struct("TokenCipher")
+            .field("active", &self.active)
+            .field("keys", &ids)
+            .finish()
+    }
+}
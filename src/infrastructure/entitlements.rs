@@ -0,0 +1,120 @@
+//! SQLite entitlement store - Access of users to apps
+
+use crate::domain::{AppId, Entitlement, EntitlementStatus, EntitlementStore};
+use crate::error::StorageError;
+use rusqlite::{Connection, Row, params};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::info;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entitlements (
+    app_id     TEXT NOT NULL,
+    user_id    TEXT NOT NULL,
+    status     TEXT NOT NULL,
+    expires_at INTEGER,
+    updated_by TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (app_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_entitlements_user ON entitlements(user_id);
+";
+
+const COLUMNS: &str = "app_id, user_id, status, expires_at, updated_by, updated_at";
+
+/// Entitlements in SQLite
+#[derive(Debug)]
+pub struct SqliteEntitlements {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteEntitlements {
+    /// Open (or create) the database at `path`, `:memory:` for a private in-memory db
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        if let Some(dir) = Path::new(path)
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+
+        info!(path = %path, "Entitlement database opened");
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn query(&self, filter: &str, key: &[&str]) -> Result<Vec<Entitlement>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM entitlements WHERE {} ORDER BY updated_at DESC, app_id, user_id",
+            COLUMNS, filter
+        ))?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(key), row_to_entitlement)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+}
+
+fn row_to_entitlement(row: &Row<'_>) -> rusqlite::Result<Entitlement> {
+    Ok(Entitlement {
+        app_id: row.get("app_id")?,
+        user_id: row.get("user_id")?,
+        // Unknown states (written by a newer release) fall back to no access
+        status: row
+            .get::<_, String>("status")?
+            .parse()
+            .unwrap_or(EntitlementStatus::Revoked),
+        expires_at: row.get("expires_at")?,
+        updated_by: row.get("updated_by")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+impl EntitlementStore for SqliteEntitlements {
+    fn get(&self, app_id: AppId, user_id: &str) -> Result<Option<Entitlement>, StorageError> {
+        Ok(self
+            .query("app_id = ?1 AND user_id = ?2", &[app_id, user_id])?
+            .pop())
+    }
+
+    fn list_app(&self, app_id: AppId) -> Result<Vec<Entitlement>, StorageError> {
+        self.query("app_id = ?1", &[app_id])
+    }
+
+    fn list_user(&self, user_id: &str) -> Result<Vec<Entitlement>, StorageError> {
+        self.query("user_id = ?1", &[user_id])
+    }
+
+    fn put(&self, e: &Entitlement) -> Result<(), StorageError> {
+        self.conn.lock().unwrap().execute(
+            &format!(
+                "INSERT OR REPLACE INTO entitlements ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                COLUMNS
+            ),
+            params![
+                e.app_id,
+                e.user_id,
+                e.status.as_str(),
+                e.expires_at,
+                e.updated_by,
+                e.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove_user(&self, user_id: &str) -> Result<usize, StorageError> {
+        let removed = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM entitlements WHERE user_id = ?1", [user_id])?;
+        Ok(removed)
+    }
+}
@@ -0,0 +1,284 @@
+//! JWT verifier - Authenticate Supabase access tokens locally, without a round trip
+//!
+//! Keys:
+//! - HS256 with the project's JWT secret (`SP_JWT_SECRET`)
+//! - Asymmetric keys (RS*, ES*, PS*, EdDSA) from a JWKS document (`SP_JWKS`), read from a file
+//!   or fetched from a URL. URL documents are fetched on first use and re-fetched when a token
+//!   names an unknown `kid` (key rotation), at most once per `JWKS_REFETCH_COOLDOWN`.
+//!
+//! Checks signature, `exp` (with leeway), `aud` and `role`.
+
+use crate::config::Config;
+use crate::domain::{Aal, User};
+use crate::error::JwtError;
+use crate::infrastructure::supabase::app_metadata_roles;
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Minimum delay between two JWKS downloads
+const JWKS_REFETCH_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Clock skew tolerated on `exp` (seconds)
+const LEEWAY_SECS: u64 = 30;
+
+/// Claims of a Supabase access token that we use
+#[derive(Debug, Deserialize)]
+pub struct SupabaseClaims {
+    pub sub: String,
+    pub exp: u64,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub role: String,
+    #[serde(default)]
+    pub user_metadata: serde_json::Value,
+    #[serde(default)]
+    pub app_metadata: serde_json::Value,
+    /// Authenticator assurance level, `aal2` once a second factor was verified
+    #[serde(default)]
+    pub aal: String,
+}
+
+impl SupabaseClaims {
+    /// Assurance level of the token, `aal1` when the claim is missing or unknown
+    pub fn aal(&self) -> Aal {
+        self.aal.parse().unwrap_or_default()
+    }
+
+    /// Domain user for a verified token (no refresh token: the client owns the Supabase session)
+    pub fn into_user(self, access_token: &str) -> User {
+        let username = self
+            .user_metadata
+            .get("username")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        User {
+            id: self.sub,
+            email: self.email,
+            username,
+            role: self.role,
+            roles: app_metadata_roles(&self.app_metadata),
+            access_token: access_token.to_string(),
+            refresh_token: String::new(),
+            expires_at: self.exp,
+        }
+    }
+}
+
+/// Where the JWKS document comes from
+#[derive(Debug)]
+enum JwksSource {
+    File(String),
+    Url(String),
+}
+
+/// Verifies Supabase JWTs against the configured keys
+pub struct JwtVerifier {
+    hmac: Option<DecodingKey>,
+    jwks_source: Option<JwksSource>,
+    // kid -> key, from the JWKS document
+    jwks: RwLock<HashMap<String, DecodingKey>>,
+    last_fetch: Mutex<Option<Instant>>,
+    audience: Vec<String>,
+    roles: Vec<String>,
+}
+
+impl JwtVerifier {
+    /// Build from config, `None` when neither a secret nor a JWKS is configured
+    /// Panics on an unreadable JWKS file, like any other invalid configuration
+    pub fn from_config(cfg: &Config) -> Option<Self> {
+        let secret = cfg.sp_jwt_secret.trim();
+        let jwks = cfg.sp_jwks.trim();
+        if secret.is_empty() && jwks.is_empty() {
+            return None;
+        }
+
+        let verifier = Self::new(
+            (!secret.is_empty()).then_some(secret),
+            (!jwks.is_empty()).then_some(jwks),
+            split_list(&cfg.sp_jwt_audience),
+            split_list(&cfg.sp_jwt_roles),
+        )
+        .unwrap_or_else(|e| panic!("SP_JWKS is invalid: {}", e));
+
+        info!(
+            hs256 = verifier.hmac.is_some(),
+            jwks = ?verifier.jwks_source,
+            audience = ?verifier.audience,
+            roles = ?verifier.roles,
+            "JWT verifier initialized"
+        );
+        Some(verifier)
+    }
+
+    /// `jwks` is an `http(s)://` URL or a file path; file documents are loaded immediately
+    pub fn new(
+        secret: Option<&str>,
+        jwks: Option<&str>,
+        audience: Vec<String>,
+        roles: Vec<String>,
+    ) -> Result<Self, JwtError> {
+        let jwks_source = jwks.map(|j| {
+            if j.starts_with("http://") || j.starts_with("https://") {
+                JwksSource::Url(j.to_string())
+            } else {
+                JwksSource::File(j.to_string())
+            }
+        });
+
+        let verifier = Self {
+            hmac: secret.map(|s| DecodingKey::from_secret(s.as_bytes())),
+            jwks_source,
+            jwks: RwLock::new(HashMap::new()),
+            last_fetch: Mutex::new(None),
+            audience,
+            roles,
+        };
+
+        if let Some(JwksSource::File(path)) = &verifier.jwks_source {
+            let json = std::fs::read_to_string(path)
+                .map_err(|e| JwtError::Jwks(format!("{}: {}", path, e)))?;
+            verifier.install_jwks(&json)?;
+        }
+        Ok(verifier)
+    }
+
+    /// Verify a token and return its claims
+    pub async fn verify(&self, token: &str) -> Result<SupabaseClaims, JwtError> {
+        let header = decode_header(token)?;
+
+        let claims = match header.alg {
+            Algorithm::HS256 => {
+                let key = self.hmac.as_ref().ok_or(JwtError::UnknownKey(None))?;
+                self.decode(token, key, header.alg)?
+            }
+            // Other HMAC variants are never issued by Supabase
+            Algorithm::HS384 | Algorithm::HS512 => return Err(JwtError::UnknownKey(None)),
+            alg => {
+                let kid = header.kid.ok_or(JwtError::UnknownKey(None))?;
+                if !self.jwks.read().unwrap().contains_key(&kid) {
+                    self.refetch_jwks().await;
+                }
+
+                let keys = self.jwks.read().unwrap();
+                let key = keys
+                    .get(&kid)
+                    .ok_or(JwtError::UnknownKey(Some(kid.clone())))?;
+                self.decode(token, key, alg)?
+            }
+        };
+
+        if !self.roles.is_empty() && !self.roles.contains(&claims.role) {
+            return Err(JwtError::RoleNotAllowed(claims.role));
+        }
+        Ok(claims)
+    }
+
+    /// Signature, `exp` and `aud` checks for one algorithm
+    /// The key's family must match `alg`, so an RSA key can't be used as an HMAC secret
+    fn decode(
+        &self,
+        token: &str,
+        key: &DecodingKey,
+        alg: Algorithm,
+    ) -> Result<SupabaseClaims, JwtError> {
+        let mut validation = Validation::new(alg);
+        validation.leeway = LEEWAY_SECS;
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if self.audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&self.audience);
+        }
+
+        Ok(decode::<SupabaseClaims>(token, key, &validation)?.claims)
+    }
+
+    /// Download the JWKS again if it comes from a URL and the cooldown has passed
+    async fn refetch_jwks(&self) {
+        let Some(JwksSource::Url(url)) = &self.jwks_source else {
+            return;
+        };
+
+        {
+            let mut last = self.last_fetch.lock().unwrap();
+            if last.is_some_and(|t| t.elapsed() < JWKS_REFETCH_COOLDOWN) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+
+        let fetched = async {
+            let response = reqwest::get(url)
+                .await
+                .map_err(|e| JwtError::Jwks(e.to_string()))?;
+            let body = response
+                .error_for_status()
+                .map_err(|e| JwtError::Jwks(e.to_string()))?
+                .text()
+                .await
+                .map_err(|e| JwtError::Jwks(e.to_string()))?;
+            self.install_jwks(&body)
+        };
+
+        match fetched.await {
+            Ok(count) => info!(url = %url, keys = count, "JWKS fetched"),
+            Err(e) => warn!(url = %url, error = %e, "Failed to fetch JWKS"),
+        }
+    }
+
+    /// Replace the key set with the asymmetric keys of a JWKS document
+    /// Symmetric (`oct`) keys and keys without a `kid` are ignored
+    fn install_jwks(&self, json: &str) -> Result<usize, JwtError> {
+        let set: JwkSet = serde_json::from_str(json).map_err(|e| JwtError::Jwks(e.to_string()))?;
+
+        let mut keys = HashMap::new();
+        for jwk in &set.keys {
+            if matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_)) {
+                continue;
+            }
+            let Some(kid) = jwk.common.key_id.clone() else {
+                continue;
+            };
+            match DecodingKey::from_jwk(jwk) {
+                Ok(key) => {
+                    keys.insert(kid, key);
+                }
+                Err(e) => warn!(kid = %kid, error = %e, "Skipping unusable JWK"),
+            }
+        }
+
+        let count = keys.len();
+        *self.jwks.write().unwrap() = keys;
+        Ok(count)
+    }
+}
+
+impl fmt::Debug for JwtVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtVerifier")
+            .field("hs256", &self.hmac.is_some())
+            .field("jwks", &self.jwks_source)
+            .field("audience", &self.audience)
+            .field("roles", &self.roles)
+            .finish()
+    }
+}
+
+/// Comma-separated config list, empty entries dropped
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+        .collect()
+}
@@ -4,6 +4,29 @@
 //! third-party APIs, message queues, etc. It translates between external
 //! formats and domain types.
 
+pub mod audit;
+pub mod crypto;
+pub mod entitlements;
+pub mod jwt;
+pub mod oauth;
+pub mod passkey;
+pub mod pending;
+pub mod provider;
+pub mod roles;
+pub mod session;
 pub mod supabase;
+pub mod throttle;
+pub mod webauthn;
 
-pub use supabase::SupabaseClient;
+pub use audit::JsonlAuditLog;
+pub use entitlements::SqliteEntitlements;
+pub use jwt::JwtVerifier;
+pub use oauth::OAuthFlows;
+pub use passkey::SqlitePasskeys;
+pub use pending::PendingStore;
+pub use provider::{AuthProviderKind, LocalProvider, SupabaseProvider};
+pub use roles::SqliteRoles;
+pub use session::{SessionBackendKind, backend_from_config};
+pub use supabase::{SupabaseAdmin, SupabaseClient};
+pub use throttle::SqliteAttempts;
+pub use webauthn::RelyingParty;
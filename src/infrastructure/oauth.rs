@@ -0,0 +1,58 @@
+//! OAuth PKCE flows - Verifiers kept server-side between the redirect and the callback
+
+use super::pending::PendingStore;
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use sha2::{Digest, Sha256};
+
+/// A sign-in started with `OAuthFlows::start`, waiting for its callback
+#[derive(Debug, Clone)]
+pub struct OAuthFlow {
+    pub provider: String,
+    /// PKCE code verifier, never leaves the server
+    pub verifier: String,
+}
+
+/// Pending OAuth sign-ins keyed by flow ID (the value of the flow cookie)
+#[derive(Debug, Clone)]
+pub struct OAuthFlows {
+    flows: PendingStore<OAuthFlow>,
+}
+
+impl OAuthFlows {
+    pub fn new(ttl: u64) -> Self {
+        Self {
+            flows: PendingStore::new(ttl),
+        }
+    }
+
+    /// Start a flow: returns its ID and the S256 code challenge to send to the provider
+    pub fn start(&self, provider: &str) -> (String, String) {
+        let verifier = random_verifier();
+        let challenge = code_challenge(&verifier);
+        let id = self.flows.insert(OAuthFlow {
+            provider: provider.to_string(),
+            verifier,
+        });
+        (id, challenge)
+    }
+
+    /// Remove and return a live flow; each flow can be completed once
+    pub fn take(&self, id: &str) -> Option<OAuthFlow> {
+        self.flows.take(id)
+    }
+}
+
+/// 32 random bytes, base64url: 43 characters, within RFC 7636's 43-128
+fn random_verifier() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// `BASE64URL(SHA256(verifier))`, the S256 method
+pub fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
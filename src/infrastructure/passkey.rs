@@ -0,0 +1,153 @@
+//! SQLite passkey backend - Registered credentials in an embedded database
+
+use crate::domain::{Passkey, PasskeyBackend};
+use crate::error::StorageError;
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::info;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS passkeys (
+    credential_id TEXT PRIMARY KEY,
+    user_id       TEXT NOT NULL,
+    name          TEXT NOT NULL,
+    public_key    BLOB NOT NULL,
+    sign_count    INTEGER NOT NULL,
+    created_at    INTEGER NOT NULL,
+    last_used_at  INTEGER,
+    registered_by TEXT
+);
+CREATE INDEX IF NOT EXISTS passkeys_user_id ON passkeys (user_id);
+";
+
+/// Columns added after the first release, with their definition for `ALTER TABLE`
+const MIGRATIONS: &[(&str, &str)] = &[("registered_by", "TEXT")];
+
+/// Passkeys in SQLite - small and rarely written, so no write-behind as for sessions
+#[derive(Debug)]
+pub struct SqlitePasskeys {
+    conn: Mutex<Connection>,
+}
+
+impl SqlitePasskeys {
+    /// Open (or create) the database at `path`, `:memory:` for a private in-memory db
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        if let Some(dir) = Path::new(path)
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Self::migrate(&conn)?;
+
+        info!(path = %path, "Passkey database opened");
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Add the columns a database created by an older release lacks
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        for (column, definition) in MIGRATIONS {
+            let exists: bool = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('passkeys') WHERE name = ?1",
+                [column],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE passkeys ADD COLUMN {} {}",
+                    column, definition
+                ))?;
+                info!(column = %column, "Passkey database migrated");
+            }
+        }
+        Ok(())
+    }
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Passkey> {
+        Ok(Passkey {
+            id: row.get("credential_id")?,
+            user_id: row.get("user_id")?,
+            name: row.get("name")?,
+            public_key: row.get("public_key")?,
+            sign_count: row.get("sign_count")?,
+            created_at: row.get("created_at")?,
+            last_used_at: row.get("last_used_at")?,
+            registered_by: row.get("registered_by")?,
+        })
+    }
+}
+
+impl PasskeyBackend for SqlitePasskeys {
+    fn insert(&self, p: &Passkey) -> Result<(), StorageError> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO passkeys (credential_id, user_id, name, public_key, sign_count,
+                created_at, last_used_at, registered_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                p.id,
+                p.user_id,
+                p.name,
+                p.public_key,
+                p.sign_count,
+                p.created_at,
+                p.last_used_at,
+                p.registered_by,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Passkey>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let passkey = conn
+            .query_row(
+                "SELECT * FROM passkeys WHERE credential_id = ?1",
+                [id],
+                Self::from_row,
+            )
+            .optional()?;
+        Ok(passkey)
+    }
+
+    fn list(&self, user_id: &str) -> Result<Vec<Passkey>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT * FROM passkeys WHERE user_id = ?1 ORDER BY created_at")?;
+        let passkeys = stmt
+            .query_map([user_id], Self::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(passkeys)
+    }
+
+    fn touch(&self, id: &str, sign_count: u32, used_at: u64) -> Result<(), StorageError> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE passkeys SET sign_count = ?2, last_used_at = ?3 WHERE credential_id = ?1",
+            params![id, sign_count, used_at],
+        )?;
+        Ok(())
+    }
+
+    fn remove(&self, user_id: &str, id: &str) -> Result<bool, StorageError> {
+        let removed = self.conn.lock().unwrap().execute(
+            "DELETE FROM passkeys WHERE credential_id = ?1 AND user_id = ?2",
+            [id, user_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    fn remove_all(&self, user_id: &str) -> Result<usize, StorageError> {
+        let removed = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM passkeys WHERE user_id = ?1", [user_id])?;
+        Ok(removed)
+    }
+}
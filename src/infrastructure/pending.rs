@@ -0,0 +1,73 @@
+//! Pending store - Short-lived server-side state between two steps of a login
+
+use crate::shared::time::now_secs;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Values keyed by a random ID handed to the client, dropped after `ttl` seconds
+/// In memory: the second step must reach the instance that handled the first
+#[derive(Clone)]
+pub struct PendingStore<T> {
+    ttl: u64,
+    entries: Arc<Mutex<HashMap<String, (u64, T)>>>,
+}
+
+impl<T: Clone> PendingStore<T> {
+    pub fn new(ttl: u64) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Store `value` under a new random ID
+    pub fn insert(&self, value: T) -> String {
+        let id = Uuid::new_v4().to_string();
+        let now = now_secs();
+
+        let mut entries = self.entries.lock().unwrap();
+        // Abandoned entries are dropped here rather than by a background task
+        entries.retain(|_, (created_at, _)| !self.expired(*created_at, now));
+        entries.insert(id.clone(), (now, value));
+        id
+    }
+
+    /// The live value for `id`, left in place
+    pub fn get(&self, id: &str) -> Option<T> {
+        let entries = self.entries.lock().unwrap();
+        let (created_at, value) = entries.get(id)?;
+        (!self.expired(*created_at, now_secs())).then(|| value.clone())
+    }
+
+    /// Change the live value for `id` in place, returns it as changed
+    pub fn update(&self, id: &str, f: impl FnOnce(&mut T)) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap();
+        let (created_at, value) = entries.get_mut(id)?;
+        if self.expired(*created_at, now_secs()) {
+            return None;
+        }
+        f(value);
+        Some(value.clone())
+    }
+
+    /// Remove and return the live value for `id`
+    pub fn take(&self, id: &str) -> Option<T> {
+        let (created_at, value) = self.entries.lock().unwrap().remove(id)?;
+        (!self.expired(created_at, now_secs())).then_some(value)
+    }
+
+    fn expired(&self, created_at: u64, now: u64) -> bool {
+        now.saturating_sub(created_at) > self.ttl
+    }
+}
+
+impl<T> fmt::Debug for PendingStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingStore")
+            .field("ttl", &self.ttl)
+            .field("pending", &self.entries.lock().unwrap().len())
+            .finish()
+    }
+}
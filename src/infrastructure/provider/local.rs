@@ -0,0 +1,326 @@
+//! Local auth provider - Users in an embedded SQLite database, no Supabase needed
+//!
+//! Meant for offline development: email/password accounts only, no email confirmation,
+//! OTP, OAuth or MFA. Passwords are Argon2id hashes; tokens are random and opaque, stored
+//! as SHA-256 digests.
+
+use crate::domain::{AuthProvider, SignIn, SignupOutcome, User};
+use crate::error::{AppError, AppResult, AuthError, StorageError};
+use crate::shared::constants::auth::{LOCAL_ACCESS_TOKEN_TTL_SECS, LOCAL_REFRESH_TOKEN_TTL_SECS};
+use crate::shared::phone::PhoneNumber;
+use crate::shared::time::now_secs;
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rusqlite::{Connection, ErrorCode, OptionalExtension, Row, params};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS users (
+    id            TEXT PRIMARY KEY,
+    email         TEXT NOT NULL UNIQUE,
+    username      TEXT NOT NULL,
+    phone         TEXT,
+    role          TEXT NOT NULL,
+    password_hash TEXT NOT NULL,
+    created_at    INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tokens (
+    refresh_hash TEXT PRIMARY KEY,
+    access_hash  TEXT NOT NULL,
+    user_id      TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    issued_at    INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS tokens_access_hash ON tokens (access_hash);
+";
+
+/// Role of every local account, as Supabase gives signed-up users
+const DEFAULT_ROLE: &str = "authenticated";
+
+/// Accounts and issued tokens in SQLite
+#[derive(Debug)]
+pub struct LocalProvider {
+    conn: Mutex<Connection>,
+    // Checked against when the email is unknown, so both cases take as long
+    dummy_hash: String,
+}
+
+/// A `users` row, without the password hash
+struct Account {
+    id: String,
+    email: String,
+    username: String,
+    role: String,
+}
+
+impl LocalProvider {
+    /// Open (or create) the database at `path`, `:memory:` for a private in-memory db
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        if let Some(dir) = Path::new(path)
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        conn.execute_batch(SCHEMA)?;
+
+        info!(path = %path, "Local auth database opened");
+        Ok(Self {
+            conn: Mutex::new(conn),
+            dummy_hash: hash_password(&new_token()),
+        })
+    }
+
+    fn account(&self, user_id: &str) -> Result<Option<Account>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let account = conn
+            .query_row(
+                "SELECT id, email, username, role FROM users WHERE id = ?1",
+                [user_id],
+                Account::from_row,
+            )
+            .optional()?;
+        Ok(account)
+    }
+
+    /// Open a token pair for `account`, forgetting refresh tokens unused for too long
+    fn issue(&self, account: Account) -> Result<User, StorageError> {
+        let (access_token, refresh_token) = (new_token(), new_token());
+        let now = now_secs();
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM tokens WHERE issued_at < ?1",
+            [now.saturating_sub(LOCAL_REFRESH_TOKEN_TTL_SECS)],
+        )?;
+        conn.execute(
+            "INSERT INTO tokens (refresh_hash, access_hash, user_id, issued_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                digest(&refresh_token),
+                digest(&access_token),
+                account.id,
+                now
+            ],
+        )?;
+
+        Ok(User {
+            id: account.id,
+            email: account.email,
+            username: account.username,
+            role: account.role,
+            // Local accounts only have roles granted through `RoleStore`
+            roles: Vec::new(),
+            access_token,
+            refresh_token,
+            expires_at: now + LOCAL_ACCESS_TOKEN_TTL_SECS,
+        })
+    }
+}
+
+impl Account {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            email: row.get("email")?,
+            username: row.get("username")?,
+            role: row.get("role")?,
+        })
+    }
+}
+
+#[async_trait]
+impl AuthProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn login(&self, email: &str, password: &str) -> AppResult<SignIn> {
+        let found = {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row(
+                "SELECT id, email, username, role, password_hash FROM users WHERE email = ?1",
+                [normalize_email(email)],
+                |row| {
+                    Ok((
+                        Account::from_row(row)?,
+                        row.get::<_, String>("password_hash")?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(StorageError::from)?
+        };
+
+        let (account, hash) = match found {
+            Some((account, hash)) => (Some(account), hash),
+            None => (None, self.dummy_hash.clone()),
+        };
+        let password = password.to_string();
+        let valid = blocking(move || verify_password(&password, &hash)).await;
+
+        match account {
+            Some(account) if valid => {
+                info!(user_id = %account.id, "Local login successful");
+                Ok(SignIn {
+                    user: self.issue(account)?,
+                    factors: Vec::new(),
+                })
+            }
+            _ => Err(AuthError::InvalidCredentials.into()),
+        }
+    }
+
+    async fn register(
+        &self,
+        email: &str,
+        password: &str,
+        username: &str,
+        phone: Option<&PhoneNumber>,
+    ) -> AppResult<SignupOutcome> {
+        let account = Account {
+            id: uuid::Uuid::new_v4().to_string(),
+            email: normalize_email(email),
+            username: username.to_string(),
+            role: DEFAULT_ROLE.to_string(),
+        };
+        let password = password.to_string();
+        let hash = blocking(move || hash_password(&password)).await;
+
+        let inserted = self.conn.lock().unwrap().execute(
+            "INSERT INTO users (id, email, username, phone, role, password_hash, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                account.id,
+                account.email,
+                account.username,
+                phone.map(PhoneNumber::e164),
+                account.role,
+                hash,
+                now_secs(),
+            ],
+        );
+        match inserted {
+            Ok(_) => {}
+            // `email` is the only unique column besides the random ID
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == ErrorCode::ConstraintViolation =>
+            {
+                return Err(AppError::validation("email", "Email already registered"));
+            }
+            Err(e) => return Err(StorageError::from(e).into()),
+        }
+
+        info!(user_id = %account.id, "Local registration successful");
+        Ok(SignupOutcome::Session(self.issue(account)?))
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> AppResult<User> {
+        // Refresh tokens are single-use, as with Supabase
+        let owner: Option<(String, u64)> = {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row(
+                "DELETE FROM tokens WHERE refresh_hash = ?1 RETURNING user_id, issued_at",
+                [digest(refresh_token)],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(StorageError::from)?
+        };
+
+        let fresh = |issued_at: u64| issued_at + LOCAL_REFRESH_TOKEN_TTL_SECS > now_secs();
+        let Some((user_id, _)) = owner.filter(|(_, issued_at)| fresh(*issued_at)) else {
+            return Err(AuthError::Unauthenticated.into());
+        };
+        let account = self.account(&user_id)?.ok_or(AuthError::Unauthenticated)?;
+        Ok(self.issue(account)?)
+    }
+
+    async fn login_as(&self, user_id: &str) -> AppResult<SignIn> {
+        let account = self.account(user_id)?.ok_or(AuthError::Unauthenticated)?;
+        Ok(SignIn {
+            user: self.issue(account)?,
+            factors: Vec::new(),
+        })
+    }
+
+    async fn logout(&self, access_token: &str) {
+        let deleted = self.conn.lock().unwrap().execute(
+            "DELETE FROM tokens WHERE access_hash = ?1",
+            [digest(access_token)],
+        );
+        if let Err(e) = deleted {
+            warn!(error = %e, "Failed to revoke local tokens");
+        }
+    }
+
+    async fn account_email(&self, user_id: &str) -> AppResult<Option<String>> {
+        Ok(self.account(user_id)?.map(|account| account.email))
+    }
+
+    /// Tokens go with the account (`ON DELETE CASCADE`)
+    async fn delete_user(&self, user_id: &str) -> AppResult<()> {
+        let deleted = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM users WHERE id = ?1", [user_id])
+            .map_err(StorageError::from)?;
+        if deleted > 0 {
+            info!(user_id = %user_id, "Local account deleted");
+        }
+        Ok(())
+    }
+}
+
+/// Emails are matched case-insensitively
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// 32 random bytes, base64url
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Tokens are looked up by digest, so a leaked database does not leak live tokens
+fn digest(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Argon2id PHC string with a random salt
+fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Argon2 with default parameters cannot fail")
+        .to_string()
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|parsed| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    })
+}
+
+/// Argon2 is slow on purpose: keep it off the async workers
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(f)
+        .await
+        .expect("Password hashing task panicked")
+}
@@ -0,0 +1,31 @@
+//! Auth providers - Implementations of `domain::AuthProvider`
+//!
+//! - `supabase` - Supabase Auth (GoTrue), the production provider
+//! - `local` - Argon2-hashed users in an embedded SQLite database, to develop offline
+
+mod local;
+mod supabase;
+
+pub use local::LocalProvider;
+pub use supabase::SupabaseProvider;
+
+use std::str::FromStr;
+
+/// Auth provider selected with `AUTH_PROVIDER`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthProviderKind {
+    Supabase,
+    Local,
+}
+
+impl FromStr for AuthProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "supabase" => Ok(Self::Supabase),
+            "local" => Ok(Self::Local),
+            other => Err(format!("unknown auth provider: {}", other)),
+        }
+    }
+}
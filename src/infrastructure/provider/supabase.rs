@@ -0,0 +1,96 @@
+//! Supabase auth provider - `AuthProvider` over the Supabase HTTP clients
+
+use crate::domain::{AuthProvider, SignIn, SignupOutcome, User};
+use crate::error::{AppError, AppResult, AuthError, SupabaseError};
+use crate::infrastructure::supabase::{OtpProof, OtpType};
+use crate::infrastructure::{SupabaseAdmin, SupabaseClient};
+use crate::shared::phone::PhoneNumber;
+use actix_web::http::StatusCode;
+use async_trait::async_trait;
+
+/// Supabase Auth - user calls with the anon client, the few that need it with the admin client
+#[derive(Debug)]
+pub struct SupabaseProvider {
+    client: SupabaseClient,
+    admin: SupabaseAdmin,
+}
+
+impl SupabaseProvider {
+    pub fn new(client: SupabaseClient, admin: SupabaseAdmin) -> Self {
+        Self { client, admin }
+    }
+}
+
+#[async_trait]
+impl AuthProvider for SupabaseProvider {
+    fn name(&self) -> &'static str {
+        "supabase"
+    }
+
+    async fn login(&self, email: &str, password: &str) -> AppResult<SignIn> {
+        self.client
+            .login(email, password)
+            .await
+            .map_err(|e| AuthError::from(e).into())
+    }
+
+    async fn register(
+        &self,
+        email: &str,
+        password: &str,
+        username: &str,
+        phone: Option<&PhoneNumber>,
+    ) -> AppResult<SignupOutcome> {
+        self.client
+            .register(email, password, username, phone)
+            .await
+            .map_err(|e| AuthError::from(e).into())
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> AppResult<User> {
+        self.client
+            .refresh(refresh_token)
+            .await
+            .map_err(rejection_is_unauthenticated)
+    }
+
+    /// See `SupabaseAdmin::passkey_sign_in` for why this needs the service role
+    async fn login_as(&self, user_id: &str) -> AppResult<SignIn> {
+        let signin = async {
+            let token_hash = self.admin.passkey_sign_in(user_id).await?;
+            self.client
+                .verify_otp(OtpType::Magiclink, OtpProof::TokenHash(&token_hash))
+                .await
+        };
+        signin.await.map_err(rejection_is_unauthenticated)
+    }
+
+    async fn logout(&self, access_token: &str) {
+        self.client.logout(access_token).await
+    }
+
+    async fn account_email(&self, user_id: &str) -> AppResult<Option<String>> {
+        match self.admin.get_user(user_id).await {
+            Ok(user) => Ok(Some(user.email)),
+            Err(SupabaseError::Http { status, .. }) if status == StatusCode::NOT_FOUND => Ok(None),
+            Err(e) => Err(AuthError::External(e).into()),
+        }
+    }
+
+    async fn delete_user(&self, user_id: &str) -> AppResult<()> {
+        match self.admin.delete_user(user_id).await {
+            Ok(()) => Ok(()),
+            Err(SupabaseError::Http { status, .. }) if status == StatusCode::NOT_FOUND => Ok(()),
+            Err(e) => Err(AuthError::External(e).into()),
+        }
+    }
+}
+
+/// Definitive refusals (revoked token, deleted or banned user) against transient failures
+fn rejection_is_unauthenticated(err: SupabaseError) -> AppError {
+    if err.is_rejection() {
+        AuthError::Unauthenticated.into()
+    } else {
+        AuthError::External(err).into()
+    }
+}
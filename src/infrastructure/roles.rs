@@ -0,0 +1,89 @@
+//! SQLite role store - Roles granted through the admin API
+
+use crate::domain::{RoleStore, UserId};
+use crate::error::StorageError;
+use crate::shared::time::now_secs;
+use rusqlite::{Connection, params};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::info;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id    TEXT NOT NULL,
+    role       TEXT NOT NULL,
+    granted_by TEXT NOT NULL,
+    granted_at INTEGER NOT NULL,
+    PRIMARY KEY (user_id, role)
+);
+";
+
+/// Role grants in SQLite
+#[derive(Debug)]
+pub struct SqliteRoles {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteRoles {
+    /// Open (or create) the database at `path`, `:memory:` for a private in-memory db
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        if let Some(dir) = Path::new(path)
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+
+        info!(path = %path, "Role database opened");
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl RoleStore for SqliteRoles {
+    fn roles(&self, user_id: &str) -> Result<Vec<String>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare_cached("SELECT role FROM user_roles WHERE user_id = ?1 ORDER BY role")?;
+        let roles = stmt
+            .query_map([user_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(roles)
+    }
+
+    fn grant(
+        &self,
+        user_id: &UserId,
+        role: &str,
+        granted_by: &UserId,
+    ) -> Result<bool, StorageError> {
+        let granted = self.conn.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO user_roles (user_id, role, granted_by, granted_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![user_id, role, granted_by, now_secs()],
+        )?;
+        Ok(granted > 0)
+    }
+
+    fn revoke(&self, user_id: &str, role: &str) -> Result<bool, StorageError> {
+        let revoked = self.conn.lock().unwrap().execute(
+            "DELETE FROM user_roles WHERE user_id = ?1 AND role = ?2",
+            [user_id, role],
+        )?;
+        Ok(revoked > 0)
+    }
+
+    fn revoke_all(&self, user_id: &str) -> Result<usize, StorageError> {
+        let revoked = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM user_roles WHERE user_id = ?1", [user_id])?;
+        Ok(revoked)
+    }
+}
@@ -0,0 +1,110 @@
+//! Encrypted session backend - Seals Supabase tokens before they reach another backend
+//!
+//! Wraps any `SessionBackend`: access and refresh tokens are encrypted on the way in
+//! and decrypted on load. Everything else (timestamps, client info) is stored as-is;
+//! session IDs already are digests of the cookie secret (`domain::session_key`).
+
+use crate::domain::{Session, SessionBackend, SessionOp};
+use crate::error::StorageError;
+use crate::infrastructure::crypto::TokenCipher;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+const ACCESS_TOKEN: &str = "access_token";
+const REFRESH_TOKEN: &str = "refresh_token";
+
+/// Decorator that encrypts token fields of every stored session
+#[derive(Debug)]
+pub struct EncryptedBackend {
+    inner: Arc<dyn SessionBackend>,
+    cipher: TokenCipher,
+}
+
+impl EncryptedBackend {
+    pub fn new(inner: Arc<dyn SessionBackend>, cipher: TokenCipher) -> Self {
+        Self { inner, cipher }
+    }
+
+    /// Associated data binding a ciphertext to its session and field
+    fn context(session_id: &str, field: &str) -> String {
+        format!("{}:{}", session_id, field)
+    }
+
+    fn seal(&self, session: &Session) -> Session {
+        let mut sealed = session.clone();
+        sealed.user.access_token = self.cipher.seal(
+            &session.user.access_token,
+            &Self::context(&session.id, ACCESS_TOKEN),
+        );
+        sealed.user.refresh_token = self.cipher.seal(
+            &session.user.refresh_token,
+            &Self::context(&session.id, REFRESH_TOKEN),
+        );
+        sealed
+    }
+
+    /// Decrypt both tokens, returning whether either needs re-sealing
+    fn open(&self, session: &mut Session) -> Result<bool, String> {
+        let access = self.cipher.open(
+            &session.user.access_token,
+            &Self::context(&session.id, ACCESS_TOKEN),
+        )?;
+        let refresh = self.cipher.open(
+            &session.user.refresh_token,
+            &Self::context(&session.id, REFRESH_TOKEN),
+        )?;
+
+        session.user.access_token = access.plaintext;
+        session.user.refresh_token = refresh.plaintext;
+        Ok(access.stale || refresh.stale)
+    }
+}
+
+impl SessionBackend for EncryptedBackend {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    /// Sessions that fail to decrypt (unknown key, tampering) are dropped, the user logs in again.
+    /// Plaintext or old-key sessions are returned normally; the store's startup compaction
+    /// then re-seals them with the active key.
+    fn load(&self) -> Result<Vec<Session>, StorageError> {
+        let mut sessions = Vec::new();
+        let mut stale = 0;
+
+        for mut session in self.inner.load()? {
+            match self.open(&mut session) {
+                Ok(needs_reseal) => {
+                    if needs_reseal {
+                        stale += 1;
+                    }
+                    sessions.push(session);
+                }
+                Err(reason) => {
+                    warn!(device_id = %session.device_id, reason = %reason, "Dropping session with undecryptable tokens");
+                }
+            }
+        }
+
+        if stale > 0 {
+            info!(count = stale, key_id = %self.cipher.active_key_id(), "Re-encrypting sessions with the active key");
+        }
+        Ok(sessions)
+    }
+
+    fn apply(&self, ops: &[SessionOp]) -> Result<(), StorageError> {
+        let sealed: Vec<SessionOp> = ops
+            .iter()
+            .map(|op| match op {
+                SessionOp::Upsert(s) => SessionOp::Upsert(Box::new(self.seal(s))),
+                SessionOp::Remove(id) => SessionOp::Remove(id.clone()),
+            })
+            .collect();
+        self.inner.apply(&sealed)
+    }
+
+    fn compact(&self, sessions: &[Session]) -> Result<(), StorageError> {
+        let sealed: Vec<Session> = sessions.iter().map(|s| self.seal(s)).collect();
+        self.inner.compact(&sealed)
+    }
+}
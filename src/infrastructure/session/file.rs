@@ -0,0 +1,314 @@
+//! File session backend - CSV snapshot plus an append-only JSONL journal
+//!
+//! Snapshot layout: a `#lapp-sessions v2` line, a CSV header, then one RFC 4180 row per session.
+//! Files without the version line are the legacy unquoted format and are still readable.
+//!
+//! Mutations are appended to `<snapshot>.journal` (one JSON object per line) and replayed
+//! on top of the snapshot at load. Compaction rewrites the snapshot and empties the journal.
+
+use super::record::SessionRecord;
+use crate::domain::{Aal, ClientInfo, Session, SessionBackend, SessionOp, User};
+use crate::error::StorageError;
+use crate::shared::fs::write_atomic;
+use crate::shared::time::now_secs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const FORMAT_PREFIX: &str = "#lapp-sessions";
+const FORMAT_VERSION: &str = "#lapp-sessions v2";
+
+/// One journal line
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalEntry {
+    Upsert(Box<SessionRecord>),
+    Remove { session_id: String },
+}
+
+impl From<&SessionOp> for JournalEntry {
+    fn from(op: &SessionOp) -> Self {
+        match op {
+            SessionOp::Upsert(s) => Self::Upsert(Box::new(SessionRecord::from(s.as_ref()))),
+            SessionOp::Remove(id) => Self::Remove {
+                session_id: id.clone(),
+            },
+        }
+    }
+}
+
+/// CSV snapshot + JSONL journal backend
+#[derive(Debug)]
+pub struct FileBackend {
+    path: PathBuf,
+    journal: PathBuf,
+}
+
+impl FileBackend {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut journal = path.clone().into_os_string();
+        journal.push(".journal");
+        Self {
+            path,
+            journal: journal.into(),
+        }
+    }
+
+    /// Read the snapshot file
+    fn load_snapshot(&self) -> Result<Vec<Session>, StorageError> {
+        if !self.path.exists() {
+            info!(path = %self.path.display(), "No session file found, starting fresh");
+            return Ok(Vec::new());
+        }
+
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let mut first = String::new();
+        reader.read_line(&mut first)?;
+
+        match first.trim_end() {
+            FORMAT_VERSION => Ok(self.load_current(reader)),
+            v if v.starts_with(FORMAT_PREFIX) => Err(StorageError::Format(format!(
+                "unsupported session file version '{}' in {}",
+                v,
+                self.path.display()
+            ))),
+            _ => self.load_legacy(reader),
+        }
+    }
+
+    /// Replay journal entries on top of `sessions`
+    /// A torn last line (crash mid-append) or any corrupt entry is reported and skipped
+    fn replay_journal(&self, sessions: &mut HashMap<String, Session>) -> Result<(), StorageError> {
+        if !self.journal.exists() {
+            return Ok(());
+        }
+
+        let reader = BufReader::new(File::open(&self.journal)?);
+        let mut replayed = 0;
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str::<JournalEntry>(&line).map_err(|e| e.to_string());
+            match entry {
+                Ok(JournalEntry::Upsert(record)) => match Session::try_from(*record) {
+                    Ok(session) => {
+                        sessions.insert(session.id.clone(), session);
+                    }
+                    Err(reason) => {
+                        warn!(path = %self.journal.display(), line = i + 1, reason = %reason, "Skipping corrupt journal entry");
+                        continue;
+                    }
+                },
+                Ok(JournalEntry::Remove { session_id }) => {
+                    sessions.remove(&session_id);
+                }
+                Err(reason) => {
+                    warn!(path = %self.journal.display(), line = i + 1, reason = %reason, "Skipping corrupt journal entry");
+                    continue;
+                }
+            }
+            replayed += 1;
+        }
+
+        info!(path = %self.journal.display(), count = replayed, "Replayed session journal");
+        Ok(())
+    }
+
+    /// Read versioned rows, skipping (and reporting) any that fail to parse
+    fn load_current(&self, reader: impl BufRead) -> Vec<Session> {
+        let mut sessions = Vec::new();
+        let mut corrupt = 0;
+
+        let mut csv = csv::Reader::from_reader(reader);
+        let headers = match csv.byte_headers() {
+            Ok(h) => h.clone(),
+            Err(e) => {
+                warn!(path = %self.path.display(), error = %e, "Unreadable session file header");
+                return sessions;
+            }
+        };
+
+        for result in csv.byte_records() {
+            // +1 for the version line that precedes the CSV data
+            let line = result
+                .as_ref()
+                .ok()
+                .and_then(|r| r.position())
+                .map(|p| p.line() + 1);
+
+            let parsed = result
+                .and_then(|r| r.deserialize::<SessionRecord>(Some(&headers)))
+                .map_err(|e| e.to_string())
+                .and_then(|r| Session::try_from(r).map_err(String::from));
+
+            match parsed {
+                Ok(session) => sessions.push(session),
+                Err(reason) => {
+                    corrupt += 1;
+                    warn!(path = %self.path.display(), line = ?line, reason = %reason, "Skipping corrupt session row");
+                }
+            }
+        }
+
+        if corrupt > 0 {
+            warn!(path = %self.path.display(), corrupt = corrupt, "Session file had corrupt rows");
+        }
+        sessions
+    }
+
+    /// Read the pre-versioning format (header already consumed): unquoted comma-joined fields
+    fn load_legacy(&self, reader: impl BufRead) -> Result<Vec<Session>, StorageError> {
+        let now = now_secs();
+        let mut sessions = Vec::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            match Self::parse_legacy_row(&line, now) {
+                Some(session) => sessions.push(session),
+                // +2: 1-based, plus the header line
+                None => {
+                    warn!(path = %self.path.display(), line = i + 2, "Skipping corrupt legacy session row")
+                }
+            }
+        }
+
+        info!(count = sessions.len(), "Migrating legacy session file");
+        Ok(sessions)
+    }
+
+    /// Parse one legacy row, `None` if it is too short to be a session
+    fn parse_legacy_row(line: &str, now: u64) -> Option<Session> {
+        let parts: Vec<&str> = line.split(',').collect();
+        if parts.len() < 8 || parts[0].is_empty() || parts[1].is_empty() {
+            return None;
+        }
+
+        let user = User {
+            id: parts[1].to_string(),
+            email: parts[2].to_string(),
+            username: parts[3].to_string(),
+            role: parts[4].to_string(),
+            access_token: parts[5].to_string(),
+            refresh_token: parts[6].to_string(),
+            expires_at: parts[7].parse().ok()?,
+            roles: Vec::new(),
+        };
+
+        // Rows written before expiry tracking get a fresh lifetime
+        let created_at = parts.get(8).and_then(|v| v.parse().ok()).unwrap_or(now);
+        let last_seen = parts.get(9).and_then(|v| v.parse().ok()).unwrap_or(now);
+
+        // Older rows have no device columns
+        let device_id = parts
+            .get(10)
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let non_empty = |v: &&str| !v.is_empty();
+        let client = ClientInfo {
+            ip: parts.get(11).copied().filter(non_empty).map(String::from),
+            // User agent was the last column and may itself contain commas
+            user_agent: parts
+                .get(12..)
+                .map(|rest| rest.join(","))
+                .filter(|v| !v.is_empty()),
+        };
+
+        Some(Session {
+            id: parts[0].to_string(),
+            secret: String::new(),
+            device_id,
+            user,
+            created_at,
+            last_seen,
+            client,
+            // Legacy rows predate MFA
+            aal: Aal::Aal1,
+            verified_at: 0,
+        })
+    }
+}
+
+impl SessionBackend for FileBackend {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn load(&self) -> Result<Vec<Session>, StorageError> {
+        let mut sessions: HashMap<String, Session> = self
+            .load_snapshot()?
+            .into_iter()
+            .map(|s| (s.id.clone(), s))
+            .collect();
+
+        self.replay_journal(&mut sessions)?;
+        Ok(sessions.into_values().collect())
+    }
+
+    fn apply(&self, ops: &[SessionOp]) -> Result<(), StorageError> {
+        if let Some(dir) = self.journal.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.journal)?;
+        // A torn last line (crash mid-append) must not swallow the first new entry
+        let torn = ends_mid_line(&mut file)?;
+        let mut writer = BufWriter::new(file);
+        if torn {
+            writer.write_all(b"\n")?;
+        }
+
+        for op in ops {
+            serde_json::to_writer(&mut writer, &JournalEntry::from(op))
+                .map_err(std::io::Error::other)?;
+            writer.write_all(b"\n")?;
+        }
+
+        writer.flush()?;
+        writer.get_ref().sync_data()?;
+        Ok(())
+    }
+
+    fn compact(&self, sessions: &[Session]) -> Result<(), StorageError> {
+        write_atomic(&self.path, |w| {
+            writeln!(w, "{}", FORMAT_VERSION)?;
+
+            let mut csv = csv::Writer::from_writer(w);
+            for session in sessions {
+                csv.serialize(SessionRecord::from(session))?;
+            }
+            csv.flush()
+        })?;
+
+        // Snapshot is durable, the journal is now redundant. A crash before this
+        // point only means replaying already-applied (idempotent) entries.
+        if self.journal.exists() {
+            File::create(&self.journal)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the file is non-empty and does not end with a newline
+fn ends_mid_line(file: &mut File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
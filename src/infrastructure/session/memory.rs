@@ -0,0 +1,26 @@
+//! In-memory session backend - Persists nothing, sessions die with the process
+
+use crate::domain::{Session, SessionBackend, SessionOp};
+use crate::error::StorageError;
+
+/// Backend that keeps nothing on disk
+#[derive(Debug, Default)]
+pub struct MemoryBackend;
+
+impl SessionBackend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn load(&self) -> Result<Vec<Session>, StorageError> {
+        Ok(Vec::new())
+    }
+
+    fn apply(&self, _ops: &[SessionOp]) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn compact(&self, _sessions: &[Session]) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+//! Session persistence - Implementations of `domain::SessionBackend`
+//!
+//! - `memory` - Nothing persisted (tests, throwaway deployments)
+//! - `file` - CSV snapshot + append-only journal, readable and easy to back up
+//! - `sqlite` - Embedded SQLite database, durable with incremental writes
+//!
+//! `encrypted` wraps any of them to seal Supabase tokens when encryption keys are configured.
+
+mod encrypted;
+mod file;
+mod memory;
+mod record;
+mod sqlite;
+
+pub use encrypted::EncryptedBackend;
+pub use file::FileBackend;
+pub use memory::MemoryBackend;
+pub use sqlite::SqliteBackend;
+
+use crate::config::Config;
+use crate::domain::SessionBackend;
+use crate::infrastructure::crypto::TokenCipher;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Session backend selected with `SESSION_BACKEND`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionBackendKind {
+    Memory,
+    File,
+    Sqlite,
+}
+
+impl FromStr for SessionBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "file" | "csv" => Ok(Self::File),
+            "sqlite" => Ok(Self::Sqlite),
+            other => Err(format!("unknown session backend: {}", other)),
+        }
+    }
+}
+
+/// Build the session backend configured in `cfg`
+pub fn backend_from_config(cfg: &Config) -> Arc<dyn SessionBackend> {
+    let backend: Arc<dyn SessionBackend> = match cfg.session_backend {
+        SessionBackendKind::Memory => Arc::new(MemoryBackend),
+        SessionBackendKind::File => Arc::new(FileBackend::new(&cfg.session_file)),
+        SessionBackendKind::Sqlite => {
+            Arc::new(SqliteBackend::open(&cfg.session_db).unwrap_or_else(|e| {
+                panic!("Failed to open session database {}: {}", cfg.session_db, e)
+            }))
+        }
+    };
+
+    match token_cipher_from_config(cfg) {
+        Some(cipher) => Arc::new(EncryptedBackend::new(backend, cipher)),
+        None => {
+            if cfg.session_backend != SessionBackendKind::Memory {
+                warn!("SESSION_ENCRYPTION_KEYS not set, Supabase tokens are stored in plaintext");
+            }
+            backend
+        }
+    }
+}
+
+/// Token cipher from `SESSION_ENCRYPTION_KEYS`, `None` when encryption is disabled.
+/// The active key defaults to the first one listed.
+fn token_cipher_from_config(cfg: &Config) -> Option<TokenCipher> {
+    let keys = cfg.session_encryption_keys.trim();
+    if keys.is_empty() {
+        return None;
+    }
+
+    let active = match cfg.session_encryption_key_id.trim() {
+        "" => keys.split(':').next().unwrap_or_default().trim(),
+        id => id,
+    };
+
+    let cipher = TokenCipher::from_spec(keys, active)
+        .unwrap_or_else(|e| panic!("SESSION_ENCRYPTION_KEYS is invalid: {}", e));
+    Some(cipher)
+}
@@ -0,0 +1,105 @@
+//! Session record - Flat serializable mirror of `Session` shared by file formats
+
+use crate::domain::{Aal, ClientInfo, Session, User};
+use serde::{Deserialize, Serialize};
+
+/// One session as a flat row (CSV) or object (journal)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub session_id: String,
+    pub user_id: String,
+    pub email: String,
+    pub username: String,
+    pub role: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: u64,
+    pub created_at: u64,
+    pub last_seen: u64,
+    pub device_id: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// Absent from rows written before MFA support
+    #[serde(default)]
+    pub aal: String,
+    /// Application roles, comma-separated; absent from rows written before roles
+    #[serde(default)]
+    pub roles: String,
+    /// Absent from rows written before re-authentication checks
+    #[serde(default)]
+    pub verified_at: u64,
+}
+
+impl From<&Session> for SessionRecord {
+    fn from(s: &Session) -> Self {
+        Self {
+            session_id: s.id.clone(),
+            user_id: s.user.id.clone(),
+            email: s.user.email.clone(),
+            username: s.user.username.clone(),
+            role: s.user.role.clone(),
+            access_token: s.user.access_token.clone(),
+            refresh_token: s.user.refresh_token.clone(),
+            expires_at: s.user.expires_at,
+            created_at: s.created_at,
+            last_seen: s.last_seen,
+            device_id: s.device_id.clone(),
+            ip: s.client.ip.clone(),
+            user_agent: s.client.user_agent.clone(),
+            aal: s.aal.to_string(),
+            roles: join_roles(&s.user.roles),
+            verified_at: s.verified_at,
+        }
+    }
+}
+
+impl TryFrom<SessionRecord> for Session {
+    type Error = &'static str;
+
+    fn try_from(r: SessionRecord) -> Result<Self, Self::Error> {
+        if r.session_id.is_empty() || r.user_id.is_empty() || r.device_id.is_empty() {
+            return Err("missing session, user or device id");
+        }
+        let aal = match r.aal.as_str() {
+            "" => Aal::Aal1,
+            level => level.parse().map_err(|_| "invalid assurance level")?,
+        };
+
+        Ok(Session {
+            id: r.session_id,
+            secret: String::new(),
+            device_id: r.device_id,
+            user: User {
+                id: r.user_id,
+                email: r.email,
+                username: r.username,
+                role: r.role,
+                roles: split_roles(&r.roles),
+                access_token: r.access_token,
+                refresh_token: r.refresh_token,
+                expires_at: r.expires_at,
+            },
+            created_at: r.created_at,
+            last_seen: r.last_seen,
+            client: ClientInfo {
+                ip: r.ip,
+                user_agent: r.user_agent,
+            },
+            aal,
+            verified_at: r.verified_at,
+        })
+    }
+}
+
+/// Roles as one column: role names never contain commas (see `app_metadata_roles`)
+pub fn join_roles(roles: &[String]) -> String {
+    roles.join(",")
+}
+
+pub fn split_roles(column: &str) -> Vec<String> {
+    column
+        .split(',')
+        .filter(|r| !r.is_empty())
+        .map(String::from)
+        .collect()
+}
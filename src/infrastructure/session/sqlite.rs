@@ -0,0 +1,184 @@
+//! SQLite session backend - Sessions persisted in an embedded database
+
+use super::record::{join_roles, split_roles};
+use crate::domain::{Aal, ClientInfo, Session, SessionBackend, SessionOp, User};
+use crate::error::StorageError;
+use rusqlite::{Connection, Row, Transaction, params};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::info;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    session_id    TEXT PRIMARY KEY,
+    device_id     TEXT NOT NULL,
+    user_id       TEXT NOT NULL,
+    email         TEXT NOT NULL,
+    username      TEXT NOT NULL,
+    role          TEXT NOT NULL,
+    access_token  TEXT NOT NULL,
+    refresh_token TEXT NOT NULL,
+    expires_at    INTEGER NOT NULL,
+    created_at    INTEGER NOT NULL,
+    last_seen     INTEGER NOT NULL,
+    ip            TEXT,
+    user_agent    TEXT,
+    aal           TEXT NOT NULL DEFAULT 'aal1',
+    roles         TEXT NOT NULL DEFAULT '',
+    verified_at   INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS sessions_user_id ON sessions (user_id);
+";
+
+const UPSERT: &str = "
+INSERT OR REPLACE INTO sessions (session_id, device_id, user_id, email, username, role,
+    access_token, refresh_token, expires_at, created_at, last_seen, ip, user_agent, aal, roles,
+    verified_at)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)";
+
+/// Columns added after the first release, with their definition for `ALTER TABLE`
+const MIGRATIONS: &[(&str, &str)] = &[
+    ("aal", "TEXT NOT NULL DEFAULT 'aal1'"),
+    ("roles", "TEXT NOT NULL DEFAULT ''"),
+    ("verified_at", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+/// Embedded SQLite backend - every batch is one transaction
+#[derive(Debug)]
+pub struct SqliteBackend {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteBackend {
+    /// Open (or create) the database at `path`, `:memory:` for a private in-memory db
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        if let Some(dir) = Path::new(path)
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Self::migrate(&conn)?;
+
+        info!(path = %path, "Session database opened");
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Add the columns a database created by an older release lacks
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        for (column, definition) in MIGRATIONS {
+            let exists: bool = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('sessions') WHERE name = ?1",
+                [column],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE sessions ADD COLUMN {} {}",
+                    column, definition
+                ))?;
+                info!(column = %column, "Session database migrated");
+            }
+        }
+        Ok(())
+    }
+
+    fn upsert(tx: &Transaction<'_>, s: &Session) -> rusqlite::Result<usize> {
+        tx.prepare_cached(UPSERT)?.execute(params![
+            s.id,
+            s.device_id,
+            s.user.id,
+            s.user.email,
+            s.user.username,
+            s.user.role,
+            s.user.access_token,
+            s.user.refresh_token,
+            s.user.expires_at,
+            s.created_at,
+            s.last_seen,
+            s.client.ip,
+            s.client.user_agent,
+            s.aal.as_str(),
+            join_roles(&s.user.roles),
+            s.verified_at,
+        ])
+    }
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Session> {
+        Ok(Session {
+            id: row.get("session_id")?,
+            secret: String::new(),
+            device_id: row.get("device_id")?,
+            user: User {
+                id: row.get("user_id")?,
+                email: row.get("email")?,
+                username: row.get("username")?,
+                role: row.get("role")?,
+                roles: split_roles(&row.get::<_, String>("roles")?),
+                access_token: row.get("access_token")?,
+                refresh_token: row.get("refresh_token")?,
+                expires_at: row.get("expires_at")?,
+            },
+            created_at: row.get("created_at")?,
+            last_seen: row.get("last_seen")?,
+            client: ClientInfo {
+                ip: row.get("ip")?,
+                user_agent: row.get("user_agent")?,
+            },
+            // Unknown levels (written by a newer release) fall back to the weakest
+            aal: row.get::<_, String>("aal")?.parse().unwrap_or(Aal::Aal1),
+            verified_at: row.get("verified_at")?,
+        })
+    }
+}
+
+impl SessionBackend for SqliteBackend {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn load(&self) -> Result<Vec<Session>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT * FROM sessions")?;
+        let sessions = stmt
+            .query_map([], Self::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(sessions)
+    }
+
+    fn apply(&self, ops: &[SessionOp]) -> Result<(), StorageError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for op in ops {
+            match op {
+                SessionOp::Upsert(s) => Self::upsert(&tx, s)?,
+                SessionOp::Remove(id) => {
+                    tx.execute("DELETE FROM sessions WHERE session_id = ?1", [id])?
+                }
+            };
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn compact(&self, sessions: &[Session]) -> Result<(), StorageError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM sessions", [])?;
+        for s in sessions {
+            Self::upsert(&tx, s)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
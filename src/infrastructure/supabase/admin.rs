@@ -0,0 +1,204 @@
+//! Supabase admin API - Calls authenticated with the service role key
+//!
+//! Kept apart from `SupabaseClient`: the service role bypasses row level security, so it only
+//! ever goes to admin endpoints and never rides along on calls made for a user.
+
+use super::types::{
+    AdminUpdateUserBody, AdminUser, AdminUsersResponse, GenerateLinkBody, GenerateLinkResponse,
+    OtpType, UserPage,
+};
+use crate::config::Config;
+use crate::error::SupabaseError;
+use crate::shared::constants::urls::{
+    SUPABASE_ADMIN_GENERATE_LINK_PATH, SUPABASE_ADMIN_USERS_PATH,
+};
+use actix_web::http::StatusCode;
+use reqwest::{Client, Method, RequestBuilder};
+use serde_json::Value;
+use std::fmt;
+use tracing::{debug, info, instrument};
+use uuid::Uuid;
+
+/// Supabase admin API client
+#[derive(Clone)]
+pub struct SupabaseAdmin {
+    url: String,
+    /// Bypasses row level security and unlocks the admin API - never sent to clients
+    service_role_key: String,
+}
+
+impl SupabaseAdmin {
+    pub fn new(cfg: &Config) -> Self {
+        info!(url = %cfg.sp_url, "Supabase admin client initialized");
+        Self {
+            url: cfg.sp_url.clone(),
+            service_role_key: cfg.sp_service_role.clone(),
+        }
+    }
+
+    /// Request to an admin endpoint
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let endpoint = format!("{}{}", self.url, path);
+        debug!(endpoint = %endpoint, "Sending admin request");
+
+        Client::new()
+            .request(method, endpoint)
+            .header("apikey", &self.service_role_key)
+            .header("Authorization", format!("Bearer {}", self.service_role_key))
+    }
+
+    /// Path of a user; `user_id` goes into a URL called with the service role, so anything but
+    /// a UUID is answered like an unknown user without calling Supabase
+    fn user_path(user_id: &str) -> Result<String, SupabaseError> {
+        let id = Uuid::parse_str(user_id).map_err(|_| SupabaseError::Http {
+            status: StatusCode::NOT_FOUND,
+            body: "user ID is not a UUID".to_string(),
+        })?;
+        Ok(format!("{}/{}", SUPABASE_ADMIN_USERS_PATH, id))
+    }
+
+    /// One page of users, `page` starting at 1
+    #[instrument(skip(self))]
+    pub async fn list_users(&self, page: u32, per_page: u32) -> Result<UserPage, SupabaseError> {
+        let response = self
+            .request(Method::GET, SUPABASE_ADMIN_USERS_PATH)
+            .query(&[("page", page), ("per_page", per_page)])
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        let total = response
+            .headers()
+            .get("x-total-count")
+            .and_then(|v| v.to_str().ok()?.parse().ok());
+        let list: AdminUsersResponse = SupabaseError::parse_response(response).await?;
+        Ok(UserPage {
+            users: list.users,
+            total,
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_user(&self, user_id: &str) -> Result<AdminUser, SupabaseError> {
+        let response = self
+            .request(Method::GET, &Self::user_path(user_id)?)
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        SupabaseError::parse_response(response).await
+    }
+
+    /// Merge keys into the user's `app_metadata` and/or `user_metadata`
+    #[instrument(skip(self, app_metadata, user_metadata))]
+    pub async fn update_metadata(
+        &self,
+        user_id: &str,
+        app_metadata: Option<&Value>,
+        user_metadata: Option<&Value>,
+    ) -> Result<AdminUser, SupabaseError> {
+        self.update_user(
+            user_id,
+            &AdminUpdateUserBody {
+                app_metadata,
+                user_metadata,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Refuse logins and token refreshes for `hours`
+    #[instrument(skip(self))]
+    pub async fn ban(&self, user_id: &str, hours: u64) -> Result<AdminUser, SupabaseError> {
+        let duration = format!("{}h", hours);
+        self.update_user(
+            user_id,
+            &AdminUpdateUserBody {
+                ban_duration: Some(&duration),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn unban(&self, user_id: &str) -> Result<AdminUser, SupabaseError> {
+        self.update_user(
+            user_id,
+            &AdminUpdateUserBody {
+                ban_duration: Some("none"),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    async fn update_user(
+        &self,
+        user_id: &str,
+        body: &AdminUpdateUserBody<'_>,
+    ) -> Result<AdminUser, SupabaseError> {
+        let response = self
+            .request(Method::PUT, &Self::user_path(user_id)?)
+            .json(body)
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        SupabaseError::parse_response(response).await
+    }
+
+    /// Delete a user and everything Supabase holds for them (identities, factors, sessions)
+    #[instrument(skip(self))]
+    pub async fn delete_user(&self, user_id: &str) -> Result<(), SupabaseError> {
+        let response = self
+            .request(Method::DELETE, &Self::user_path(user_id)?)
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        SupabaseError::check_response(response).await
+    }
+
+    /// Sign-in token for a user LAPP verified itself with a passkey, without their password
+    /// Returns a token hash, redeemed with `SupabaseClient::verify_otp(OtpType::Magiclink, ...)`
+    ///
+    /// Supabase has no passkey grant: the only way to open a session for a user who did not type
+    /// a password is a magic link minted with the service role (never emailed) and redeemed at
+    /// once. Anyone holding the key can sign in as anyone, so this is the one user-facing flow
+    /// allowed to use it, only after `PasskeyService` checked the assertion. `AuthService`
+    /// audits every use as `service_sign_in`.
+    #[instrument(skip(self))]
+    pub async fn passkey_sign_in(&self, user_id: &str) -> Result<String, SupabaseError> {
+        let user = self.get_user(user_id).await?;
+        let token_hash = self.magic_link(&user.email).await?;
+        info!("Service role sign-in link minted for a passkey login");
+        Ok(token_hash)
+    }
+
+    /// Create a magic link for `email` without emailing it, returns its token hash
+    async fn magic_link(&self, email: &str) -> Result<String, SupabaseError> {
+        let response = self
+            .request(Method::POST, SUPABASE_ADMIN_GENERATE_LINK_PATH)
+            .json(&GenerateLinkBody {
+                kind: OtpType::Magiclink,
+                email,
+            })
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        let link: GenerateLinkResponse = SupabaseError::parse_response(response).await?;
+        Ok(link.hashed_token)
+    }
+}
+
+// The service role key is left out of logs
+impl fmt::Debug for SupabaseAdmin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SupabaseAdmin")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
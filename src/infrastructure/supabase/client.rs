@@ -1,16 +1,26 @@
 //! Supabase HTTP client - Handles all Supabase API communication
 
-use super::types::{LoginBody, RegisterBody, RegisterMetadata, SupabaseAuthResponse};
+use super::types::{
+    ChallengeResponse, EnrollFactorBody, EnrollFactorResponse, LoginBody, OtpBody, OtpProof,
+    OtpType, PkceBody, RecoverBody, RefreshBody, RegisterBody, RegisterMetadata, ResendBody,
+    SignupResponse, SupabaseAuthResponse, SupabaseUserRaw, TotpEnrollment, UpdateUserBody,
+    VerifyBody, VerifyFactorBody,
+};
 use crate::config::Config;
-use crate::domain::User;
+use crate::domain::{Factor, SignIn, SignupOutcome, User};
 use crate::error::SupabaseError;
-use crate::shared::constants::urls::{SUPABASE_AUTH_PATH, SUPABASE_LOGOUT_PATH, SUPABASE_SIGNUP_PATH};
-use reqwest::Client;
+use crate::shared::constants::urls::{
+    SUPABASE_AUTH_PATH, SUPABASE_AUTHORIZE_PATH, SUPABASE_FACTORS_PATH, SUPABASE_LOGOUT_PATH,
+    SUPABASE_OTP_PATH, SUPABASE_PKCE_PATH, SUPABASE_RECOVER_PATH, SUPABASE_REFRESH_PATH,
+    SUPABASE_RESEND_PATH, SUPABASE_SIGNUP_PATH, SUPABASE_USER_PATH, SUPABASE_VERIFY_PATH,
+};
+use crate::shared::phone::PhoneNumber;
+use reqwest::{Client, Url};
 use std::fmt;
 use tracing::{debug, info, instrument, warn};
 
-/// Supabase API client
-#[derive(Clone, Debug)]
+/// Supabase API client - anon key only, admin calls go through `SupabaseAdmin`
+#[derive(Clone)]
 pub struct SupabaseClient {
     url: String,
     anon_key: String,
@@ -27,7 +37,7 @@ impl SupabaseClient {
 
     /// Login with email and password
     #[instrument(skip(self, password), fields(email = %email))]
-    pub async fn login(&self, email: &str, password: &str) -> Result<User, SupabaseError> {
+    pub async fn login(&self, email: &str, password: &str) -> Result<SignIn, SupabaseError> {
         let endpoint = format!("{}{}", self.url, SUPABASE_AUTH_PATH);
         debug!(endpoint = %endpoint, "Sending login request");
 
@@ -45,20 +55,375 @@ impl SupabaseClient {
         Ok(parsed.into())
     }
 
+    /// Exchange a refresh token for a new access/refresh token pair
+    /// Supabase rotates refresh tokens: the one passed in is spent after this call
+    #[instrument(skip(self, refresh_token))]
+    pub async fn refresh(&self, refresh_token: &str) -> Result<User, SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_REFRESH_PATH);
+        debug!(endpoint = %endpoint, "Sending token refresh request");
+
+        let response = Client::new()
+            .post(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Content-Type", "application/json")
+            .json(&RefreshBody { refresh_token })
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        let parsed: SupabaseAuthResponse = SupabaseError::parse_response(response).await?;
+        debug!(user_id = %parsed.user.id, "Token refresh successful");
+        Ok(parsed.into())
+    }
+
+    /// Supabase URL starting an OAuth sign-in with `provider` (PKCE, S256 challenge)
+    /// Supabase sends the browser back to `redirect_to` with `?code=...`
+    pub fn authorize_url(&self, provider: &str, redirect_to: &str, code_challenge: &str) -> String {
+        let endpoint = format!("{}{}", self.url, SUPABASE_AUTHORIZE_PATH);
+        let params = [
+            ("provider", provider),
+            ("redirect_to", redirect_to),
+            ("code_challenge", code_challenge),
+            ("code_challenge_method", "s256"),
+        ];
+        match Url::parse_with_params(&endpoint, &params) {
+            Ok(url) => url.into(),
+            // SP_URL is validated by every other call, keep the unencoded form for the logs
+            Err(e) => {
+                warn!(error = %e, "Invalid Supabase URL");
+                endpoint
+            }
+        }
+    }
+
+    /// Exchange an OAuth authorization code and its PKCE verifier for a session
+    #[instrument(skip(self, auth_code, code_verifier))]
+    pub async fn exchange_code(
+        &self,
+        auth_code: &str,
+        code_verifier: &str,
+    ) -> Result<SignIn, SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_PKCE_PATH);
+        debug!(endpoint = %endpoint, "Sending code exchange request");
+
+        let response = Client::new()
+            .post(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Content-Type", "application/json")
+            .json(&PkceBody {
+                auth_code,
+                code_verifier,
+            })
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        let parsed: SupabaseAuthResponse = SupabaseError::parse_response(response).await?;
+        info!(user_id = %parsed.user.id, "OAuth code exchanged");
+        Ok(parsed.into())
+    }
+
+    /// Fetch the user an access token belongs to - Supabase validates the token
+    /// The returned user carries `access_token` and no refresh token
+    #[instrument(skip(self, access_token))]
+    pub async fn get_user(
+        &self,
+        access_token: &str,
+        expires_at: u64,
+    ) -> Result<User, SupabaseError> {
+        let raw = self.fetch_user(access_token).await?;
+        Ok(raw.into_user(access_token.to_string(), String::new(), expires_at))
+    }
+
+    /// E.164 phone number the user gave at registration, read from their metadata
+    #[instrument(skip(self, access_token))]
+    pub async fn registered_phone(
+        &self,
+        access_token: &str,
+    ) -> Result<Option<String>, SupabaseError> {
+        Ok(self.fetch_user(access_token).await?.registered_phone())
+    }
+
+    async fn fetch_user(&self, access_token: &str) -> Result<SupabaseUserRaw, SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_USER_PATH);
+        debug!(endpoint = %endpoint, "Sending get user request");
+
+        let response = Client::new()
+            .get(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        SupabaseError::parse_response(response).await
+    }
+
+    /// Ask Supabase to email a password recovery link/code
+    /// Supabase answers the same whether or not the email is registered
+    #[instrument(skip(self, email, redirect_to))]
+    pub async fn recover(
+        &self,
+        email: &str,
+        redirect_to: Option<&str>,
+    ) -> Result<(), SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_RECOVER_PATH);
+        debug!(endpoint = %endpoint, "Sending recovery request");
+
+        let mut request = Client::new()
+            .post(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Content-Type", "application/json")
+            .json(&RecoverBody { email });
+        if let Some(url) = redirect_to {
+            request = request.query(&[("redirect_to", url)]);
+        }
+
+        let response = request.send().await.map_err(SupabaseError::from_reqwest)?;
+        SupabaseError::check_response(response).await
+    }
+
+    /// Email a passwordless sign-in magic link / 6-digit code (depends on the email template)
+    /// With `create_user` false, unknown emails get nothing (Supabase still answers 200)
+    #[instrument(skip(self, email))]
+    pub async fn send_email_otp(
+        &self,
+        email: &str,
+        create_user: bool,
+    ) -> Result<(), SupabaseError> {
+        self.send_otp(&OtpBody {
+            email: Some(email),
+            phone: None,
+            create_user,
+        })
+        .await
+    }
+
+    /// Text a sign-in code to a phone number (E.164) already confirmed on an account
+    #[instrument(skip(self, phone))]
+    pub async fn send_sms_otp(&self, phone: &str) -> Result<(), SupabaseError> {
+        self.send_otp(&OtpBody {
+            email: None,
+            phone: Some(phone),
+            create_user: false,
+        })
+        .await
+    }
+
+    async fn send_otp(&self, body: &OtpBody<'_>) -> Result<(), SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_OTP_PATH);
+        debug!(endpoint = %endpoint, "Sending OTP request");
+
+        let response = Client::new()
+            .post(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        SupabaseError::check_response(response).await
+    }
+
+    /// Exchange a one-time token for a Supabase session
+    #[instrument(skip(self, proof))]
+    pub async fn verify_otp(
+        &self,
+        kind: OtpType,
+        proof: OtpProof<'_>,
+    ) -> Result<SignIn, SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_VERIFY_PATH);
+        debug!(endpoint = %endpoint, "Sending verify request");
+
+        let response = Client::new()
+            .post(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Content-Type", "application/json")
+            .json(&VerifyBody::new(kind, proof))
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        let parsed: SupabaseAuthResponse = SupabaseError::parse_response(response).await?;
+        info!(user_id = %parsed.user.id, kind = ?kind, "One-time token verified");
+        Ok(parsed.into())
+    }
+
+    /// Verified MFA factors of the user owning `access_token`
+    #[instrument(skip(self, access_token))]
+    pub async fn list_factors(&self, access_token: &str) -> Result<Vec<Factor>, SupabaseError> {
+        Ok(self.fetch_user(access_token).await?.verified_factors())
+    }
+
+    /// Start enrolling a TOTP factor; it only counts once verified with a first code
+    #[instrument(skip(self, access_token))]
+    pub async fn enroll_totp(
+        &self,
+        access_token: &str,
+        friendly_name: Option<&str>,
+    ) -> Result<(String, TotpEnrollment), SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_FACTORS_PATH);
+        debug!(endpoint = %endpoint, "Sending factor enroll request");
+
+        let response = Client::new()
+            .post(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("Content-Type", "application/json")
+            .json(&EnrollFactorBody {
+                factor_type: "totp",
+                friendly_name,
+            })
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        let parsed: EnrollFactorResponse = SupabaseError::parse_response(response).await?;
+        Ok((parsed.id, parsed.totp))
+    }
+
+    /// Challenge a factor and verify `code` against it
+    /// Returns the user with new tokens at aal2
+    #[instrument(skip(self, access_token, code))]
+    pub async fn verify_totp(
+        &self,
+        access_token: &str,
+        factor_id: &str,
+        code: &str,
+    ) -> Result<User, SupabaseError> {
+        let endpoint = format!("{}{}/{}", self.url, SUPABASE_FACTORS_PATH, factor_id);
+        debug!(endpoint = %endpoint, "Sending factor challenge request");
+
+        let response = Client::new()
+            .post(format!("{}/challenge", endpoint))
+            .header("apikey", &self.anon_key)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+        let challenge: ChallengeResponse = SupabaseError::parse_response(response).await?;
+
+        let response = Client::new()
+            .post(format!("{}/verify", endpoint))
+            .header("apikey", &self.anon_key)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("Content-Type", "application/json")
+            .json(&VerifyFactorBody {
+                challenge_id: &challenge.id,
+                code,
+            })
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        let parsed: SupabaseAuthResponse = SupabaseError::parse_response(response).await?;
+        info!(user_id = %parsed.user.id, "MFA factor verified");
+        Ok(parsed.into())
+    }
+
+    /// Remove a factor (Supabase requires an aal2 token once the factor is verified)
+    #[instrument(skip(self, access_token))]
+    pub async fn unenroll_factor(
+        &self,
+        access_token: &str,
+        factor_id: &str,
+    ) -> Result<(), SupabaseError> {
+        let endpoint = format!("{}{}/{}", self.url, SUPABASE_FACTORS_PATH, factor_id);
+        debug!(endpoint = %endpoint, "Sending factor unenroll request");
+
+        let response = Client::new()
+            .delete(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        SupabaseError::check_response(response).await
+    }
+
+    /// Set a new password for the user owning `access_token`
+    #[instrument(skip(self, access_token, password))]
+    pub async fn update_password(
+        &self,
+        access_token: &str,
+        password: &str,
+    ) -> Result<(), SupabaseError> {
+        let body = UpdateUserBody {
+            password: Some(password),
+            ..Default::default()
+        };
+        self.update_user(access_token, &body).await
+    }
+
+    /// Attach a phone number (E.164) to the user owning `access_token`
+    /// Supabase texts a code, the number is only set once it is verified (`OtpType::PhoneChange`)
+    #[instrument(skip(self, access_token, phone))]
+    pub async fn request_phone_change(
+        &self,
+        access_token: &str,
+        phone: &str,
+    ) -> Result<(), SupabaseError> {
+        let body = UpdateUserBody {
+            phone: Some(phone),
+            ..Default::default()
+        };
+        self.update_user(access_token, &body).await
+    }
+
+    /// Move the account of `access_token` to a new email address
+    /// Supabase emails a confirmation link, the address only changes once it is followed
+    #[instrument(skip(self, access_token, email))]
+    pub async fn request_email_change(
+        &self,
+        access_token: &str,
+        email: &str,
+    ) -> Result<(), SupabaseError> {
+        let body = UpdateUserBody {
+            email: Some(email),
+            ..Default::default()
+        };
+        self.update_user(access_token, &body).await
+    }
+
+    async fn update_user(
+        &self,
+        access_token: &str,
+        body: &UpdateUserBody<'_>,
+    ) -> Result<(), SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_USER_PATH);
+        debug!(endpoint = %endpoint, "Sending user update request");
+
+        let response = Client::new()
+            .put(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        SupabaseError::check_response(response).await
+    }
 
     /// Register a new user with profile data
+    /// Projects requiring email confirmation answer without a session
     #[instrument(skip(self, password), fields(email = %email, username = %username))]
     pub async fn register(
         &self,
         email: &str,
         password: &str,
         username: &str,
-        phone_country_code: Option<&str>,
-        phone_number: Option<&str>,
-    ) -> Result<User, SupabaseError> {
+        phone: Option<&PhoneNumber>,
+    ) -> Result<SignupOutcome, SupabaseError> {
         let endpoint = format!("{}{}", self.url, SUPABASE_SIGNUP_PATH);
         debug!(endpoint = %endpoint, "Sending register request");
 
+        let phone_country_code = phone.map(|p| format!("+{}", p.country_code));
+        let e164 = phone.map(PhoneNumber::e164);
+
         let response = Client::new()
             .post(&endpoint)
             .header("apikey", &self.anon_key)
@@ -68,17 +433,49 @@ impl SupabaseClient {
                 password,
                 data: RegisterMetadata {
                     username,
-                    phone_country_code,
-                    phone_number,
+                    phone_country_code: phone_country_code.as_deref(),
+                    phone_number: phone.map(|p| p.national.as_str()),
+                    phone: e164.as_deref(),
                 },
             })
             .send()
             .await
             .map_err(SupabaseError::from_reqwest)?;
 
-        let parsed: SupabaseAuthResponse = SupabaseError::parse_response(response).await?;
-        info!(user_id = %parsed.user.id, "Registration successful");
-        Ok(parsed.into())
+        match SupabaseError::parse_response(response).await? {
+            SignupResponse::Session(parsed) => {
+                info!(user_id = %parsed.user.id, "Registration successful");
+                Ok(SignupOutcome::Session((*parsed).into()))
+            }
+            SignupResponse::PendingConfirmation(user) => {
+                info!(user_id = %user.id, "Registration pending email confirmation");
+                Ok(SignupOutcome::PendingConfirmation {
+                    user_id: user.id,
+                    email: user.email,
+                })
+            }
+        }
+    }
+
+    /// Send the signup confirmation email again
+    #[instrument(skip(self, email))]
+    pub async fn resend_confirmation(&self, email: &str) -> Result<(), SupabaseError> {
+        let endpoint = format!("{}{}", self.url, SUPABASE_RESEND_PATH);
+        debug!(endpoint = %endpoint, "Sending resend confirmation request");
+
+        let response = Client::new()
+            .post(&endpoint)
+            .header("apikey", &self.anon_key)
+            .header("Content-Type", "application/json")
+            .json(&ResendBody {
+                kind: OtpType::Signup,
+                email,
+            })
+            .send()
+            .await
+            .map_err(SupabaseError::from_reqwest)?;
+
+        SupabaseError::check_response(response).await
     }
 
     /// Logout user by invalidating their access token with Supabase
@@ -109,6 +506,15 @@ impl SupabaseClient {
     }
 }
 
+// Keys left out of logs
+impl fmt::Debug for SupabaseClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SupabaseClient")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
 impl fmt::Display for SupabaseClient {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "SupabaseClient(url={})", self.url)
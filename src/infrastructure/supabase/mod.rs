@@ -1,6 +1,9 @@
-//! Supabase integration - Authentication provider
+//! Supabase integration - Authentication provider and admin API
 
+mod admin;
 mod client;
 mod types;
 
+pub use admin::SupabaseAdmin;
 pub use client::SupabaseClient;
+pub use types::{AdminUser, OtpProof, OtpType, TotpEnrollment, UserPage, app_metadata_roles};
@@ -1,6 +1,7 @@
 //! Supabase API types - Request/Response structures
 
-use crate::domain::User;
+use crate::domain::{Factor, SignIn, User};
+use crate::shared::phone::PhoneNumber;
 use serde::{Deserialize, Serialize};
 
 // ============================================================================
@@ -13,6 +14,139 @@ pub struct LoginBody<'a> {
     pub password: &'a str,
 }
 
+#[derive(Serialize)]
+pub struct RefreshBody<'a> {
+    pub refresh_token: &'a str,
+}
+
+/// Authorization code + PKCE verifier, exchanged for a session
+#[derive(Serialize)]
+pub struct PkceBody<'a> {
+    pub auth_code: &'a str,
+    pub code_verifier: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct RecoverBody<'a> {
+    pub email: &'a str,
+}
+
+/// Passwordless sign-in request, by email (link/code) or phone (SMS code)
+#[derive(Serialize)]
+pub struct OtpBody<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<&'a str>,
+    pub create_user: bool,
+}
+
+#[derive(Serialize)]
+pub struct ResendBody<'a> {
+    #[serde(rename = "type")]
+    pub kind: OtpType,
+    pub email: &'a str,
+}
+
+/// Enroll a new MFA factor
+#[derive(Serialize)]
+pub struct EnrollFactorBody<'a> {
+    pub factor_type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub friendly_name: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+pub struct VerifyFactorBody<'a> {
+    pub challenge_id: &'a str,
+    pub code: &'a str,
+}
+
+/// Attributes changed through `PUT /user`, only the fields set are sent
+#[derive(Default, Serialize)]
+pub struct UpdateUserBody<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<&'a str>,
+}
+
+/// What a one-time token was issued for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OtpType {
+    Recovery,
+    /// Signup confirmation (legacy email templates)
+    Signup,
+    /// Email confirmation/OTP (current email templates)
+    Email,
+    /// Magic link sign-in (legacy email templates)
+    Magiclink,
+    /// SMS sign-in code
+    Sms,
+    /// SMS code confirming a new phone number
+    PhoneChange,
+}
+
+/// Proof of a one-time token: the hash from an email link, or the code typed by the user
+#[derive(Debug, Clone, Copy)]
+pub enum OtpProof<'a> {
+    TokenHash(&'a str),
+    EmailCode {
+        email: &'a str,
+        code: &'a str,
+    },
+    /// Code received by SMS, `phone` in E.164
+    PhoneCode {
+        phone: &'a str,
+        code: &'a str,
+    },
+}
+
+impl<'a> OtpProof<'a> {
+    /// Email or phone a typed code was sent to, `None` for a token hash
+    pub fn account(&self) -> Option<&'a str> {
+        match *self {
+            Self::TokenHash(_) => None,
+            Self::EmailCode { email, .. } => Some(email),
+            Self::PhoneCode { phone, .. } => Some(phone),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct VerifyBody<'a> {
+    #[serde(rename = "type")]
+    pub kind: OtpType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_hash: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<&'a str>,
+}
+
+impl<'a> VerifyBody<'a> {
+    pub fn new(kind: OtpType, proof: OtpProof<'a>) -> Self {
+        let (token_hash, email, phone, token) = match proof {
+            OtpProof::TokenHash(hash) => (Some(hash), None, None, None),
+            OtpProof::EmailCode { email, code } => (None, Some(email), None, Some(code)),
+            OtpProof::PhoneCode { phone, code } => (None, None, Some(phone), Some(code)),
+        };
+        Self {
+            kind,
+            token_hash,
+            email,
+            phone,
+            token,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct RegisterBody<'a> {
     pub email: &'a str,
@@ -27,6 +161,30 @@ pub struct RegisterMetadata<'a> {
     pub phone_country_code: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub phone_number: Option<&'a str>,
+    /// Same number in E.164, what SMS login and phone verification use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<&'a str>,
+}
+
+/// Admin: create a sign-in link without emailing it
+#[derive(Serialize)]
+pub struct GenerateLinkBody<'a> {
+    #[serde(rename = "type")]
+    pub kind: OtpType,
+    pub email: &'a str,
+}
+
+/// Admin `PUT /admin/users/{id}`, only the fields set are sent
+/// Supabase merges the metadata objects into the stored ones key by key
+#[derive(Default, Serialize)]
+pub struct AdminUpdateUserBody<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_metadata: Option<&'a serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_metadata: Option<&'a serde_json::Value>,
+    /// `12h`, `none` to lift a ban
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ban_duration: Option<&'a str>,
 }
 
 // ============================================================================
@@ -44,6 +202,26 @@ pub struct SupabaseAuthResponse {
     pub user: SupabaseUserRaw,
 }
 
+/// Admin `GET /admin/users` answer
+#[derive(Debug, Deserialize)]
+pub struct AdminUsersResponse {
+    pub users: Vec<AdminUser>,
+}
+
+/// Admin `generate_link` answer (the user fields are ignored)
+#[derive(Debug, Deserialize)]
+pub struct GenerateLinkResponse {
+    pub hashed_token: String,
+}
+
+/// Signup answer: a session, or just the user when email confirmation is required
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum SignupResponse {
+    Session(Box<SupabaseAuthResponse>),
+    PendingConfirmation(Box<SupabaseUserRaw>),
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct SupabaseUserRaw {
@@ -56,16 +234,85 @@ pub struct SupabaseUserRaw {
     created_at: Option<String>,
     email_confirmed_at: Option<String>,
     last_sign_in_at: Option<String>,
+    /// MFA factors, `null` when none were ever enrolled
+    #[serde(default)]
+    factors: Option<Vec<FactorRaw>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FactorRaw {
+    id: String,
+    factor_type: String,
+    status: String,
+    friendly_name: Option<String>,
+}
+
+/// `POST /factors` answer for a TOTP factor
+#[derive(Debug, Deserialize)]
+pub struct EnrollFactorResponse {
+    pub id: String,
+    pub totp: TotpEnrollment,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChallengeResponse {
+    pub id: String,
+}
+
+// ============================================================================
+// OUTCOMES
+// ============================================================================
+
+/// Secret of a TOTP factor being enrolled, shown once to set up the authenticator app
+#[derive(Debug, Clone, Deserialize)]
+pub struct TotpEnrollment {
+    /// SVG data URI of the QR code
+    pub qr_code: String,
+    pub secret: String,
+    /// `otpauth://` URI encoded in the QR code
+    pub uri: String,
+}
+
+/// An account as the admin API sees it - no tokens, all the metadata
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminUser {
+    pub id: String,
+    /// Empty for phone-only accounts
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default)]
+    pub role: String,
+    /// Only writable with the service role (roles live here)
+    #[serde(default)]
+    pub app_metadata: serde_json::Value,
+    /// Writable by the user (username, phone given at signup...)
+    #[serde(default)]
+    pub user_metadata: serde_json::Value,
+    pub created_at: Option<String>,
+    pub email_confirmed_at: Option<String>,
+    pub last_sign_in_at: Option<String>,
+    /// RFC 3339, set while the user is banned
+    pub banned_until: Option<String>,
+}
+
+/// One page of `SupabaseAdmin::list_users`
+#[derive(Debug, Clone)]
+pub struct UserPage {
+    pub users: Vec<AdminUser>,
+    /// Every user in the project, when Supabase reports it
+    pub total: Option<u64>,
 }
 
 // ============================================================================
 // CONVERSION TO DOMAIN MODEL
 // ============================================================================
 
-impl From<SupabaseAuthResponse> for User {
-    fn from(resp: SupabaseAuthResponse) -> Self {
-        let username = resp
-            .user
+impl SupabaseUserRaw {
+    /// Build the domain user, attaching the tokens it was fetched with
+    pub fn into_user(self, access_token: String, refresh_token: String, expires_at: u64) -> User {
+        let username = self
             .user_metadata
             .get("username")
             .and_then(|v| v.as_str())
@@ -73,13 +320,88 @@ impl From<SupabaseAuthResponse> for User {
             .to_string();
 
         User {
-            id: resp.user.id,
-            email: resp.user.email,
+            id: self.id,
+            email: self.email,
             username,
-            role: resp.user.role,
-            access_token: resp.access_token,
-            refresh_token: resp.refresh_token,
-            expires_at: resp.expires_at,
+            role: self.role,
+            roles: app_metadata_roles(&self.app_metadata),
+            access_token,
+            refresh_token,
+            expires_at,
+        }
+    }
+}
+
+impl SupabaseUserRaw {
+    /// E.164 phone number collected at registration, if any
+    /// Accounts created before normalization only have the raw country code and number
+    pub fn registered_phone(&self) -> Option<String> {
+        let meta = &self.user_metadata;
+        if let Some(phone) = meta.get("phone").and_then(|v| v.as_str()) {
+            return Some(phone.to_string());
+        }
+        let country_code = meta.get("phone_country_code")?.as_str()?;
+        let number = meta.get("phone_number")?.as_str()?;
+        PhoneNumber::from_parts(country_code, number)
+            .ok()
+            .map(|p| p.e164())
+    }
+}
+
+impl SupabaseUserRaw {
+    /// Verified factors only: unverified ones are abandoned enrollments
+    pub fn verified_factors(&self) -> Vec<Factor> {
+        self.factors
+            .iter()
+            .flatten()
+            .filter(|f| f.status == "verified")
+            .map(|f| Factor {
+                id: f.id.clone(),
+                factor_type: f.factor_type.clone(),
+                friendly_name: f.friendly_name.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Application roles in `app_metadata` (set with the service role, users cannot change it):
+/// a `roles` array and/or a single `role`. Names that are not plain identifiers are ignored.
+pub fn app_metadata_roles(app_metadata: &serde_json::Value) -> Vec<String> {
+    let listed = app_metadata
+        .get("roles")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|r| r.as_str());
+    let single = app_metadata.get("role").and_then(|r| r.as_str());
+
+    let mut roles: Vec<String> = listed
+        .chain(single)
+        .filter(|r| {
+            !r.is_empty()
+                && r.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+        .map(String::from)
+        .collect();
+    roles.sort();
+    roles.dedup();
+    roles
+}
+
+impl From<SupabaseAuthResponse> for SignIn {
+    fn from(resp: SupabaseAuthResponse) -> Self {
+        let factors = resp.user.verified_factors();
+        Self {
+            user: resp.into(),
+            factors,
         }
     }
 }
+
+impl From<SupabaseAuthResponse> for User {
+    fn from(resp: SupabaseAuthResponse) -> Self {
+        resp.user
+            .into_user(resp.access_token, resp.refresh_token, resp.expires_at)
+    }
+}
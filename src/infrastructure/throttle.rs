@@ -0,0 +1,125 @@
+//! SQLite attempt store - Failed logins and lockouts survive restarts
+
+use crate::domain::{AttemptStore, Reserved};
+use crate::error::StorageError;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior, params};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::info;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS login_failures (
+    key TEXT NOT NULL,
+    at  INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_login_failures_key ON login_failures(key, at);
+CREATE INDEX IF NOT EXISTS idx_login_failures_at ON login_failures(at);
+CREATE TABLE IF NOT EXISTS login_lockouts (
+    key          TEXT PRIMARY KEY,
+    locked_until INTEGER NOT NULL
+);
+";
+
+/// Failed attempts and lockouts in SQLite
+#[derive(Debug)]
+pub struct SqliteAttempts {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteAttempts {
+    /// Open (or create) the database at `path`, `:memory:` for a private in-memory db
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        if let Some(dir) = Path::new(path)
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+
+        info!(path = %path, "Login throttle database opened");
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl AttemptStore for SqliteAttempts {
+    fn failures(&self, key: &str, since: u64) -> Result<Vec<u64>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT at FROM login_failures WHERE key = ?1 AND at >= ?2 ORDER BY at",
+        )?;
+        let failures = stmt
+            .query_map(params![key, since], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<u64>>>()?;
+        Ok(failures)
+    }
+
+    fn reserve(&self, keys: &[String], at: u64, since: u64) -> Result<Vec<Reserved>, StorageError> {
+        let mut conn = self.conn.lock().unwrap();
+        // Write-locked from the start: other processes sharing the file wait for the insert
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        tx.execute("DELETE FROM login_failures WHERE at < ?1", [since])?;
+
+        let mut reserved = Vec::with_capacity(keys.len());
+        for key in keys {
+            let failures = tx
+                .prepare_cached("SELECT at FROM login_failures WHERE key = ?1 ORDER BY at")?
+                .query_map([key], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<u64>>>()?;
+            let locked_until = tx
+                .query_row(
+                    "SELECT locked_until FROM login_lockouts WHERE key = ?1",
+                    [key],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            tx.execute(
+                "INSERT INTO login_failures (key, at) VALUES (?1, ?2)",
+                params![key, at],
+            )?;
+            reserved.push(Reserved {
+                id: tx.last_insert_rowid(),
+                failures,
+                locked_until,
+            });
+        }
+
+        tx.commit()?;
+        Ok(reserved)
+    }
+
+    fn release(&self, ids: &[i64]) -> Result<(), StorageError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for id in ids {
+            tx.execute("DELETE FROM login_failures WHERE rowid = ?1", [id])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn clear_failures(&self, key: &str) -> Result<(), StorageError> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM login_failures WHERE key = ?1", [key])?;
+        Ok(())
+    }
+
+    fn lock(&self, key: &str, until: u64) -> Result<(), StorageError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO login_lockouts (key, locked_until) VALUES (?1, ?2)",
+            params![key, until],
+        )?;
+        tx.execute("DELETE FROM login_failures WHERE key = ?1", [key])?;
+        tx.commit()?;
+        Ok(())
+    }
+}
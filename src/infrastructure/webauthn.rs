@@ -0,0 +1,256 @@
+//! WebAuthn - Passkey ceremonies verified locally
+//!
+//! Covers what passkeys need and nothing more: ES256 (P-256) credentials, `none` attestation
+//! (the authenticator model is not checked) and user verification on every ceremony.
+
+use crate::config::Config;
+use crate::error::WebAuthnError;
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use ciborium::Value;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// COSE algorithm identifier of ES256, the only one offered in `pubKeyCredParams`
+pub const COSE_ES256: i64 = -7;
+
+// Authenticator data flags
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_DATA: u8 = 0x40;
+
+/// Our side of the ceremonies: the RP ID credentials are scoped to, and the origins
+/// allowed to use them
+#[derive(Debug, Clone)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+    origins: Vec<String>,
+}
+
+/// A credential accepted by `verify_registration`
+#[derive(Debug, Clone)]
+pub struct NewCredential {
+    pub id: Vec<u8>,
+    /// SEC1 uncompressed P-256 point
+    pub public_key: Vec<u8>,
+    pub sign_count: u32,
+}
+
+/// The parts of `clientDataJSON` we check
+#[derive(Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+}
+
+/// `rpIdHash (32) | flags (1) | signCount (4) | [aaguid (16) | idLen (2) | id | COSE key]`
+struct AuthenticatorData<'a> {
+    rp_id_hash: &'a [u8],
+    flags: u8,
+    sign_count: u32,
+    /// Credential ID and COSE public key, present on registration only
+    attested: Option<(Vec<u8>, Value)>,
+}
+
+impl RelyingParty {
+    pub fn new(id: &str, name: &str, origins: Vec<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            origins,
+        }
+    }
+
+    /// Origins default to `https://<rp id>`
+    pub fn from_config(cfg: &Config) -> Self {
+        let mut origins: Vec<String> = cfg
+            .webauthn_origins
+            .split(',')
+            .map(|o| o.trim().trim_end_matches('/').to_string())
+            .filter(|o| !o.is_empty())
+            .collect();
+        if origins.is_empty() {
+            origins.push(format!("https://{}", cfg.webauthn_rp_id));
+        }
+        Self::new(&cfg.webauthn_rp_id, &cfg.webauthn_rp_name, origins)
+    }
+
+    /// Check a `navigator.credentials.create()` response against the `challenge` we issued
+    pub fn verify_registration(
+        &self,
+        challenge: &[u8],
+        client_data_json: &[u8],
+        attestation_object: &[u8],
+    ) -> Result<NewCredential, WebAuthnError> {
+        self.check_client_data(client_data_json, "webauthn.create", challenge)?;
+
+        let attestation: Value = ciborium::from_reader(attestation_object)
+            .map_err(|_| WebAuthnError::Malformed("attestation object"))?;
+        // The attestation statement is not verified: we ask for `none`
+        let auth_data = map_get(&attestation, |k| k.as_text() == Some("authData"))
+            .and_then(Value::as_bytes)
+            .ok_or(WebAuthnError::Malformed("attestation object"))?;
+
+        let data = self.check_authenticator_data(auth_data)?;
+        let (id, key) = data
+            .attested
+            .ok_or(WebAuthnError::Malformed("attested credential data"))?;
+
+        Ok(NewCredential {
+            id,
+            public_key: cose_to_sec1(&key)?,
+            sign_count: data.sign_count,
+        })
+    }
+
+    /// Check a `navigator.credentials.get()` response signed with `public_key`
+    /// Returns the new signature counter to store
+    pub fn verify_assertion(
+        &self,
+        challenge: &[u8],
+        client_data_json: &[u8],
+        authenticator_data: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+        stored_count: u32,
+    ) -> Result<u32, WebAuthnError> {
+        self.check_client_data(client_data_json, "webauthn.get", challenge)?;
+        let data = self.check_authenticator_data(authenticator_data)?;
+
+        let key =
+            VerifyingKey::from_sec1_bytes(public_key).map_err(|_| WebAuthnError::UnsupportedKey)?;
+        let signature = Signature::from_der(signature).map_err(|_| WebAuthnError::Signature)?;
+        let mut signed = authenticator_data.to_vec();
+        signed.extend_from_slice(&Sha256::digest(client_data_json));
+        key.verify(&signed, &signature)
+            .map_err(|_| WebAuthnError::Signature)?;
+
+        // Authenticators without a counter (most synced passkeys) always send 0
+        if (data.sign_count != 0 || stored_count != 0) && data.sign_count <= stored_count {
+            return Err(WebAuthnError::Counter {
+                stored: stored_count,
+                received: data.sign_count,
+            });
+        }
+        Ok(data.sign_count)
+    }
+
+    fn check_client_data(
+        &self,
+        json: &[u8],
+        expected: &'static str,
+        challenge: &[u8],
+    ) -> Result<(), WebAuthnError> {
+        let data: ClientData =
+            serde_json::from_slice(json).map_err(|_| WebAuthnError::Malformed("client data"))?;
+
+        if data.kind != expected {
+            return Err(WebAuthnError::Type { expected });
+        }
+        let signed_challenge = URL_SAFE_NO_PAD
+            .decode(data.challenge.trim_end_matches('='))
+            .ok();
+        if signed_challenge.as_deref() != Some(challenge) {
+            return Err(WebAuthnError::Challenge);
+        }
+        if !self.origins.contains(&data.origin) {
+            return Err(WebAuthnError::Origin(data.origin));
+        }
+        Ok(())
+    }
+
+    fn check_authenticator_data<'a>(
+        &self,
+        bytes: &'a [u8],
+    ) -> Result<AuthenticatorData<'a>, WebAuthnError> {
+        let data = AuthenticatorData::parse(bytes)?;
+
+        if data.rp_id_hash != Sha256::digest(self.id.as_bytes()).as_slice() {
+            return Err(WebAuthnError::RpId);
+        }
+        // A passkey replaces the password: holding the device is not enough
+        let required = FLAG_USER_PRESENT | FLAG_USER_VERIFIED;
+        if data.flags & required != required {
+            return Err(WebAuthnError::UserVerification);
+        }
+        Ok(data)
+    }
+}
+
+impl<'a> AuthenticatorData<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, WebAuthnError> {
+        const MALFORMED: WebAuthnError = WebAuthnError::Malformed("authenticator data");
+
+        let (rp_id_hash, rest) = bytes.split_at_checked(32).ok_or(MALFORMED)?;
+        let (&flags, rest) = rest.split_first().ok_or(MALFORMED)?;
+        let (counter, rest) = rest.split_at_checked(4).ok_or(MALFORMED)?;
+        let sign_count = u32::from_be_bytes([counter[0], counter[1], counter[2], counter[3]]);
+
+        let attested = if flags & FLAG_ATTESTED_DATA != 0 {
+            let rest = rest.get(16..).ok_or(MALFORMED)?;
+            let (len, rest) = rest.split_at_checked(2).ok_or(MALFORMED)?;
+            let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+            let (id, mut rest) = rest.split_at_checked(len).ok_or(MALFORMED)?;
+            // Extensions may follow the key: read exactly one CBOR item
+            let key: Value = ciborium::from_reader(&mut rest).map_err(|_| MALFORMED)?;
+            Some((id.to_vec(), key))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rp_id_hash,
+            flags,
+            sign_count,
+            attested,
+        })
+    }
+}
+
+/// SEC1 point of an EC2 / P-256 / ES256 COSE key
+fn cose_to_sec1(key: &Value) -> Result<Vec<u8>, WebAuthnError> {
+    let param = |label: i64| map_get(key, |k| k.as_integer() == Some(label.into()));
+    let int = |label: i64| param(label).and_then(Value::as_integer).map(i128::from);
+    let coordinate = |label: i64| {
+        param(label)
+            .and_then(Value::as_bytes)
+            .filter(|c| c.len() == 32)
+    };
+
+    // kty = EC2, alg = ES256, crv = P-256
+    if int(1) != Some(2) || int(3) != Some(COSE_ES256.into()) || int(-1) != Some(1) {
+        return Err(WebAuthnError::UnsupportedKey);
+    }
+    let (x, y) = coordinate(-2)
+        .zip(coordinate(-3))
+        .ok_or(WebAuthnError::UnsupportedKey)?;
+
+    let mut point = Vec::with_capacity(65);
+    point.push(0x04);
+    point.extend_from_slice(x);
+    point.extend_from_slice(y);
+    // Refuse points off the curve now rather than at the first login
+    VerifyingKey::from_sec1_bytes(&point).map_err(|_| WebAuthnError::UnsupportedKey)?;
+    Ok(point)
+}
+
+fn map_get(map: &Value, matches: impl Fn(&Value) -> bool) -> Option<&Value> {
+    map.as_map()?
+        .iter()
+        .find(|(k, _)| matches(k))
+        .map(|(_, v)| v)
+}
+
+/// Fresh ceremony challenge: 32 random bytes
+pub fn new_challenge() -> Vec<u8> {
+    let mut challenge = vec![0u8; 32];
+    OsRng.fill_bytes(&mut challenge);
+    challenge
+}
@@ -17,10 +17,16 @@ mod shared;
 mod tests;
 
 // Imports
-use actix_web::{middleware::Logger, rt::signal, web, App as ActixApp, HttpServer};
+use actix_web::{
+    App as ActixApp, HttpServer,
+    middleware::{Logger, from_fn},
+    rt::signal,
+    web,
+};
 use app::App;
+use std::time::Duration;
 use tracing::info;
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
 // ============================================================================
 // MAIN
@@ -41,6 +47,15 @@ async fn main() -> std::io::Result<()> {
         "Starting server"
     );
 
+    // Persist session changes, purge expired sessions and refresh tokens in the background
+    app.auth.sessions().spawn_flusher();
+    app.auth
+        .sessions()
+        .spawn_reaper(Duration::from_secs(app.config.session_reap_interval));
+    app.auth.spawn_refresher(Duration::from_secs(
+        app.config.session_refresh_interval.max(1),
+    ));
+
     // Share app state across handlers
     let app_data = web::Data::new(app.clone());
 
@@ -50,6 +65,8 @@ async fn main() -> std::io::Result<()> {
             .app_data(app_data.clone())
             // Request logging middleware
             .wrap(Logger::new("%a \"%r\" %s %b %Dms"))
+            // Per-request tracing span (carries user_id once authenticated)
+            .wrap(from_fn(api::middleware::request_span))
             // Configure routes
             .configure(api::init)
     })
@@ -67,7 +84,12 @@ async fn main() -> std::io::Result<()> {
         srv_handle.stop(true).await;
     });
 
-    server.await
+    let result = server.await;
+
+    // Final flush once in-flight requests are done, so no session change is lost
+    app.auth.sessions().shutdown();
+
+    result
 }
 
 // ============================================================================
@@ -0,0 +1,37 @@
+//! Audit service - Records authentication events and searches them
+
+use crate::config::Config;
+use crate::domain::{AuditEntry, AuditLog, AuditQuery};
+use crate::error::{AppResult, StorageError};
+use crate::infrastructure::JsonlAuditLog;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// Audit service - an entry that cannot be written is logged, the audited action still goes on
+#[derive(Clone, Debug)]
+pub struct AuditService {
+    log: Arc<dyn AuditLog>,
+}
+
+impl AuditService {
+    pub fn new(log: Arc<dyn AuditLog>) -> Self {
+        info!("AuditService initialized");
+        Self { log }
+    }
+
+    /// JSONL log from the configuration
+    pub fn from_config(cfg: &Config) -> Result<Self, StorageError> {
+        Ok(Self::new(Arc::new(JsonlAuditLog::open(&cfg.audit_log)?)))
+    }
+
+    pub fn record(&self, entry: AuditEntry) {
+        if let Err(e) = self.log.append(&entry) {
+            error!(action = %entry.action, error = %e, "Failed to write audit entry");
+        }
+    }
+
+    /// Entries matching `query`, newest first
+    pub fn query(&self, query: &AuditQuery) -> AppResult<Vec<AuditEntry>> {
+        Ok(self.log.query(query)?)
+    }
+}
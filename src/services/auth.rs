@@ -1,91 +1,1247 @@
 //! Authentication service - Orchestrates login, register, logout flows
 
 use crate::config::Config;
-use crate::domain::{SessionStore, User};
-use crate::error::{AppError, AppResult, AuthError};
-use crate::infrastructure::SupabaseClient;
+use crate::domain::{
+    Aal, AuditAction, AuditEntry, AuditOutcome, AuthProvider, ClientInfo, Factor, Session,
+    SessionId, SessionStore, SignIn, SignupOutcome, User,
+};
+use crate::error::{AppError, AppResult, AuthError, SupabaseError};
+use crate::infrastructure::supabase::{OtpProof, OtpType, TotpEnrollment};
+use crate::infrastructure::{
+    AuthProviderKind, JwtVerifier, LocalProvider, OAuthFlows, PendingStore, SupabaseAdmin,
+    SupabaseClient, SupabaseProvider,
+};
+use crate::services::{AuditService, LoginThrottle};
+use crate::shared::constants::auth::{MFA_MAX_ATTEMPTS, MFA_PENDING_TTL_SECS, OAUTH_FLOW_TTL_SECS};
+use crate::shared::jwt::peek_claims;
+use crate::shared::phone::PhoneNumber;
+use crate::shared::time::now_secs;
+use std::collections::HashMap;
 use std::fmt;
-use tracing::{info, instrument};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, instrument, warn};
+
+/// Outcome of a registration
+#[derive(Debug)]
+pub enum Registration {
+    /// Logged in right away
+    Session(Box<Session>),
+    /// Nothing to log in with until the emailed link is followed
+    PendingConfirmation { email: String },
+}
+
+/// Outcome of a first-factor login
+#[derive(Debug)]
+pub enum LoginOutcome {
+    /// Logged in
+    Session(Box<Session>),
+    /// The account has a second factor: no session until `complete_mfa_login`
+    MfaRequired {
+        mfa_token: String,
+        factors: Vec<Factor>,
+    },
+}
+
+/// Tokens of a first-factor login held back until the second factor is verified
+#[derive(Debug, Clone)]
+struct PendingMfa {
+    user: User,
+    client: ClientInfo,
+    factors: Vec<Factor>,
+    // Wrong codes so far, the login is dropped at `MFA_MAX_ATTEMPTS`
+    failures: u32,
+}
 
 /// Authentication service - coordinates auth flows
 #[derive(Clone, Debug)]
 pub struct AuthService {
-    supabase: SupabaseClient,
+    // Checks passwords and issues tokens
+    provider: Arc<dyn AuthProvider>,
+    // Flows only Supabase offers (OTP, OAuth, MFA...), `None` with the local provider
+    supabase: Option<SupabaseClient>,
     sessions: SessionStore,
+    // Link target of password recovery emails
+    password_reset_redirect: Option<String>,
+    // Local bearer JWT verification, `None` falls back to asking Supabase
+    jwt: Option<Arc<JwtVerifier>>,
+    // OAuth providers accepted by `start_oauth`, and sign-ins waiting for their callback
+    oauth_providers: Vec<String>,
+    oauth_flows: OAuthFlows,
+    // First-factor logins waiting for their TOTP code, keyed by `mfa_token`
+    mfa_pending: PendingStore<PendingMfa>,
+    // Failed password logins per account and IP, delays and lockouts
+    throttle: LoginThrottle,
+    // Logins, registrations, logouts, revocations and refreshes
+    audit: AuditService,
+    // Refresh tokens this many seconds before they expire
+    refresh_margin: u64,
+    // One refresh at a time per session: Supabase refresh tokens are single-use
+    refresh_locks: Arc<Mutex<HashMap<SessionId, Arc<tokio::sync::Mutex<()>>>>>,
 }
 
 impl AuthService {
     pub fn new(cfg: &Config, sessions: SessionStore) -> Self {
-        info!("AuthService initialized");
+        let (provider, supabase): (Arc<dyn AuthProvider>, _) = match cfg.auth_provider {
+            AuthProviderKind::Supabase => {
+                let client = SupabaseClient::new(cfg);
+                let provider = SupabaseProvider::new(client.clone(), SupabaseAdmin::new(cfg));
+                (Arc::new(provider), Some(client))
+            }
+            AuthProviderKind::Local => {
+                let local = LocalProvider::open(&cfg.local_auth_db).unwrap_or_else(|e| {
+                    panic!(
+                        "Failed to open local auth database {}: {}",
+                        cfg.local_auth_db, e
+                    )
+                });
+                warn!("Local auth provider: for development, Supabase-only flows are disabled");
+                (Arc::new(local), None)
+            }
+        };
+
+        let jwt = JwtVerifier::from_config(cfg).map(Arc::new);
+        if jwt.is_none() && supabase.is_some() {
+            warn!("SP_JWT_SECRET / SP_JWKS not set, bearer JWTs are verified by calling Supabase");
+        }
+
+        info!(provider = %provider.name(), "AuthService initialized");
         Self {
-            supabase: SupabaseClient::new(cfg),
+            provider,
+            supabase,
             sessions,
+            password_reset_redirect: Some(cfg.password_reset_redirect.clone())
+                .filter(|u| !u.is_empty()),
+            jwt,
+            oauth_providers: cfg
+                .oauth_providers
+                .split(',')
+                .map(|p| p.trim().to_ascii_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            oauth_flows: OAuthFlows::new(OAUTH_FLOW_TTL_SECS),
+            mfa_pending: PendingStore::new(MFA_PENDING_TTL_SECS),
+            throttle: LoginThrottle::from_config(cfg).unwrap_or_else(|e| {
+                panic!(
+                    "Failed to open login throttle database {}: {}",
+                    cfg.login_throttle_db, e
+                )
+            }),
+            audit: AuditService::from_config(cfg)
+                .unwrap_or_else(|e| panic!("Failed to open audit log {}: {}", cfg.audit_log, e)),
+            refresh_margin: cfg.session_refresh_margin,
+            refresh_locks: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Fail as `supabase` would, for handlers that must refuse before asking for any proof
+    pub fn require_supabase(&self, feature: &'static str) -> AppResult<()> {
+        self.supabase(feature).map(|_| ())
+    }
+
+    /// The Supabase client, for flows no other provider offers
+    fn supabase(&self, feature: &'static str) -> AppResult<&SupabaseClient> {
+        self.supabase
+            .as_ref()
+            .ok_or_else(|| AuthError::Unsupported { feature }.into())
+    }
+
+    /// Resolve a session ID to a live session with a usable access token
+    /// Tokens near expiry are refreshed first; a session whose refresh is rejected is invalidated
+    pub async fn current_session(&self, session_id: &str) -> Option<Session> {
+        let session = self.sessions.get_session(session_id)?;
+        if !session.needs_refresh(self.refresh_margin, now_secs()) {
+            return Some(session);
+        }
+        self.refresh_session(session_id).await
+    }
+
+    /// Resolve a Supabase JWT sent as a bearer token (no LAPP session involved), with its `aal`
+    /// Verified locally when keys are configured, otherwise by Supabase
+    #[instrument(skip(self, jwt))]
+    pub async fn authenticate_jwt(&self, jwt: &str) -> AppResult<(User, Aal)> {
+        if let Some(verifier) = &self.jwt {
+            return match verifier.verify(jwt).await {
+                Ok(claims) => {
+                    let aal = claims.aal();
+                    Ok((claims.into_user(jwt), aal))
+                }
+                Err(e) => {
+                    info!(reason = %e, "Bearer JWT rejected");
+                    Err(AuthError::Unauthenticated.into())
+                }
+            };
+        }
+
+        // Local provider tokens are opaque, only LAPP sessions can stand for them
+        let Some(supabase) = &self.supabase else {
+            return Err(AuthError::Unauthenticated.into());
+        };
+
+        // Expired tokens are refused without a round trip
+        let claims = peek_claims(jwt).ok_or(AuthError::Unauthenticated)?;
+        let expires_at = claims
+            .get("exp")
+            .and_then(|exp| exp.as_u64())
+            .filter(|exp| *exp > now_secs())
+            .ok_or(AuthError::Unauthenticated)?;
+
+        match supabase.get_user(jwt, expires_at).await {
+            // Supabase accepted the token, so its claims can be trusted from here
+            Ok(user) => {
+                let aal = claims
+                    .get("aal")
+                    .and_then(|aal| aal.as_str()?.parse().ok())
+                    .unwrap_or_default();
+                Ok((user, aal))
+            }
+            Err(e) if e.is_rejection() => Err(AuthError::Unauthenticated.into()),
+            Err(e) => Err(AuthError::External(e).into()),
+        }
+    }
+
+    /// Refresh the Supabase tokens of a session
+    /// Returns the session with its (possibly already refreshed) tokens, `None` if it is gone
+    /// or its access token can no longer be used
+    #[instrument(skip(self, session_id))]
+    pub async fn refresh_session(&self, session_id: &str) -> Option<Session> {
+        let lock = self
+            .refresh_locks
+            .lock()
+            .unwrap()
+            .entry(session_id.to_string())
+            .or_default()
+            .clone();
+        let result = {
+            let _guard = lock.lock().await;
+            self.refresh_locked(session_id).await
+        };
+
+        // Last one out removes the lock entry (the map holds one reference, `lock` another)
+        let mut locks = self.refresh_locks.lock().unwrap();
+        if locks
+            .get(session_id)
+            .is_some_and(|l| Arc::strong_count(l) <= 2)
+        {
+            locks.remove(session_id);
+        }
+        result
+    }
+
+    /// Refresh body, caller holds the session's refresh lock
+    async fn refresh_locked(&self, session_id: &str) -> Option<Session> {
+        // Re-read under the lock: a concurrent caller may have refreshed already.
+        // Peek: the background refresher must not keep idle sessions alive.
+        let session = self.sessions.peek_session(session_id)?;
+        let now = now_secs();
+        if !session.needs_refresh(self.refresh_margin, now) {
+            return Some(session);
+        }
+
+        let audit = |outcome, detail: &str| {
+            AuditEntry::new(AuditAction::TokenRefresh, outcome, &session.client, now)
+                .user(&session.user.id)
+                .detail(detail)
+        };
+        match self.provider.refresh(&session.user.refresh_token).await {
+            Ok(user) if user.id == session.user.id => {
+                debug!(device_id = %session.device_id, "Access token refreshed");
+                self.audit
+                    .record(audit(AuditOutcome::Success, &session.device_id));
+                self.sessions.update_user(session_id, user)
+            }
+            Ok(user) => {
+                warn!(expected = %session.user.id, got = %user.id, "Token refresh returned another user, invalidating session");
+                self.audit
+                    .record(audit(AuditOutcome::Failure, "user_mismatch"));
+                self.sessions.delete_session(session_id);
+                None
+            }
+            Err(AppError::Auth(AuthError::Unauthenticated)) => {
+                info!(device_id = %session.device_id, "Token refresh rejected, invalidating session");
+                self.audit.record(audit(AuditOutcome::Failure, "rejected"));
+                self.sessions.delete_session(session_id);
+                None
+            }
+            Err(e) => {
+                // Transient failure: keep the session for a later retry, but never hand out a dead token
+                warn!(device_id = %session.device_id, error = %e, "Token refresh failed");
+                self.audit
+                    .record(audit(AuditOutcome::Failure, "unavailable"));
+                (session.user.expires_at > now).then_some(session)
+            }
         }
     }
 
+    /// Spawn a background task refreshing tokens about to expire every `every`,
+    /// so idle-but-live sessions don't wake up with a dead token
+    pub fn spawn_refresher(&self, every: Duration) -> JoinHandle<()> {
+        let auth = self.clone();
+        info!(
+            interval_secs = every.as_secs(),
+            margin_secs = self.refresh_margin,
+            "Token refresher started"
+        );
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                ticker.tick().await;
+
+                let due = auth.sessions.sessions_needing_refresh(auth.refresh_margin);
+                if due.is_empty() {
+                    debug!("Token refresher: nothing to refresh");
+                    continue;
+                }
+
+                let mut refreshed = 0;
+                for session in &due {
+                    if auth.refresh_session(&session.id).await.is_some() {
+                        refreshed += 1;
+                    }
+                }
+                info!(
+                    due = due.len(),
+                    refreshed = refreshed,
+                    "Token refresher run complete"
+                );
+            }
+        })
+    }
+
     /// Login user with email and password
-    /// Returns User on success, AppError on failure (automatically logged)
-    #[instrument(skip(self, password), fields(email = %email))]
-    pub async fn login(&self, email: &str, password: &str) -> AppResult<User> {
-        let user = self
-            .supabase
-            .login(email, password)
+    /// Returns the new Session, or the MFA challenge to pass first; AppError on failure
+    /// Throttled per account and client IP: wrong passwords lead to delays, then a lockout
+    #[instrument(skip(self, password, client), fields(email = %email))]
+    pub async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        client: ClientInfo,
+    ) -> AppResult<LoginOutcome> {
+        let failure = |reason: &AppError| {
+            AuditEntry::new(
+                AuditAction::Login,
+                AuditOutcome::Failure,
+                &client,
+                now_secs(),
+            )
+            .email(email)
+            .detail(reason.code().as_str().to_ascii_lowercase())
+        };
+
+        let attempt = match self.throttle.reserve(email, client.ip.as_deref()) {
+            Ok(attempt) => attempt,
+            Err(e) => {
+                self.audit.record(failure(&e));
+                return Err(e);
+            }
+        };
+
+        let signin = match self.provider.login(email, password).await {
+            Ok(signin) => signin,
+            Err(e @ AppError::Auth(AuthError::InvalidCredentials)) => {
+                self.audit.record(failure(&e));
+                self.throttle.record_failure(attempt)?;
+                return Err(e);
+            }
+            Err(e) => {
+                self.throttle.release(attempt)?;
+                return Err(e);
+            }
+        };
+        self.throttle.record_success(attempt)?;
+        Ok(self.start_session(signin, client, "password"))
+    }
+
+    /// Open a session after a first-factor login, unless the account has a second factor:
+    /// then the tokens wait server-side until `complete_mfa_login`
+    /// `method` (`password`, `otp`, `passkey`, `oauth`) goes to the audit log
+    fn start_session(&self, signin: SignIn, client: ClientInfo, method: &str) -> LoginOutcome {
+        let factors: Vec<Factor> = signin
+            .factors
+            .into_iter()
+            .filter(|f| f.factor_type == "totp")
+            .collect();
+
+        let audit = AuditEntry::new(
+            AuditAction::Login,
+            AuditOutcome::Success,
+            &client,
+            now_secs(),
+        )
+        .user(&signin.user.id)
+        .email(&signin.user.email);
+
+        if factors.is_empty() {
+            self.audit.record(audit.detail(method));
+            let session = self.sessions.create_session(signin.user, client);
+            info!(user_id = %session.user.id, "User logged in");
+            return LoginOutcome::Session(Box::new(session));
+        }
+
+        info!(user_id = %signin.user.id, "User logged in, second factor required");
+        self.audit
+            .record(audit.detail(format!("{}, second factor required", method)));
+        let mfa_token = self.mfa_pending.insert(PendingMfa {
+            user: signin.user,
+            client,
+            factors: factors.clone(),
+            failures: 0,
+        });
+        LoginOutcome::MfaRequired { mfa_token, factors }
+    }
+
+    /// Second step of an MFA login: verify a TOTP `code` and open an aal2 session
+    /// `factor_id` defaults to the user's first factor
+    #[instrument(skip(self, mfa_token, code))]
+    pub async fn complete_mfa_login(
+        &self,
+        mfa_token: &str,
+        factor_id: Option<&str>,
+        code: &str,
+    ) -> AppResult<Session> {
+        let pending = self
+            .mfa_pending
+            .get(mfa_token)
+            .ok_or(AuthError::InvalidOtp)?;
+        let factor = match factor_id {
+            Some(id) => pending.factors.iter().find(|f| f.id == id),
+            None => pending.factors.first(),
+        }
+        .ok_or_else(|| AppError::validation("factor_id", "Unknown factor"))?;
+        let supabase = self.supabase("MFA")?;
+        // Wrong codes count against the account like wrong passwords
+        let attempt = self
+            .throttle
+            .reserve(&pending.user.email, pending.client.ip.as_deref())?;
+
+        // A wrong code keeps the pending login for another try, until it expires or
+        // `MFA_MAX_ATTEMPTS` codes were wrong
+        let user = match supabase
+            .verify_totp(&pending.user.access_token, &factor.id, code)
+            .await
+        {
+            Ok(user) => user,
+            Err(e) if e.is_rejection() => {
+                self.audit.record(
+                    AuditEntry::new(
+                        AuditAction::Login,
+                        AuditOutcome::Failure,
+                        &pending.client,
+                        now_secs(),
+                    )
+                    .user(&pending.user.id)
+                    .email(&pending.user.email)
+                    .detail("totp"),
+                );
+                self.throttle.record_failure(attempt)?;
+                let failures = self.mfa_pending.update(mfa_token, |p| p.failures += 1);
+                if failures.is_some_and(|p| p.failures >= MFA_MAX_ATTEMPTS) {
+                    self.mfa_pending.take(mfa_token);
+                    warn!(user_id = %pending.user.id, "Pending MFA login dropped after too many wrong codes");
+                }
+                return Err(AuthError::InvalidOtp.into());
+            }
+            Err(e) => {
+                self.throttle.release(attempt)?;
+                return Err(AuthError::External(e).into());
+            }
+        };
+        self.throttle.record_success(attempt)?;
+
+        // Concurrent attempts: only the first to finish gets a session
+        if self.mfa_pending.take(mfa_token).is_none() {
+            return Err(AuthError::InvalidOtp.into());
+        }
+        self.audit.record(
+            AuditEntry::new(
+                AuditAction::Login,
+                AuditOutcome::Success,
+                &pending.client,
+                now_secs(),
+            )
+            .user(&user.id)
+            .email(&user.email)
+            .detail("totp"),
+        );
+        let session = self
+            .sessions
+            .create_session_with_aal(user, pending.client, Aal::Aal2);
+        info!(user_id = %session.user.id, "User logged in with second factor");
+        Ok(session)
+    }
+
+    /// The user's verified MFA factors, none with a provider without MFA
+    #[instrument(skip(self, user), fields(user_id = %user.id))]
+    pub async fn list_factors(&self, user: &User) -> AppResult<Vec<Factor>> {
+        let Some(supabase) = &self.supabase else {
+            return Ok(Vec::new());
+        };
+        match supabase.list_factors(&user.access_token).await {
+            Ok(factors) => Ok(factors),
+            Err(e) if e.is_rejection() => Err(AuthError::Unauthenticated.into()),
+            Err(e) => Err(AuthError::External(e).into()),
+        }
+    }
+
+    /// Start enrolling a TOTP factor: returns its ID and the secret for the authenticator app
+    #[instrument(skip(self, user), fields(user_id = %user.id))]
+    pub async fn enroll_totp(
+        &self,
+        user: &User,
+        friendly_name: Option<&str>,
+    ) -> AppResult<(String, TotpEnrollment)> {
+        match self
+            .supabase("MFA")?
+            .enroll_totp(&user.access_token, friendly_name)
+            .await
+        {
+            Ok(enrollment) => {
+                info!(factor_id = %enrollment.0, "TOTP enrollment started");
+                Ok(enrollment)
+            }
+            Err(e) if e.is_rejection() => Err(AppError::validation(
+                "friendly_name",
+                format!("Enrollment refused: {}", e),
+            )),
+            Err(e) => Err(AuthError::External(e).into()),
+        }
+    }
+
+    /// Verify a TOTP code: confirms a new enrollment, or steps a session up to aal2
+    /// The session adopts the aal2 tokens; a bearer JWT caller keeps its own
+    #[instrument(skip(self, session_id, user, code), fields(user_id = %user.id))]
+    pub async fn verify_totp(
+        &self,
+        session_id: Option<&str>,
+        user: &User,
+        factor_id: &str,
+        code: &str,
+    ) -> AppResult<()> {
+        let verified = match self
+            .supabase("MFA")?
+            .verify_totp(&user.access_token, factor_id, code)
             .await
-            .map_err(|e| AppError::Auth(AuthError::from(e)))?;
+        {
+            Ok(verified) => verified,
+            Err(e) if e.is_rejection() => return Err(AuthError::InvalidOtp.into()),
+            Err(e) => return Err(AuthError::External(e).into()),
+        };
 
-        self.sessions.create_session(user.clone());
-        info!(user_id = %user.id, "User logged in");
+        if let Some(session_id) = session_id {
+            self.sessions.elevate(session_id, verified, Aal::Aal2);
+        }
+        info!(factor_id = %factor_id, "TOTP code verified");
+        Ok(())
+    }
 
-        Ok(user)
+    /// Remove one of the user's factors
+    #[instrument(skip(self, user), fields(user_id = %user.id))]
+    pub async fn unenroll_factor(&self, user: &User, factor_id: &str) -> AppResult<()> {
+        match self
+            .supabase("MFA")?
+            .unenroll_factor(&user.access_token, factor_id)
+            .await
+        {
+            Ok(()) => {
+                info!(factor_id = %factor_id, "MFA factor removed");
+                Ok(())
+            }
+            Err(e) if e.is_rejection() => Err(AppError::validation("factor_id", "Unknown factor")),
+            Err(e) => Err(AuthError::External(e).into()),
+        }
     }
 
     /// Register a new user with profile data
-    #[instrument(skip(self, password), fields(email = %email, username = %username))]
+    /// Returns a session, or `PendingConfirmation` when the provider requires email confirmation
+    #[instrument(skip(self, password, client), fields(email = %email, username = %username))]
     pub async fn register(
         &self,
         email: &str,
         password: &str,
         username: &str,
-        phone_country_code: Option<&str>,
-        phone_number: Option<&str>,
-    ) -> AppResult<User> {
-        let user = self
-            .supabase
-            .register(email, password, username, phone_country_code, phone_number)
+        phone: Option<&PhoneNumber>,
+        client: ClientInfo,
+    ) -> AppResult<Registration> {
+        let audit = |outcome| {
+            AuditEntry::new(AuditAction::Register, outcome, &client, now_secs()).email(email)
+        };
+        let outcome = match self
+            .provider
+            .register(email, password, username, phone)
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                self.audit.record(
+                    audit(AuditOutcome::Failure).detail(e.code().as_str().to_ascii_lowercase()),
+                );
+                return Err(e);
+            }
+        };
+
+        match outcome {
+            SignupOutcome::Session(user) => {
+                self.audit
+                    .record(audit(AuditOutcome::Success).user(&user.id));
+                let session = self.sessions.create_session(user, client);
+                info!(user_id = %session.user.id, "User registered");
+                Ok(Registration::Session(Box::new(session)))
+            }
+            SignupOutcome::PendingConfirmation { user_id, email } => {
+                self.audit.record(
+                    audit(AuditOutcome::Success)
+                        .user(&user_id)
+                        .detail("pending_confirmation"),
+                );
+                info!(user_id = %user_id, "User registered, awaiting email confirmation");
+                Ok(Registration::PendingConfirmation { email })
+            }
+        }
+    }
+
+    /// Send the confirmation email again
+    /// Rejections are swallowed so the response never reveals whether the email is registered
+    #[instrument(skip(self, email))]
+    pub async fn resend_confirmation(&self, email: &str) -> AppResult<()> {
+        match self
+            .supabase("Email confirmation")?
+            .resend_confirmation(email)
             .await
-            .map_err(|e| AppError::Auth(AuthError::from(e)))?;
+        {
+            Ok(()) => info!("Confirmation email resent"),
+            Err(SupabaseError::Http { status, body }) => {
+                warn!(status = %status, body = %body, "Confirmation resend refused by Supabase");
+            }
+            Err(e) => return Err(AuthError::External(e).into()),
+        }
+        Ok(())
+    }
 
-        self.sessions.create_session(user.clone());
-        info!(user_id = %user.id, "User registered");
+    /// Email a passwordless sign-in link/code
+    /// Rejections are swallowed so the response never reveals whether the email is registered
+    #[instrument(skip(self, email))]
+    pub async fn send_login_otp(&self, email: &str, create_user: bool) -> AppResult<()> {
+        match self
+            .supabase("Email sign-in")?
+            .send_email_otp(email, create_user)
+            .await
+        {
+            Ok(()) => info!("Sign-in link/code sent"),
+            Err(SupabaseError::Http { status, body }) => {
+                warn!(status = %status, body = %body, "Sign-in OTP refused by Supabase");
+            }
+            Err(e) => return Err(AuthError::External(e).into()),
+        }
+        Ok(())
+    }
 
-        Ok(user)
+    /// Text a sign-in code to a verified phone number (E.164)
+    /// Rejections are swallowed so the response never reveals whether the number is registered
+    #[instrument(skip(self, phone))]
+    pub async fn send_sms_otp(&self, phone: &str) -> AppResult<()> {
+        match self.supabase("SMS sign-in")?.send_sms_otp(phone).await {
+            Ok(()) => info!("Sign-in SMS sent"),
+            Err(SupabaseError::Http { status, body }) => {
+                warn!(status = %status, body = %body, "Sign-in SMS refused by Supabase");
+            }
+            Err(e) => return Err(AuthError::External(e).into()),
+        }
+        Ok(())
     }
 
-    /// Logout user - invalidates session locally and notifies Supabase
-    #[instrument(skip(self, session_id))]
-    pub async fn logout(&self, session_id: &str) -> bool {
+    /// Text a verification code to `phone`, or to the number given at registration
+    /// Returns the E.164 number the code was sent to
+    #[instrument(skip(self, user, phone), fields(user_id = %user.id))]
+    pub async fn start_phone_verification(
+        &self,
+        user: &User,
+        phone: Option<String>,
+    ) -> AppResult<String> {
+        let supabase = self.supabase("Phone verification")?;
+        let phone = match phone {
+            Some(phone) => phone,
+            None => supabase
+                .registered_phone(&user.access_token)
+                .await
+                .map_err(|e| AppError::Auth(AuthError::from(e)))?
+                .ok_or_else(|| AppError::validation("phone", "No phone number on file"))?,
+        };
+
+        match supabase
+            .request_phone_change(&user.access_token, &phone)
+            .await
+        {
+            Ok(()) => {
+                info!("Phone verification code sent");
+                Ok(phone)
+            }
+            Err(e) if e.is_rejection() => Err(AppError::validation(
+                "phone",
+                format!("Phone number rejected: {}", e),
+            )),
+            Err(e) => Err(AuthError::External(e).into()),
+        }
+    }
+
+    /// Confirm the number from `start_phone_verification`, enabling SMS login for it
+    #[instrument(skip(self, session_id, user, phone, code), fields(user_id = %user.id))]
+    pub async fn confirm_phone(
+        &self,
+        session_id: Option<&str>,
+        user: &User,
+        phone: &str,
+        code: &str,
+    ) -> AppResult<()> {
+        let verified = match self
+            .supabase("Phone verification")?
+            .verify_otp(OtpType::PhoneChange, OtpProof::PhoneCode { phone, code })
+            .await
+        {
+            Ok(signin) => signin.user,
+            Err(e) if e.is_rejection() => return Err(AuthError::InvalidOtp.into()),
+            Err(e) => return Err(AuthError::External(e).into()),
+        };
+
+        if verified.id != user.id {
+            warn!(other_user = %verified.id, "Phone code belongs to another user");
+            return Err(AuthError::InvalidOtp.into());
+        }
+
+        // Verification issues fresh tokens; a LAPP session adopts them, a bearer JWT caller
+        // keeps its own (logging the new ones out would be global and end theirs too)
+        if let Some(session_id) = session_id {
+            self.sessions.update_user(session_id, verified);
+        }
+        info!("Phone number verified");
+        Ok(())
+    }
+
+    /// Log in with a one-time token: signup confirmation, magic link, emailed or texted code
+    /// Typed codes are throttled like `login`, per email or E.164 phone and client IP; token
+    /// hashes from links are too long to guess
+    #[instrument(skip(self, proof, client))]
+    pub async fn login_with_otp(
+        &self,
+        kind: OtpType,
+        proof: OtpProof<'_>,
+        client: ClientInfo,
+    ) -> AppResult<LoginOutcome> {
+        let supabase = self.supabase("One-time token login")?;
+        let attempt = match proof.account() {
+            Some(account) => Some(self.throttle.reserve(account, client.ip.as_deref())?),
+            None => None,
+        };
+
+        let signin = match supabase.verify_otp(kind, proof).await {
+            Ok(signin) => signin,
+            Err(e) if e.is_rejection() => {
+                self.audit.record(
+                    AuditEntry::new(
+                        AuditAction::Login,
+                        AuditOutcome::Failure,
+                        &client,
+                        now_secs(),
+                    )
+                    .detail("otp"),
+                );
+                if let Some(attempt) = attempt {
+                    self.throttle.record_failure(attempt)?;
+                }
+                return Err(AuthError::InvalidOtp.into());
+            }
+            Err(e) => {
+                if let Some(attempt) = attempt {
+                    self.throttle.release(attempt)?;
+                }
+                return Err(AuthError::External(e).into());
+            }
+        };
+        if let Some(attempt) = attempt {
+            self.throttle.record_success(attempt)?;
+        }
+
+        info!(kind = ?kind, "One-time token accepted");
+        Ok(self.start_session(signin, client, "otp"))
+    }
+
+    /// Log in a user whose passkey was verified by `PasskeyService`
+    /// The provider issues tokens without the password (with Supabase, through the service
+    /// role), audited as `service_sign_in`; the session is the same as for any other login
+    #[instrument(skip(self, client))]
+    pub async fn login_with_passkey(
+        &self,
+        user_id: &str,
+        client: ClientInfo,
+    ) -> AppResult<LoginOutcome> {
+        let issued = self.provider.login_as(user_id).await;
+        let outcome = match &issued {
+            Ok(_) => AuditOutcome::Success,
+            Err(_) => AuditOutcome::Failure,
+        };
+        self.audit.record(
+            AuditEntry::new(AuditAction::ServiceSignIn, outcome, &client, now_secs())
+                .user(user_id)
+                .detail(format!("passkey via {}", self.provider.name())),
+        );
+
+        match issued {
+            Ok(signin) => Ok(self.start_session(signin, client, "passkey")),
+            // Deleted or banned since the passkey was registered
+            Err(AppError::Auth(AuthError::Unauthenticated)) => {
+                warn!("Auth provider refused the passkey login");
+                self.audit.record(
+                    AuditEntry::new(
+                        AuditAction::Login,
+                        AuditOutcome::Failure,
+                        &client,
+                        now_secs(),
+                    )
+                    .user(user_id)
+                    .detail("passkey"),
+                );
+                Err(AuthError::PasskeyRejected.into())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Start an OAuth sign-in with `provider`, coming back to `callback_url`
+    /// Returns the flow ID to bind to the browser and the URL to send it to
+    #[instrument(skip(self, callback_url))]
+    pub fn start_oauth(&self, provider: &str, callback_url: &str) -> AppResult<(String, String)> {
+        let provider = provider.to_ascii_lowercase();
+        if !self.oauth_providers.contains(&provider) {
+            return Err(AppError::validation(
+                "provider",
+                "Unsupported sign-in provider",
+            ));
+        }
+
+        let supabase = self.supabase("OAuth sign-in")?;
+        let (flow_id, challenge) = self.oauth_flows.start(&provider);
+        let url = supabase.authorize_url(&provider, callback_url, &challenge);
+        info!("OAuth sign-in started");
+        Ok((flow_id, url))
+    }
+
+    /// Finish an OAuth sign-in: `flow_id` from the browser, `code` from the callback query
+    #[instrument(skip(self, flow_id, code, client))]
+    pub async fn finish_oauth(
+        &self,
+        flow_id: Option<&str>,
+        code: Option<&str>,
+        client: ClientInfo,
+    ) -> AppResult<LoginOutcome> {
+        // Consumed even when the callback carries an error, a flow is good for one attempt
+        let flow = flow_id
+            .and_then(|id| self.oauth_flows.take(id))
+            .ok_or(AuthError::OAuthState)?;
+        // No code: the user denied access or the provider failed
+        let code = code.ok_or(AuthError::OAuthFailed)?;
+
+        let signin = match self
+            .supabase("OAuth sign-in")?
+            .exchange_code(code, &flow.verifier)
+            .await
+        {
+            Ok(signin) => signin,
+            Err(e) if e.is_rejection() => {
+                self.audit.record(
+                    AuditEntry::new(
+                        AuditAction::Login,
+                        AuditOutcome::Failure,
+                        &client,
+                        now_secs(),
+                    )
+                    .detail(format!("oauth:{}", flow.provider)),
+                );
+                return Err(AuthError::OAuthFailed.into());
+            }
+            Err(e) => return Err(AuthError::External(e).into()),
+        };
+
+        info!(provider = %flow.provider, "OAuth code accepted");
+        Ok(self.start_session(signin, client, &format!("oauth:{}", flow.provider)))
+    }
+
+    /// Logout user - invalidates session locally and notifies the provider
+    #[instrument(skip(self, session_id, client))]
+    pub async fn logout(&self, session_id: &str, client: ClientInfo) -> bool {
         // Remove from local store and get user data (contains access_token)
         let user = self.sessions.delete_session(session_id);
 
         if let Some(user) = user {
-            // Notify Supabase to invalidate the token (best-effort)
-            self.supabase.logout(&user.access_token).await;
-            info!(session_id = %session_id, "User logged out");
+            self.audit.record(
+                AuditEntry::new(
+                    AuditAction::Logout,
+                    AuditOutcome::Success,
+                    &client,
+                    now_secs(),
+                )
+                .user(&user.id),
+            );
+            // Notify the provider to invalidate the token (best-effort)
+            self.provider.logout(&user.access_token).await;
+            info!(user_id = %user.id, "User logged out");
             true
         } else {
-            info!(session_id = %session_id, "Logout attempted but session not found");
+            info!("Logout attempted but session not found");
             false
         }
     }
 
+    /// Start password recovery - Supabase emails a reset link/code if the account exists
+    /// Rejections are swallowed so the response never reveals whether the email is registered
+    #[instrument(skip(self, email))]
+    pub async fn forgot_password(&self, email: &str) -> AppResult<()> {
+        match self
+            .supabase("Password recovery")?
+            .recover(email, self.password_reset_redirect.as_deref())
+            .await
+        {
+            Ok(()) => info!("Password recovery requested"),
+            Err(SupabaseError::Http { status, body }) => {
+                warn!(status = %status, body = %body, "Password recovery refused by Supabase");
+            }
+            // Transport failures say nothing about the account, report them
+            Err(e) => return Err(AuthError::External(e).into()),
+        }
+        Ok(())
+    }
+
+    /// Finish password recovery: verify the token, set the new password and log the user
+    /// out everywhere. Returns how many LAPP sessions were revoked.
+    #[instrument(skip(self, proof, password))]
+    pub async fn reset_password(&self, proof: OtpProof<'_>, password: &str) -> AppResult<usize> {
+        let supabase = self.supabase("Password recovery")?;
+        let recovery = match supabase.verify_otp(OtpType::Recovery, proof).await {
+            Ok(signin) => signin.user,
+            Err(e) if e.is_rejection() => return Err(AuthError::InvalidResetToken.into()),
+            Err(e) => return Err(AuthError::External(e).into()),
+        };
+
+        let updated = supabase
+            .update_password(&recovery.access_token, password)
+            .await;
+        // The recovery session was only needed for the update
+        supabase.logout(&recovery.access_token).await;
+
+        match updated {
+            Ok(()) => {}
+            Err(e) if e.is_rejection() => {
+                return Err(AppError::validation(
+                    "password",
+                    format!("Password rejected: {}", e),
+                ));
+            }
+            Err(e) => return Err(AuthError::External(e).into()),
+        }
+
+        // Whoever knew the old password must not stay logged in
+        let revoked = self.end_sessions(&recovery.id, "password_reset").await;
+
+        info!(user_id = %recovery.id, revoked, "Password reset");
+        Ok(revoked)
+    }
+
+    /// End every session of a user (password reset, ban...), `reason` goes to the audit log
+    /// Returns how many were ended
+    #[instrument(skip(self))]
+    pub async fn end_sessions(&self, user_id: &str, reason: &str) -> usize {
+        let revoked = self.sessions.delete_user_sessions(user_id);
+        for session in &revoked {
+            self.audit.record(
+                AuditEntry::new(
+                    AuditAction::SessionRevoked,
+                    AuditOutcome::Success,
+                    &session.client,
+                    now_secs(),
+                )
+                .user(user_id)
+                .detail(reason),
+            );
+            self.provider.logout(&session.user.access_token).await;
+        }
+        revoked.len()
+    }
+
+    /// Change the password of a logged-in user, `current` is checked by logging in with it
+    /// (throttled like `login`). Every other session of the user is revoked, the one asking
+    /// (`session_id`, `None` for bearer JWT callers) stays. Returns how many were revoked.
+    #[instrument(skip(self, session_id, user, current, password, client), fields(user_id = %user.id))]
+    pub async fn change_password(
+        &self,
+        session_id: Option<&str>,
+        user: &User,
+        current: &str,
+        password: &str,
+        client: ClientInfo,
+    ) -> AppResult<usize> {
+        let supabase = self.supabase("Password change")?;
+        if !self
+            .check_password(user, current, AuditAction::PasswordChange, &client)
+            .await?
+        {
+            return Err(AppError::validation(
+                "current_password",
+                "Current password is incorrect",
+            ));
+        }
+
+        match supabase.update_password(&user.access_token, password).await {
+            Ok(()) => {}
+            Err(e) if e.is_rejection() => {
+                return Err(AppError::validation(
+                    "new_password",
+                    format!("Password rejected: {}", e),
+                ));
+            }
+            Err(e) => return Err(AuthError::External(e).into()),
+        }
+        self.audit.record(
+            AuditEntry::new(
+                AuditAction::PasswordChange,
+                AuditOutcome::Success,
+                &client,
+                now_secs(),
+            )
+            .user(&user.id)
+            .email(&user.email),
+        );
+
+        // Whoever knew the old password must not stay logged in elsewhere
+        let others: Vec<_> = self
+            .sessions
+            .list_user_sessions(&user.id)
+            .into_iter()
+            .filter(|s| Some(s.id.as_str()) != session_id)
+            .collect();
+        let mut revoked = 0;
+        for session in others {
+            let Some(session) = self
+                .sessions
+                .delete_user_session(&user.id, &session.device_id)
+            else {
+                continue;
+            };
+            self.audit.record(
+                AuditEntry::new(
+                    AuditAction::SessionRevoked,
+                    AuditOutcome::Success,
+                    &session.client,
+                    now_secs(),
+                )
+                .user(&user.id)
+                .detail("password_change"),
+            );
+            self.provider.logout(&session.user.access_token).await;
+            revoked += 1;
+        }
+
+        info!(revoked, "Password changed");
+        Ok(revoked)
+    }
+
+    /// Re-check the password of a logged-in user before a sensitive `action`, by logging in
+    /// with it (throttled like `login`). A wrong password is audited as a failed `action`.
+    #[instrument(skip(self, user, password, client), fields(user_id = %user.id))]
+    pub async fn check_password(
+        &self,
+        user: &User,
+        password: &str,
+        action: AuditAction,
+        client: &ClientInfo,
+    ) -> AppResult<bool> {
+        let attempt = self.throttle.reserve(&user.email, client.ip.as_deref())?;
+
+        match self.provider.login(&user.email, password).await {
+            Ok(signin) => {
+                self.throttle.record_success(attempt)?;
+                // The session was only needed to prove the password
+                self.provider.logout(&signin.user.access_token).await;
+                Ok(true)
+            }
+            Err(AppError::Auth(AuthError::InvalidCredentials)) => {
+                self.audit.record(
+                    AuditEntry::new(action, AuditOutcome::Failure, client, now_secs())
+                        .user(&user.id)
+                        .email(&user.email)
+                        .detail("wrong_password"),
+                );
+                self.throttle.record_failure(attempt)?;
+                Ok(false)
+            }
+            Err(e) => {
+                self.throttle.release(attempt)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Same as `check_password`, for accounts without one (OAuth, passkey, email sign-in):
+    /// checks a sign-in code emailed by `send_login_otp`. A wrong code is audited as a failed
+    /// `action`.
+    #[instrument(skip(self, user, code, client), fields(user_id = %user.id))]
+    pub async fn check_email_code(
+        &self,
+        user: &User,
+        code: &str,
+        action: AuditAction,
+        client: &ClientInfo,
+    ) -> AppResult<bool> {
+        let supabase = self.supabase("Email code re-authentication")?;
+        let attempt = self.throttle.reserve(&user.email, client.ip.as_deref())?;
+
+        let proof = OtpProof::EmailCode {
+            email: &user.email,
+            code,
+        };
+        match supabase.verify_otp(OtpType::Email, proof).await {
+            Ok(signin) if signin.user.id == user.id => {
+                self.throttle.record_success(attempt)?;
+                // The session was only needed to prove the code
+                self.provider.logout(&signin.user.access_token).await;
+                Ok(true)
+            }
+            Ok(signin) => {
+                warn!(other_user = %signin.user.id, "Email code belongs to another user");
+                self.provider.logout(&signin.user.access_token).await;
+                self.throttle.release(attempt)?;
+                Ok(false)
+            }
+            Err(e) if e.is_rejection() => {
+                self.audit.record(
+                    AuditEntry::new(action, AuditOutcome::Failure, client, now_secs())
+                        .user(&user.id)
+                        .email(&user.email)
+                        .detail("wrong_code"),
+                );
+                self.throttle.record_failure(attempt)?;
+                Ok(false)
+            }
+            Err(e) => {
+                self.throttle.release(attempt)?;
+                Err(AuthError::External(e).into())
+            }
+        }
+    }
+
+    /// Email address of an account, `None` if the provider has none with this ID
+    pub async fn account_email(&self, user_id: &str) -> AppResult<Option<String>> {
+        self.provider.account_email(user_id).await
+    }
+
+    /// Delete an account with the provider and end all its sessions, by its owner or by
+    /// the admin `deleted_by`. Local data of the apps, roles, entitlements and passkeys is
+    /// erased afterwards, see `App::erase_user_data`. Returns how many sessions were ended.
+    #[instrument(skip(self, email, client))]
+    pub async fn delete_account(
+        &self,
+        user_id: &str,
+        email: &str,
+        deleted_by: Option<&str>,
+        client: ClientInfo,
+    ) -> AppResult<usize> {
+        self.provider.delete_user(user_id).await?;
+
+        // The provider dropped their tokens with the account, only the LAPP sessions remain
+        let ended = self.sessions.delete_user_sessions(user_id).len();
+        let mut audit = AuditEntry::new(
+            AuditAction::AccountDeleted,
+            AuditOutcome::Success,
+            &client,
+            now_secs(),
+        )
+        .user(user_id)
+        .email(email);
+        if let Some(admin) = deleted_by {
+            audit = audit.detail(format!("by {}", admin));
+        }
+        self.audit.record(audit);
+
+        info!(sessions = ended, "Account deleted");
+        Ok(ended)
+    }
+
+    /// Move a logged-in user to a new email address; the caller has re-authenticated them.
+    /// Supabase emails a confirmation link, the account keeps its address until it is followed
+    #[instrument(skip(self, user, email, client), fields(user_id = %user.id))]
+    pub async fn change_email(
+        &self,
+        user: &User,
+        email: &str,
+        client: ClientInfo,
+    ) -> AppResult<()> {
+        let supabase = self.supabase("Email change")?;
+        let audit = AuditEntry::new(
+            AuditAction::EmailChange,
+            AuditOutcome::Success,
+            &client,
+            now_secs(),
+        )
+        .user(&user.id)
+        .email(email);
+
+        match supabase
+            .request_email_change(&user.access_token, email)
+            .await
+        {
+            Ok(()) => {
+                self.audit.record(audit.detail("confirmation_sent"));
+                info!("Email change requested");
+                Ok(())
+            }
+            Err(e) if e.is_rejection() => Err(AppError::validation(
+                "email",
+                format!("Email address rejected: {}", e),
+            )),
+            Err(e) => Err(AuthError::External(e).into()),
+        }
+    }
+
+    /// Revoke one of the user's sessions (e.g. a lost phone) by its device ID
+    /// `client` is the device asking, audited with the revoked device ID
+    #[instrument(skip(self, client))]
+    pub async fn revoke_session(&self, user_id: &str, device_id: &str, client: ClientInfo) -> bool {
+        let Some(session) = self.sessions.delete_user_session(user_id, device_id) else {
+            info!("Revocation attempted but session not found");
+            return false;
+        };
+        self.audit.record(
+            AuditEntry::new(
+                AuditAction::SessionRevoked,
+                AuditOutcome::Success,
+                &client,
+                now_secs(),
+            )
+            .user(user_id)
+            .detail(device_id),
+        );
+
+        self.provider.logout(&session.user.access_token).await;
+        info!("Session revoked");
+        true
+    }
+
     /// Get session store reference (for user lookups)
     pub fn sessions(&self) -> &SessionStore {
         &self.sessions
     }
+
+    /// Audit log of authentication events
+    pub fn audit(&self) -> &AuditService {
+        &self.audit
+    }
 }
 
 impl fmt::Display for AuthService {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "AuthService({})", self.supabase)
+        write!(f, "AuthService(provider={})", self.provider.name())
     }
 }
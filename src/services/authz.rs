@@ -0,0 +1,114 @@
+//! Authorization service - Resolves a user's roles and checks their permissions
+
+use crate::config::Config;
+use crate::domain::{Permission, ROLES, RoleStore, User, UserId, role_permissions};
+use crate::error::{AppError, AppResult, AuthError, StorageError};
+use crate::infrastructure::SqliteRoles;
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+/// What a user may do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grants {
+    /// Every role of the user, sorted
+    pub roles: Vec<String>,
+    /// Permissions granted by those roles, sorted
+    pub permissions: Vec<Permission>,
+}
+
+impl Grants {
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+/// Authorization service - roles come with the user (Supabase role claim and `app_metadata`)
+/// and from the local role store
+#[derive(Clone, Debug)]
+pub struct AuthzService {
+    roles: Arc<dyn RoleStore>,
+}
+
+impl AuthzService {
+    pub fn new(roles: Arc<dyn RoleStore>) -> Self {
+        info!("AuthzService initialized");
+        Self { roles }
+    }
+
+    /// SQLite role store from the configuration
+    pub fn from_config(cfg: &Config) -> Result<Self, StorageError> {
+        Ok(Self::new(Arc::new(SqliteRoles::open(&cfg.roles_db)?)))
+    }
+
+    /// Roles and permissions of `user`
+    pub fn grants(&self, user: &User) -> AppResult<Grants> {
+        let mut roles = self.roles.roles(&user.id)?;
+        roles.extend(user.roles.iter().cloned());
+        if !user.role.is_empty() {
+            roles.push(user.role.clone());
+        }
+        roles.sort();
+        roles.dedup();
+
+        let mut permissions: Vec<Permission> = roles
+            .iter()
+            .flat_map(|r| role_permissions(r).iter().copied())
+            .collect();
+        permissions.sort();
+        permissions.dedup();
+
+        Ok(Grants { roles, permissions })
+    }
+
+    pub fn has_role(&self, user: &User, role: &str) -> AppResult<bool> {
+        Ok(self.grants(user)?.roles.iter().any(|r| r == role))
+    }
+
+    /// `Ok` if one of the user's roles grants `permission`, `AuthError::Forbidden` otherwise
+    pub fn require(&self, user: &User, permission: Permission) -> AppResult<()> {
+        if self.grants(user)?.allows(permission) {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden {
+                permission: permission.as_str(),
+            }
+            .into())
+        }
+    }
+
+    /// Roles granted to a user in the local store (not those from Supabase)
+    pub fn granted_roles(&self, user_id: &str) -> AppResult<Vec<String>> {
+        Ok(self.roles.roles(user_id)?)
+    }
+
+    /// Grant a built-in role, `false` if the user already had it
+    #[instrument(skip(self))]
+    pub fn grant(&self, user_id: &UserId, role: &str, granted_by: &UserId) -> AppResult<bool> {
+        if !ROLES.iter().any(|(name, _)| *name == role) {
+            return Err(AppError::validation("role", "Unknown role"));
+        }
+        let granted = self.roles.grant(user_id, role, granted_by)?;
+        if granted {
+            info!("Role granted");
+        }
+        Ok(granted)
+    }
+
+    /// Revoke a locally granted role, `false` if the user did not have it
+    #[instrument(skip(self))]
+    pub fn revoke(&self, user_id: &str, role: &str) -> AppResult<bool> {
+        let revoked = self.roles.revoke(user_id, role)?;
+        if revoked {
+            info!("Role revoked");
+        }
+        Ok(revoked)
+    }
+
+    /// Revoke every locally granted role of a closed account
+    #[instrument(skip(self))]
+    pub fn forget_user(&self, user_id: &str) -> AppResult<usize> {
+        let revoked = self.roles.revoke_all(user_id)?;
+        info!(revoked, "Roles of closed account revoked");
+        Ok(revoked)
+    }
+}
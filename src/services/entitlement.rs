@@ -0,0 +1,123 @@
+//! Entitlement service - Which users may use which app
+
+use crate::config::Config;
+use crate::domain::{AppId, AppInstance, Entitlement, EntitlementStatus, EntitlementStore, UserId};
+use crate::error::{AppError, AppResult, AuthError, StorageError};
+use crate::infrastructure::SqliteEntitlements;
+use crate::shared::time::now_secs;
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+/// Entitlement service - nobody may use an app until an admin grants them access
+#[derive(Clone, Debug)]
+pub struct EntitlementService {
+    entitlements: Arc<dyn EntitlementStore>,
+    // Apps hosted on the platform
+    apps: Vec<AppInstance>,
+}
+
+impl EntitlementService {
+    pub fn new(entitlements: Arc<dyn EntitlementStore>, apps: Vec<AppInstance>) -> Self {
+        info!(apps = apps.len(), "EntitlementService initialized");
+        Self { entitlements, apps }
+    }
+
+    /// SQLite store from the configuration
+    pub fn from_config(cfg: &Config, apps: Vec<AppInstance>) -> Result<Self, StorageError> {
+        let entitlements = SqliteEntitlements::open(&cfg.entitlements_db)?;
+        Ok(Self::new(Arc::new(entitlements), apps))
+    }
+
+    /// Apps hosted on the platform
+    pub fn apps(&self) -> &[AppInstance] {
+        &self.apps
+    }
+
+    /// A hosted app, validation error for unknown IDs
+    pub fn app(&self, app_id: &str) -> AppResult<&AppInstance> {
+        self.apps
+            .iter()
+            .find(|app| app.id == app_id)
+            .ok_or_else(|| AppError::validation("app_id", "Unknown app"))
+    }
+
+    /// `Ok` with the entitlement if the user may use the app right now
+    pub fn check(&self, user_id: &str, app_id: AppId) -> AppResult<Entitlement> {
+        match self.entitlements.get(app_id, user_id)? {
+            Some(e) if e.is_active(now_secs()) => Ok(e),
+            Some(e) if e.status == EntitlementStatus::Trial => {
+                Err(AuthError::TrialExpired { app: app_id }.into())
+            }
+            _ => Err(AuthError::NotEntitled { app: app_id }.into()),
+        }
+    }
+
+    /// Entitlements of a user, including revoked and expired ones
+    pub fn for_user(&self, user_id: &str) -> AppResult<Vec<Entitlement>> {
+        Ok(self.entitlements.list_user(user_id)?)
+    }
+
+    /// Entitlements to an app, most recently changed first
+    pub fn list(&self, app_id: &str) -> AppResult<Vec<Entitlement>> {
+        let app = self.app(app_id)?;
+        Ok(self.entitlements.list_app(app.id)?)
+    }
+
+    /// Give a user access to an app, for `trial_secs` or until revoked
+    /// Replaces any previous entitlement (a trial can be turned into full access and back)
+    #[instrument(skip(self))]
+    pub fn grant(
+        &self,
+        app_id: &str,
+        user_id: &UserId,
+        trial_secs: Option<u64>,
+        granted_by: &UserId,
+    ) -> AppResult<Entitlement> {
+        let app = self.app(app_id)?;
+        let now = now_secs();
+        let entitlement = Entitlement {
+            app_id: app.id.to_string(),
+            user_id: user_id.clone(),
+            status: match trial_secs {
+                Some(_) => EntitlementStatus::Trial,
+                None => EntitlementStatus::Granted,
+            },
+            expires_at: trial_secs.map(|secs| now + secs),
+            updated_by: granted_by.clone(),
+            updated_at: now,
+        };
+        self.entitlements.put(&entitlement)?;
+
+        info!(status = %entitlement.status, "Entitlement granted");
+        Ok(entitlement)
+    }
+
+    /// Take away a user's access to an app, `false` if they had none
+    #[instrument(skip(self))]
+    pub fn revoke(&self, app_id: &str, user_id: &str, revoked_by: &UserId) -> AppResult<bool> {
+        let app = self.app(app_id)?;
+        let Some(mut entitlement) = self.entitlements.get(app.id, user_id)? else {
+            return Ok(false);
+        };
+        if entitlement.status == EntitlementStatus::Revoked {
+            return Ok(false);
+        }
+
+        entitlement.status = EntitlementStatus::Revoked;
+        entitlement.expires_at = None;
+        entitlement.updated_by = revoked_by.clone();
+        entitlement.updated_at = now_secs();
+        self.entitlements.put(&entitlement)?;
+
+        info!("Entitlement revoked");
+        Ok(true)
+    }
+
+    /// Delete every entitlement of a closed account, revoked ones included
+    #[instrument(skip(self))]
+    pub fn forget_user(&self, user_id: &str) -> AppResult<usize> {
+        let removed = self.entitlements.remove_user(user_id)?;
+        info!(removed, "Entitlements of closed account removed");
+        Ok(removed)
+    }
+}
@@ -3,6 +3,18 @@
 //! Services coordinate between domain entities and infrastructure.
 //! They contain the core business rules and workflows.
 
+mod audit;
 mod auth;
+mod authz;
+mod entitlement;
+mod passkey;
+mod throttle;
+mod user_admin;
 
-pub use auth::AuthService;
+pub use audit::AuditService;
+pub use auth::{AuthService, LoginOutcome, Registration};
+pub use authz::{AuthzService, Grants};
+pub use entitlement::EntitlementService;
+pub use passkey::{Assertion, Attestation, Ceremony, PasskeyService};
+pub use throttle::LoginThrottle;
+pub use user_admin::UserAdminService;
@@ -0,0 +1,240 @@
+//! Passkey service - WebAuthn registration and login ceremonies
+
+use crate::config::Config;
+use crate::domain::{Passkey, PasskeyBackend, User, UserId};
+use crate::error::{AppError, AppResult, AuthError, StorageError, WebAuthnError};
+use crate::infrastructure::webauthn::new_challenge;
+use crate::infrastructure::{PendingStore, RelyingParty, SqlitePasskeys};
+use crate::shared::constants::auth::PASSKEY_CEREMONY_TTL_SECS;
+use crate::shared::time::now_secs;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+
+/// A ceremony in progress: the challenge the authenticator must sign, and the ID the
+/// client sends back with its response
+#[derive(Debug, Clone)]
+pub struct Ceremony {
+    pub id: String,
+    pub challenge: Vec<u8>,
+}
+
+/// What the browser sent back from `navigator.credentials.create()`, decoded
+pub struct Attestation<'a> {
+    pub client_data_json: &'a [u8],
+    pub attestation_object: &'a [u8],
+}
+
+/// What the browser sent back from `navigator.credentials.get()`, decoded
+pub struct Assertion<'a> {
+    /// Credential ID, base64url
+    pub credential_id: &'a str,
+    pub client_data_json: &'a [u8],
+    pub authenticator_data: &'a [u8],
+    pub signature: &'a [u8],
+}
+
+#[derive(Debug, Clone)]
+struct PendingRegistration {
+    user_id: UserId,
+    // Session that re-authenticated to start it, the only one allowed to finish it
+    device_id: Option<String>,
+    challenge: Vec<u8>,
+}
+
+/// Passkey service - verifies ceremonies and owns the credential store
+/// Logging in with the verified user is left to `AuthService::login_with_passkey`
+#[derive(Clone, Debug)]
+pub struct PasskeyService {
+    rp: RelyingParty,
+    passkeys: Arc<dyn PasskeyBackend>,
+    // Challenges waiting for the authenticator's answer, keyed by ceremony ID
+    registrations: PendingStore<PendingRegistration>,
+    logins: PendingStore<Vec<u8>>,
+}
+
+impl PasskeyService {
+    pub fn new(rp: RelyingParty, passkeys: Arc<dyn PasskeyBackend>) -> Self {
+        info!(rp_id = %rp.id, "PasskeyService initialized");
+        Self {
+            rp,
+            passkeys,
+            registrations: PendingStore::new(PASSKEY_CEREMONY_TTL_SECS),
+            logins: PendingStore::new(PASSKEY_CEREMONY_TTL_SECS),
+        }
+    }
+
+    /// Relying party and SQLite store from the configuration
+    pub fn from_config(cfg: &Config) -> Result<Self, StorageError> {
+        let passkeys = SqlitePasskeys::open(&cfg.passkey_db)?;
+        Ok(Self::new(
+            RelyingParty::from_config(cfg),
+            Arc::new(passkeys),
+        ))
+    }
+
+    pub fn relying_party(&self) -> &RelyingParty {
+        &self.rp
+    }
+
+    /// Passkeys of a user, oldest first
+    pub fn list(&self, user_id: &str) -> AppResult<Vec<Passkey>> {
+        Ok(self.passkeys.list(user_id)?)
+    }
+
+    /// Start registering a passkey for `user`, from the session `device_id` (`None` for bearer
+    /// tokens). Callers must have re-authenticated the user first.
+    /// Returns the ceremony and the user's current passkeys, which the authenticator must not
+    /// register twice
+    pub fn start_registration(
+        &self,
+        user: &User,
+        device_id: Option<&str>,
+    ) -> AppResult<(Ceremony, Vec<Passkey>)> {
+        let existing = self.passkeys.list(&user.id)?;
+        let challenge = new_challenge();
+        let id = self.registrations.insert(PendingRegistration {
+            user_id: user.id.clone(),
+            device_id: device_id.map(str::to_string),
+            challenge: challenge.clone(),
+        });
+        Ok((Ceremony { id, challenge }, existing))
+    }
+
+    /// Verify the authenticator's answer and store the new passkey
+    #[instrument(skip(self, user, response), fields(user_id = %user.id))]
+    pub fn finish_registration(
+        &self,
+        ceremony_id: &str,
+        user: &User,
+        device_id: Option<&str>,
+        name: &str,
+        response: Attestation<'_>,
+    ) -> AppResult<Passkey> {
+        // A ceremony started by another account or session is as good as an unknown one
+        let pending = self
+            .registrations
+            .take(ceremony_id)
+            .filter(|p| p.user_id == user.id && p.device_id.as_deref() == device_id)
+            .ok_or(AuthError::PasskeyRejected)?;
+
+        let credential = self
+            .rp
+            .verify_registration(
+                &pending.challenge,
+                response.client_data_json,
+                response.attestation_object,
+            )
+            .map_err(rejected)?;
+
+        let id = URL_SAFE_NO_PAD.encode(&credential.id);
+        if self.passkeys.get(&id)?.is_some() {
+            return Err(AppError::validation(
+                "credential",
+                "Passkey already registered",
+            ));
+        }
+
+        let passkey = Passkey {
+            id,
+            user_id: user.id.clone(),
+            name: name.to_string(),
+            public_key: credential.public_key,
+            sign_count: credential.sign_count,
+            created_at: now_secs(),
+            last_used_at: None,
+            registered_by: pending.device_id,
+        };
+        self.passkeys.insert(&passkey)?;
+
+        info!(credential_id = %passkey.id, "Passkey registered");
+        Ok(passkey)
+    }
+
+    /// Start a login: any passkey of this site may answer (discoverable credentials)
+    pub fn start_login(&self) -> Ceremony {
+        let challenge = new_challenge();
+        let id = self.logins.insert(challenge.clone());
+        Ceremony { id, challenge }
+    }
+
+    /// Verify a signed login challenge, returning the passkey's owner
+    #[instrument(skip(self, response), fields(credential_id = %response.credential_id))]
+    pub fn finish_login(&self, ceremony_id: &str, response: Assertion<'_>) -> AppResult<UserId> {
+        let passkey = self.verify_assertion(ceremony_id, response)?;
+        info!(user_id = %passkey.user_id, "Passkey verified");
+        Ok(passkey.user_id)
+    }
+
+    /// Verify a signed login challenge as a fresh proof from `user_id` (e.g. before closing
+    /// the account), `false` if it fails, the passkey is someone else's or it was registered by
+    /// the session asking, `device_id`: a hijacked session must not vouch for itself
+    pub fn confirm(
+        &self,
+        user_id: &str,
+        device_id: Option<&str>,
+        ceremony_id: &str,
+        response: Assertion<'_>,
+    ) -> AppResult<bool> {
+        match self.verify_assertion(ceremony_id, response) {
+            Ok(passkey) if passkey.user_id != user_id => Ok(false),
+            Ok(passkey) if device_id.is_some() && passkey.registered_by.as_deref() == device_id => {
+                warn!(credential_id = %passkey.id, "Passkey registered by this session refused");
+                Ok(false)
+            }
+            Ok(_) => Ok(true),
+            Err(AppError::Auth(AuthError::PasskeyRejected)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn verify_assertion(&self, ceremony_id: &str, response: Assertion<'_>) -> AppResult<Passkey> {
+        let challenge = self
+            .logins
+            .take(ceremony_id)
+            .ok_or(AuthError::PasskeyRejected)?;
+        let passkey = self
+            .passkeys
+            .get(response.credential_id)?
+            .ok_or_else(|| rejected(WebAuthnError::UnknownCredential))?;
+
+        let sign_count = self
+            .rp
+            .verify_assertion(
+                &challenge,
+                response.client_data_json,
+                response.authenticator_data,
+                response.signature,
+                &passkey.public_key,
+                passkey.sign_count,
+            )
+            .map_err(rejected)?;
+        self.passkeys.touch(&passkey.id, sign_count, now_secs())?;
+        Ok(passkey)
+    }
+
+    /// Delete one of the user's passkeys, `false` if they have none with this ID
+    #[instrument(skip(self))]
+    pub fn remove(&self, user_id: &str, credential_id: &str) -> AppResult<bool> {
+        let removed = self.passkeys.remove(user_id, credential_id)?;
+        if removed {
+            info!("Passkey removed");
+        }
+        Ok(removed)
+    }
+
+    /// Delete every passkey of a closed account
+    #[instrument(skip(self))]
+    pub fn forget_user(&self, user_id: &str) -> AppResult<usize> {
+        let removed = self.passkeys.remove_all(user_id)?;
+        info!(removed, "Passkeys of closed account removed");
+        Ok(removed)
+    }
+}
+
+/// Clients learn that the ceremony failed, the reason stays in the logs
+fn rejected(reason: WebAuthnError) -> AppError {
+    warn!(reason = %reason, "Passkey verification failed");
+    AuthError::PasskeyRejected.into()
+}
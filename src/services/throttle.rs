@@ -0,0 +1,151 @@
+//! Login throttle - Slows down and locks out password guessing
+
+use crate::config::Config;
+use crate::domain::{AttemptStore, Reserved, ThrottleKey, ThrottlePolicy};
+use crate::error::{AppResult, AuthError, StorageError};
+use crate::infrastructure::SqliteAttempts;
+use crate::shared::constants::auth::MAX_LOGIN_DELAY_SECS;
+use crate::shared::time::now_secs;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Login throttle - failed logins are counted per account and per client IP over a sliding window
+/// Repeated failures on an account impose growing delays, then lock it out for a while
+#[derive(Clone, Debug)]
+pub struct LoginThrottle {
+    policy: ThrottlePolicy,
+    attempts: Arc<dyn AttemptStore>,
+}
+
+impl LoginThrottle {
+    pub fn new(policy: ThrottlePolicy, attempts: Arc<dyn AttemptStore>) -> Self {
+        info!(?policy, "LoginThrottle initialized");
+        Self { policy, attempts }
+    }
+
+    /// SQLite store and limits from the configuration
+    pub fn from_config(cfg: &Config) -> Result<Self, StorageError> {
+        let policy = ThrottlePolicy {
+            window: cfg.login_window.max(1),
+            account_attempts: cfg.login_account_attempts.max(1),
+            ip_attempts: cfg.login_ip_attempts.max(1),
+            lockout: cfg.login_lockout,
+            delay_after: cfg.login_delay_after,
+        };
+        Ok(Self::new(
+            policy,
+            Arc::new(SqliteAttempts::open(&cfg.login_throttle_db)?),
+        ))
+    }
+
+    /// Let a login to `email` from `ip` go ahead, or refuse it with `AccountLocked` or
+    /// `RateLimited` and the seconds to wait
+    /// The attempt counts as a failure from the start, so concurrent attempts cannot all slip
+    /// in under the limits; settle it with `record_failure`, `record_success` or `release`.
+    /// One abandoned halfway (client gone) stays counted as a failure.
+    pub fn reserve(&self, email: &str, ip: Option<&str>) -> AppResult<Attempt> {
+        let now = now_secs();
+        let account = ThrottleKey::account(email).to_string();
+        let mut keys = vec![account.clone()];
+        if let Some(ip) = ip {
+            keys.push(ThrottleKey::Ip(ip.to_string()).to_string());
+        }
+
+        let reserved = self
+            .attempts
+            .reserve(&keys, now, now.saturating_sub(self.policy.window))?;
+        let attempt = Attempt {
+            account,
+            ids: reserved.iter().map(|r| r.id).collect(),
+        };
+        if let Err(e) = self.admit(&reserved[0], reserved.get(1), now) {
+            self.attempts.release(&attempt.ids)?;
+            return Err(e);
+        }
+        Ok(attempt)
+    }
+
+    /// Whether an attempt finding its account and IP as they are may go ahead
+    fn admit(&self, account: &Reserved, ip: Option<&Reserved>, now: u64) -> AppResult<()> {
+        if let Some(until) = account.locked_until
+            && until > now
+        {
+            return Err(AuthError::AccountLocked {
+                retry_after: until - now,
+            }
+            .into());
+        }
+
+        if let Some(ip) = ip {
+            let limit = self.policy.ip_attempts as usize;
+            if ip.failures.len() >= limit {
+                // Allowed again once enough failures have left the window
+                let frees_at = ip.failures[ip.failures.len() - limit] + self.policy.window;
+                return Err(AuthError::RateLimited {
+                    retry_after: frees_at.saturating_sub(now).max(1),
+                }
+                .into());
+            }
+        }
+
+        // Only reachable with attempts in flight: the lock they are heading for is not set yet
+        if account.failures.len() >= self.policy.account_attempts as usize {
+            return Err(AuthError::AccountLocked {
+                retry_after: self.policy.lockout.max(1),
+            }
+            .into());
+        }
+
+        let delay = self
+            .policy
+            .delay(account.failures.len() as u32, MAX_LOGIN_DELAY_SECS);
+        if let Some(&last) = account.failures.last()
+            && last + delay > now
+        {
+            return Err(AuthError::RateLimited {
+                retry_after: last + delay - now,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// A wrong password: the attempt stays counted, locking the account once it reached its limit
+    pub fn record_failure(&self, attempt: Attempt) -> AppResult<()> {
+        let now = now_secs();
+        let since = now.saturating_sub(self.policy.window);
+
+        let failures = self.attempts.failures(&attempt.account, since)?.len();
+        if failures >= self.policy.account_attempts as usize {
+            self.attempts
+                .lock(&attempt.account, now + self.policy.lockout)?;
+            warn!(
+                failures,
+                lockout_secs = self.policy.lockout,
+                "Account locked after failed logins"
+            );
+        }
+        Ok(())
+    }
+
+    /// A successful login forgets the account's failures (not those of the IP, but this one)
+    pub fn record_success(&self, attempt: Attempt) -> AppResult<()> {
+        self.attempts.release(&attempt.ids)?;
+        Ok(self.attempts.clear_failures(&attempt.account)?)
+    }
+
+    /// An attempt that could not be made (auth provider unreachable...) is not a failure
+    pub fn release(&self, attempt: Attempt) -> AppResult<()> {
+        Ok(self.attempts.release(&attempt.ids)?)
+    }
+}
+
+/// A login attempt let through by `LoginThrottle::reserve`, counted as a failure until settled
+#[derive(Debug)]
+#[must_use = "settle the attempt with `record_failure`, `record_success` or `release`"]
+pub struct Attempt {
+    account: String,
+    // Failures recorded in advance, on the account and the IP
+    ids: Vec<i64>,
+}
@@ -0,0 +1,137 @@
+//! User administration service - Accounts managed through the Supabase admin API
+
+use crate::config::Config;
+use crate::domain::{AuditAction, AuditEntry, AuditOutcome, ClientInfo};
+use crate::error::{AppResult, AuthError, SupabaseError};
+use crate::infrastructure::supabase::{AdminUser, UserPage};
+use crate::infrastructure::{AuthProviderKind, SupabaseAdmin};
+use crate::services::AuditService;
+use crate::shared::time::now_secs;
+use actix_web::http::StatusCode;
+use serde_json::Value;
+use tracing::{info, instrument};
+
+/// User administration - list, inspect, edit and ban accounts
+/// Deleting one also erases LAPP data, see `DELETE /admin/users/{id}`
+#[derive(Clone, Debug)]
+pub struct UserAdminService {
+    // `None` with the local provider
+    admin: Option<SupabaseAdmin>,
+    // Bans and metadata changes
+    audit: AuditService,
+}
+
+impl UserAdminService {
+    pub fn new(admin: Option<SupabaseAdmin>, audit: AuditService) -> Self {
+        info!(enabled = admin.is_some(), "UserAdminService initialized");
+        Self { admin, audit }
+    }
+
+    /// Admin client from the configuration, unless the local provider is used
+    pub fn from_config(cfg: &Config, audit: AuditService) -> Self {
+        let admin = match cfg.auth_provider {
+            AuthProviderKind::Supabase => Some(SupabaseAdmin::new(cfg)),
+            AuthProviderKind::Local => None,
+        };
+        Self::new(admin, audit)
+    }
+
+    fn admin(&self) -> AppResult<&SupabaseAdmin> {
+        self.admin.as_ref().ok_or_else(|| {
+            AuthError::Unsupported {
+                feature: "User administration",
+            }
+            .into()
+        })
+    }
+
+    /// One page of accounts, `page` starting at 1
+    pub async fn list(&self, page: u32, per_page: u32) -> AppResult<UserPage> {
+        self.admin()?
+            .list_users(page, per_page)
+            .await
+            .map_err(|e| AuthError::External(e).into())
+    }
+
+    /// An account, `None` if there is none with this ID
+    pub async fn get(&self, user_id: &str) -> AppResult<Option<AdminUser>> {
+        found(self.admin()?.get_user(user_id).await)
+    }
+
+    /// Merge keys into an account's `app_metadata` (roles...) and/or `user_metadata`
+    #[instrument(skip(self, app_metadata, user_metadata, client))]
+    pub async fn update_metadata(
+        &self,
+        user_id: &str,
+        app_metadata: Option<&Value>,
+        user_metadata: Option<&Value>,
+        updated_by: &str,
+        client: &ClientInfo,
+    ) -> AppResult<Option<AdminUser>> {
+        let user = found(
+            self.admin()?
+                .update_metadata(user_id, app_metadata, user_metadata)
+                .await,
+        )?;
+        if user.is_some() {
+            self.record(user_id, format!("metadata by {}", updated_by), client);
+            info!("User metadata updated");
+        }
+        Ok(user)
+    }
+
+    /// Refuse logins and token refreshes of an account for `hours`
+    /// LAPP sessions are not touched, see `AuthService::end_sessions`
+    #[instrument(skip(self, client))]
+    pub async fn ban(
+        &self,
+        user_id: &str,
+        hours: u64,
+        banned_by: &str,
+        client: &ClientInfo,
+    ) -> AppResult<Option<AdminUser>> {
+        let user = found(self.admin()?.ban(user_id, hours).await)?;
+        if user.is_some() {
+            self.record(user_id, format!("ban {}h by {}", hours, banned_by), client);
+            info!("User banned");
+        }
+        Ok(user)
+    }
+
+    #[instrument(skip(self, client))]
+    pub async fn unban(
+        &self,
+        user_id: &str,
+        unbanned_by: &str,
+        client: &ClientInfo,
+    ) -> AppResult<Option<AdminUser>> {
+        let user = found(self.admin()?.unban(user_id).await)?;
+        if user.is_some() {
+            self.record(user_id, format!("unban by {}", unbanned_by), client);
+            info!("User unbanned");
+        }
+        Ok(user)
+    }
+
+    fn record(&self, user_id: &str, detail: String, client: &ClientInfo) {
+        self.audit.record(
+            AuditEntry::new(
+                AuditAction::AdminUpdate,
+                AuditOutcome::Success,
+                client,
+                now_secs(),
+            )
+            .user(user_id)
+            .detail(detail),
+        );
+    }
+}
+
+/// Unknown accounts are `None`, other failures errors
+fn found(result: Result<AdminUser, SupabaseError>) -> AppResult<Option<AdminUser>> {
+    match result {
+        Ok(user) => Ok(Some(user)),
+        Err(SupabaseError::Http { status, .. }) if status == StatusCode::NOT_FOUND => Ok(None),
+        Err(e) => Err(AuthError::External(e).into()),
+    }
+}
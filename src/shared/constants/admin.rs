@@ -0,0 +1,10 @@
+//! Admin constants - Account administration limits
+
+/// Users per page when listing accounts without `per_page`
+pub const DEFAULT_USERS_PER_PAGE: u32 = 50;
+
+/// Most users one page may hold
+pub const MAX_USERS_PER_PAGE: u32 = 1000;
+
+/// Ban length without `hours`: a hundred years, as good as permanent
+pub const PERMANENT_BAN_HOURS: u64 = 876_000;
@@ -0,0 +1,7 @@
+//! App constants - Entitlements to the apps hosted on the platform
+
+/// Entitlement database location
+pub const DEFAULT_ENTITLEMENTS_DB: &str = "data/entitlements.db";
+
+/// Longest trial an admin can grant (days)
+pub const MAX_TRIAL_DAYS: u32 = 365;
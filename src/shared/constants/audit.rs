@@ -0,0 +1,10 @@
+//! Audit constants - Security audit log location and query limits
+
+/// Audit log location (JSONL)
+pub const DEFAULT_AUDIT_LOG: &str = "data/audit.jsonl";
+
+/// Entries returned by an audit query without a `limit`
+pub const DEFAULT_AUDIT_QUERY_LIMIT: usize = 100;
+
+/// Most entries one audit query may return
+pub const MAX_AUDIT_QUERY_LIMIT: usize = 1000;
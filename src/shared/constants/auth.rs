@@ -0,0 +1,75 @@
+//! Auth constants - Defaults for auth providers, bearer token verification, OAuth sign-in, MFA,
+//! passkeys, roles and login throttling
+
+/// User database of the local auth provider
+pub const DEFAULT_LOCAL_AUTH_DB: &str = "data/users.db";
+
+/// Lifetime of local provider access tokens (1 hour, as Supabase's default)
+pub const LOCAL_ACCESS_TOKEN_TTL_SECS: u64 = 60 * 60;
+
+/// Local provider refresh tokens unused for this long are refused (30 days)
+pub const LOCAL_REFRESH_TOKEN_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Audience Supabase puts in user access tokens
+pub const DEFAULT_JWT_AUDIENCE: &str = "authenticated";
+
+/// Postgres roles accepted in bearer JWTs (comma-separated)
+pub const DEFAULT_JWT_ROLES: &str = "authenticated";
+
+/// OAuth providers enabled in Supabase that `/auth/oauth/{provider}` accepts (comma-separated)
+pub const DEFAULT_OAUTH_PROVIDERS: &str = "github,google";
+
+/// Cookie binding an OAuth callback to the browser that started the flow
+pub const OAUTH_FLOW_COOKIE: &str = "oauth_flow";
+
+/// Time allowed between the redirect to the provider and the callback (10 minutes)
+pub const OAUTH_FLOW_TTL_SECS: u64 = 10 * 60;
+
+/// Time allowed to enter the TOTP code after the password (5 minutes)
+pub const MFA_PENDING_TTL_SECS: u64 = 5 * 60;
+
+/// Cookie carrying the `mfa_token` of a browser login (OAuth, email link) to `/auth/mfa/verify`
+pub const MFA_TOKEN_COOKIE: &str = "mfa_token";
+
+/// Path the MFA token cookie is sent to
+pub const MFA_VERIFY_PATH: &str = "/auth/mfa/verify";
+
+/// Wrong TOTP codes accepted for one pending login before it is dropped
+pub const MFA_MAX_ATTEMPTS: u32 = 5;
+
+/// A session that passed MFA this recently needs no other proof for sensitive actions (5 minutes)
+pub const REAUTH_MAX_AGE_SECS: u64 = 5 * 60;
+
+/// Relying party defaults: passkeys are bound to this domain (set WEBAUTHN_RP_ID in production)
+pub const DEFAULT_WEBAUTHN_RP_ID: &str = "localhost";
+pub const DEFAULT_WEBAUTHN_RP_NAME: &str = "LAPP";
+
+/// Time allowed to complete a passkey registration or login (5 minutes)
+pub const PASSKEY_CEREMONY_TTL_SECS: u64 = 5 * 60;
+
+/// Passkey database location
+pub const DEFAULT_PASSKEY_DB: &str = "data/passkeys.db";
+
+/// Database of locally granted roles
+pub const DEFAULT_ROLES_DB: &str = "data/roles.db";
+
+/// Failed logins are counted over this sliding window (15 minutes)
+pub const DEFAULT_LOGIN_WINDOW_SECS: u64 = 15 * 60;
+
+/// Failed logins on one account within the window before it is locked
+pub const DEFAULT_LOGIN_ACCOUNT_ATTEMPTS: u32 = 5;
+
+/// Failed logins from one IP within the window before it is rate limited
+pub const DEFAULT_LOGIN_IP_ATTEMPTS: u32 = 50;
+
+/// How long a locked account refuses logins (15 minutes)
+pub const DEFAULT_LOGIN_LOCKOUT_SECS: u64 = 15 * 60;
+
+/// Failed logins on one account before each new attempt has to wait
+pub const DEFAULT_LOGIN_DELAY_AFTER: u32 = 2;
+
+/// Longest wait imposed between two attempts on an account
+pub const MAX_LOGIN_DELAY_SECS: u64 = 60;
+
+/// Failed logins and lockouts database location
+pub const DEFAULT_LOGIN_THROTTLE_DB: &str = "data/login_throttle.db";
@@ -12,6 +12,20 @@ use actix_web::http::StatusCode;
 pub mod codes {
     // Auth
     pub const AUTH_INVALID_CREDENTIALS: &str = "AUTH_INVALID_CREDENTIALS";
+    pub const RATE_LIMITED: &str = "RATE_LIMITED";
+    pub const ACCOUNT_LOCKED: &str = "ACCOUNT_LOCKED";
+    pub const AUTH_UNAUTHENTICATED: &str = "AUTH_UNAUTHENTICATED";
+    pub const AUTH_INSUFFICIENT_ROLE: &str = "AUTH_INSUFFICIENT_ROLE";
+    pub const FORBIDDEN: &str = "FORBIDDEN";
+    pub const APP_ACCESS_DENIED: &str = "APP_ACCESS_DENIED";
+    pub const APP_TRIAL_EXPIRED: &str = "APP_TRIAL_EXPIRED";
+    pub const AUTH_INVALID_RESET_TOKEN: &str = "AUTH_INVALID_RESET_TOKEN";
+    pub const AUTH_INVALID_OTP: &str = "AUTH_INVALID_OTP";
+    pub const AUTH_OAUTH_STATE: &str = "AUTH_OAUTH_STATE";
+    pub const AUTH_OAUTH_FAILED: &str = "AUTH_OAUTH_FAILED";
+    pub const AUTH_MFA_REQUIRED: &str = "AUTH_MFA_REQUIRED";
+    pub const AUTH_PASSKEY_REJECTED: &str = "AUTH_PASSKEY_REJECTED";
+    pub const AUTH_PROVIDER_UNSUPPORTED: &str = "AUTH_PROVIDER_UNSUPPORTED";
 
     // Supabase
     pub const SUPABASE_HTTP_ERROR: &str = "SUPABASE_HTTP_ERROR";
@@ -21,6 +35,10 @@ pub mod codes {
 
     // Validation
     pub const VALIDATION_FAILED: &str = "VALIDATION_FAILED";
+
+    // Internal
+    pub const INTERNAL_ERROR: &str = "INTERNAL_ERROR";
+    pub const STORAGE_ERROR: &str = "STORAGE_ERROR";
 }
 
 // ============================================================================
@@ -30,6 +48,20 @@ pub mod codes {
 pub mod messages {
     // Auth
     pub const AUTH_INVALID_CREDENTIALS: &str = "Invalid email or password";
+    pub const RATE_LIMITED: &str = "Too many attempts, try again later";
+    pub const ACCOUNT_LOCKED: &str = "Account temporarily locked after too many failed logins";
+    pub const AUTH_UNAUTHENTICATED: &str = "Authentication required";
+    pub const AUTH_INSUFFICIENT_ROLE: &str = "You do not have access to this resource";
+    pub const FORBIDDEN: &str = "You do not have permission to perform this action";
+    pub const APP_ACCESS_DENIED: &str = "You do not have access to this app";
+    pub const APP_TRIAL_EXPIRED: &str = "Your trial of this app has ended";
+    pub const AUTH_INVALID_RESET_TOKEN: &str = "Reset link is invalid or has expired";
+    pub const AUTH_INVALID_OTP: &str = "Link or code is invalid or has expired";
+    pub const AUTH_OAUTH_STATE: &str = "Sign-in attempt expired, please try again";
+    pub const AUTH_OAUTH_FAILED: &str = "Sign-in with this provider failed";
+    pub const AUTH_MFA_REQUIRED: &str = "Two-factor authentication required";
+    pub const AUTH_PASSKEY_REJECTED: &str = "Passkey could not be verified";
+    pub const AUTH_PROVIDER_UNSUPPORTED: &str = "Not available with the configured auth provider";
 
     // Supabase
     pub const SUPABASE_HTTP_ERROR: &str = "Authentication service error";
@@ -39,6 +71,10 @@ pub mod messages {
 
     // Validation
     pub const VALIDATION_FAILED: &str = "Invalid input data";
+
+    // Internal
+    pub const INTERNAL_ERROR: &str = "Internal server error";
+    pub const STORAGE_ERROR: &str = "Internal storage error";
 }
 
 // ============================================================================
@@ -49,6 +85,20 @@ pub mod status {
     use super::*;
 
     pub const AUTH_INVALID_CREDENTIALS: StatusCode = StatusCode::UNAUTHORIZED;
+    pub const RATE_LIMITED: StatusCode = StatusCode::TOO_MANY_REQUESTS;
+    pub const ACCOUNT_LOCKED: StatusCode = StatusCode::TOO_MANY_REQUESTS;
+    pub const AUTH_UNAUTHENTICATED: StatusCode = StatusCode::UNAUTHORIZED;
+    pub const AUTH_INSUFFICIENT_ROLE: StatusCode = StatusCode::FORBIDDEN;
+    pub const FORBIDDEN: StatusCode = StatusCode::FORBIDDEN;
+    pub const APP_ACCESS_DENIED: StatusCode = StatusCode::FORBIDDEN;
+    pub const APP_TRIAL_EXPIRED: StatusCode = StatusCode::FORBIDDEN;
+    pub const AUTH_INVALID_RESET_TOKEN: StatusCode = StatusCode::BAD_REQUEST;
+    pub const AUTH_INVALID_OTP: StatusCode = StatusCode::BAD_REQUEST;
+    pub const AUTH_OAUTH_STATE: StatusCode = StatusCode::BAD_REQUEST;
+    pub const AUTH_OAUTH_FAILED: StatusCode = StatusCode::BAD_REQUEST;
+    pub const AUTH_MFA_REQUIRED: StatusCode = StatusCode::FORBIDDEN;
+    pub const AUTH_PASSKEY_REJECTED: StatusCode = StatusCode::UNAUTHORIZED;
+    pub const AUTH_PROVIDER_UNSUPPORTED: StatusCode = StatusCode::NOT_IMPLEMENTED;
 
     pub const SUPABASE_HTTP_ERROR: StatusCode = StatusCode::BAD_GATEWAY;
     pub const SUPABASE_NETWORK_ERROR: StatusCode = StatusCode::BAD_GATEWAY;
@@ -56,4 +106,7 @@ pub mod status {
     pub const SUPABASE_TIMEOUT: StatusCode = StatusCode::GATEWAY_TIMEOUT;
 
     pub const VALIDATION_FAILED: StatusCode = StatusCode::BAD_REQUEST;
+
+    pub const INTERNAL_ERROR: StatusCode = StatusCode::INTERNAL_SERVER_ERROR;
+    pub const STORAGE_ERROR: StatusCode = StatusCode::INTERNAL_SERVER_ERROR;
 }
@@ -1,4 +1,9 @@
 //! Application constants
 
+pub mod admin;
+pub mod apps;
+pub mod audit;
+pub mod auth;
 pub mod errors;
+pub mod session;
 pub mod urls;
@@ -0,0 +1,35 @@
+//! Session constants - Default lifetimes for server-side sessions
+
+/// Name of the cookie carrying the session ID
+pub const SESSION_COOKIE: &str = "session_id";
+
+/// Idle timeout: a session unused for this long is expired (2 hours)
+pub const DEFAULT_IDLE_TTL_SECS: u64 = 2 * 60 * 60;
+
+/// Absolute lifetime: no session outlives this, even if active (7 days)
+pub const DEFAULT_ABSOLUTE_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// How often the background reaper purges expired sessions (5 minutes)
+pub const DEFAULT_REAP_INTERVAL_SECS: u64 = 5 * 60;
+
+/// Concurrent sessions kept per user when no limit is configured
+pub const DEFAULT_MAX_SESSIONS_PER_USER: usize = 10;
+
+/// Default locations of the file and SQLite session backends
+pub const DEFAULT_SESSION_FILE: &str = "data/sessions.csv";
+pub const DEFAULT_SESSION_DB: &str = "data/sessions.db";
+
+/// Write-behind: maximum delay before session changes reach disk
+pub const DEFAULT_FLUSH_INTERVAL_SECS: u64 = 2;
+
+/// Write-behind: flush early once this many sessions have pending changes
+pub const DEFAULT_FLUSH_BATCH_SIZE: usize = 256;
+
+/// Refresh the Supabase access token when it expires within this window (5 minutes)
+pub const DEFAULT_REFRESH_MARGIN_SECS: u64 = 5 * 60;
+
+/// How often the background task refreshes tokens nearing expiry (1 minute)
+pub const DEFAULT_REFRESH_INTERVAL_SECS: u64 = 60;
+
+/// Rewrite the session snapshot after this many journaled changes
+pub const DEFAULT_COMPACT_AFTER_OPS: usize = 10_000;
@@ -4,5 +4,17 @@
 // Supabase API paths
 // ==============================
 pub const SUPABASE_AUTH_PATH: &str = "/auth/v1/token?grant_type=password";
+pub const SUPABASE_REFRESH_PATH: &str = "/auth/v1/token?grant_type=refresh_token";
+pub const SUPABASE_PKCE_PATH: &str = "/auth/v1/token?grant_type=pkce";
+pub const SUPABASE_AUTHORIZE_PATH: &str = "/auth/v1/authorize";
 pub const SUPABASE_SIGNUP_PATH: &str = "/auth/v1/signup";
-pub const SUPABASE_LOGOUT_PATH: &str = "/auth/v1/logout";
+pub const SUPABASE_USER_PATH: &str = "/auth/v1/user";
+pub const SUPABASE_RECOVER_PATH: &str = "/auth/v1/recover";
+pub const SUPABASE_VERIFY_PATH: &str = "/auth/v1/verify";
+pub const SUPABASE_RESEND_PATH: &str = "/auth/v1/resend";
+pub const SUPABASE_OTP_PATH: &str = "/auth/v1/otp";
+pub const SUPABASE_FACTORS_PATH: &str = "/auth/v1/factors";
+pub const SUPABASE_ADMIN_USERS_PATH: &str = "/auth/v1/admin/users";
+pub const SUPABASE_ADMIN_GENERATE_LINK_PATH: &str = "/auth/v1/admin/generate_link";
+// Local scope: only revoke the refresh token of this login, not the user's other devices
+pub const SUPABASE_LOGOUT_PATH: &str = "/auth/v1/logout?scope=local";
@@ -0,0 +1,19 @@
+//! JWT helpers - Inspect token structure without verifying it
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde_json::Value;
+
+/// Whether `token` has the `header.payload.signature` shape of a JWT
+/// (opaque LAPP session tokens are UUIDs and never contain dots)
+pub fn looks_like_jwt(token: &str) -> bool {
+    token.split('.').count() == 3
+}
+
+/// Decode the payload of a JWT WITHOUT checking its signature
+/// Only for routing decisions and expiry hints - never trust these claims for access control
+pub fn peek_claims(token: &str) -> Option<Value> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
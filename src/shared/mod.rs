@@ -1,3 +1,7 @@
 //! Shared utilities and constants used across the application.
 
 pub mod constants;
+pub mod fs;
+pub mod jwt;
+pub mod phone;
+pub mod time;
@@ -0,0 +1,98 @@
+//! Phone helpers - E.164 normalization for SMS login and verification
+
+/// Longest number E.164 allows, country code included
+const MAX_DIGITS: usize = 15;
+/// Shortest number accepted - shorter ones are short codes or typos
+const MIN_DIGITS: usize = 8;
+/// Countries where the leading 0 is part of the number, not a trunk prefix (Italy, San Marino...)
+const KEEP_LEADING_ZERO: &[&str] = &["39", "378", "379"];
+
+/// A phone number split the way `RegisterRequest` collects it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhoneNumber {
+    /// Country calling code, digits only (`33`)
+    pub country_code: String,
+    /// National significant number, digits only (`612345678`)
+    pub national: String,
+}
+
+impl PhoneNumber {
+    /// Build from a country code (`+33`, `33`, `0033`) and a national number in any common
+    /// notation (`06 12 34 56 78`, `(0)6-12.34.56.78`); the trunk prefix 0 is dropped
+    pub fn from_parts(country_code: &str, number: &str) -> Result<Self, &'static str> {
+        let country_code = strip_international_prefix(&compact(country_code)?)
+            .ok_or("Country code must start with + or 00")?
+            .to_string();
+        if country_code.is_empty() || country_code.len() > 3 || country_code.starts_with('0') {
+            return Err("Invalid country code");
+        }
+
+        let mut national = compact(number)?;
+        if !KEEP_LEADING_ZERO.contains(&country_code.as_str()) {
+            national = national.trim_start_matches('0').to_string();
+        }
+
+        let phone = Self {
+            country_code,
+            national,
+        };
+        phone.check_length()?;
+        Ok(phone)
+    }
+
+    /// `+<country code><national number>`
+    pub fn e164(&self) -> String {
+        format!("+{}{}", self.country_code, self.national)
+    }
+
+    fn check_length(&self) -> Result<(), &'static str> {
+        let digits = self.country_code.len() + self.national.len();
+        if !(MIN_DIGITS..=MAX_DIGITS).contains(&digits) {
+            return Err("Phone number must have 8-15 digits including the country code");
+        }
+        Ok(())
+    }
+}
+
+/// Normalize a full international number (`+33 6 12 34 56 78`, `0033612345678`) to E.164
+/// Without a numbering plan table the country code cannot be split off, only the shape is checked
+pub fn normalize_e164(input: &str) -> Result<String, &'static str> {
+    let digits = compact(input)?;
+    let digits = strip_international_prefix(&digits)
+        .ok_or("Phone number must be in international format (+33...)")?;
+    if digits.starts_with('0') {
+        return Err("Invalid country code");
+    }
+    if !(MIN_DIGITS..=MAX_DIGITS).contains(&digits.len()) {
+        return Err("Phone number must have 8-15 digits including the country code");
+    }
+    Ok(format!("+{}", digits))
+}
+
+/// Keep digits and a leading `+`, drop the usual separators, refuse anything else
+fn compact(input: &str) -> Result<String, &'static str> {
+    let mut out = String::with_capacity(input.len());
+    for (i, c) in input.trim().chars().enumerate() {
+        match c {
+            '0'..='9' => out.push(c),
+            '+' if i == 0 => out.push(c),
+            ' ' | '-' | '.' | '(' | ')' => {}
+            _ => return Err("Phone number may only contain digits, spaces and - . ( )"),
+        }
+    }
+    Ok(out)
+}
+
+/// Digits after `+` or `00`; a bare country code (`33`) is accepted as is
+fn strip_international_prefix(digits: &str) -> Option<&str> {
+    if let Some(rest) = digits.strip_prefix('+') {
+        Some(rest)
+    } else if let Some(rest) = digits.strip_prefix("00") {
+        Some(rest)
+    } else if digits.len() <= 3 {
+        // Only for country codes: a full number without prefix is ambiguous
+        Some(digits)
+    } else {
+        None
+    }
+}
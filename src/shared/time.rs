@@ -0,0 +1,11 @@
+//! Time helpers - Unix timestamps shared by sessions and tokens
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current Unix time in seconds
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,214 @@
+use super::support::{
+    PASSWORD, TAKEN_EMAIL, USER_ID, WEAK_PASSWORD, app, app_with, config, fake_supabase, user,
+};
+use crate::api;
+use crate::domain::{AuditAction, AuditOutcome, AuditQuery, ClientInfo, Session};
+use crate::infrastructure::AuthProviderKind;
+use actix_web::cookie::Cookie;
+use actix_web::http::StatusCode;
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn put(path: &str, session: &Session, body: Value) -> test::TestRequest {
+    test::TestRequest::put()
+        .uri(path)
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .set_json(body)
+}
+
+fn change_password(session: &Session, current: &str, new: &str) -> test::TestRequest {
+    put(
+        "/user/password",
+        session,
+        json!({ "current_password": current, "new_password": new }),
+    )
+}
+
+#[actix_web::test]
+async fn test_password_change_logs_out_other_devices_only() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let laptop = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let phone = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let other = state
+        .auth
+        .sessions()
+        .create_session(user("user-2", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let resp = test::call_service(
+        &svc,
+        change_password(&laptop, PASSWORD, "new-password").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["revoked_sessions"], 1);
+
+    assert!(state.auth.sessions().get_session(&laptop.id).is_some());
+    assert!(state.auth.sessions().get_session(&phone.id).is_none());
+    assert!(state.auth.sessions().get_session(&other.id).is_some());
+
+    let query = AuditQuery {
+        user_id: Some(USER_ID.to_string()),
+        limit: 10,
+        ..AuditQuery::default()
+    };
+    let actions: Vec<AuditAction> = state
+        .auth
+        .audit()
+        .query(&query)
+        .unwrap()
+        .iter()
+        .map(|e| e.action)
+        .collect();
+    assert_eq!(
+        actions,
+        [AuditAction::SessionRevoked, AuditAction::PasswordChange]
+    );
+}
+
+#[actix_web::test]
+async fn test_wrong_current_or_rejected_new_password_keeps_sessions() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let laptop = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let phone = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    for (current, new, field) in [
+        ("wrong-password", "new-password", "current_password"),
+        (PASSWORD, WEAK_PASSWORD, "new_password"),
+    ] {
+        let resp =
+            test::call_service(&svc, change_password(&laptop, current, new).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let error: Value = test::read_body_json(resp).await;
+        assert_eq!(error["field"], field);
+    }
+
+    assert!(state.auth.sessions().get_session(&phone.id).is_some());
+    let query = AuditQuery {
+        user_id: Some(USER_ID.to_string()),
+        limit: 10,
+        ..AuditQuery::default()
+    };
+    let entries = state.auth.audit().query(&query).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].outcome, AuditOutcome::Failure);
+}
+
+#[actix_web::test]
+async fn test_email_change_sends_confirmation() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let resp = test::call_service(
+        &svc,
+        put(
+            "/user/email",
+            &session,
+            json!({ "email": "New@Example.com", "password": PASSWORD }),
+        )
+        .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    // Re-authentication, then the change
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+    let resp = test::call_service(
+        &svc,
+        put(
+            "/user/email",
+            &session,
+            json!({ "email": TAKEN_EMAIL, "password": PASSWORD }),
+        )
+        .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "email");
+
+    // The address only changes once the link is followed
+    let entries = state
+        .auth
+        .audit()
+        .query(&AuditQuery {
+            limit: 10,
+            ..AuditQuery::default()
+        })
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, AuditAction::EmailChange);
+    assert_eq!(entries[0].email.as_deref(), Some("new@example.com"));
+    assert_eq!(
+        state
+            .auth
+            .sessions()
+            .get_session(&session.id)
+            .unwrap()
+            .user
+            .email,
+        format!("{}@example.com", USER_ID)
+    );
+
+    let body = json!({ "email": "new@example.com", "password": "wrong-password" });
+    let resp = test::call_service(&svc, put("/user/email", &session, body).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "password");
+}
+
+#[actix_web::test]
+async fn test_account_changes_need_supabase() {
+    let mut cfg = config("http://127.0.0.1:9");
+    cfg.auth_provider = AuthProviderKind::Local;
+    let state = app_with(cfg);
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    for req in [
+        change_password(&session, PASSWORD, "new-password"),
+        put(
+            "/user/email",
+            &session,
+            json!({ "email": "new@example.com", "password": PASSWORD }),
+        ),
+    ] {
+        let resp = test::call_service(&svc, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NOT_IMPLEMENTED);
+    }
+}
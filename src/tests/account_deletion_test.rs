@@ -0,0 +1,208 @@
+use super::support::{CODE_OK, PASSWORD, USER_ID, app, app_with, config, fake_supabase, user};
+use crate::api;
+use crate::apps::CollectionApp;
+use crate::domain::{Aal, AuditAction, AuditOutcome, AuditQuery, ClientInfo, Session};
+use crate::infrastructure::AuthProviderKind;
+use actix_web::cookie::Cookie;
+use actix_web::http::StatusCode;
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+use std::sync::atomic::Ordering;
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn delete_me(session: &Session, password: &str) -> test::TestRequest {
+    test::TestRequest::delete()
+        .uri("/user/me")
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .set_json(json!({ "password": password }))
+}
+
+#[actix_web::test]
+async fn test_account_deletion_erases_user_everywhere() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    let id = USER_ID.to_string();
+    let laptop = state
+        .auth
+        .sessions()
+        .create_session(user(&id, "authenticated"), ClientInfo::default());
+    let phone = state
+        .auth
+        .sessions()
+        .create_session(user(&id, "authenticated"), ClientInfo::default());
+    let other = state
+        .auth
+        .sessions()
+        .create_session(user("user-2", "authenticated"), ClientInfo::default());
+    state
+        .authz
+        .grant(&id, "support", &"root".to_string())
+        .unwrap();
+    state
+        .entitlements
+        .grant(CollectionApp::ID, &id, None, &"root".to_string())
+        .unwrap();
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, delete_me(&laptop, PASSWORD).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    assert_eq!(cookie.value(), "");
+
+    // Password re-check, then the admin delete
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert!(state.auth.sessions().get_session(&laptop.id).is_none());
+    assert!(state.auth.sessions().get_session(&phone.id).is_none());
+    assert!(state.auth.sessions().get_session(&other.id).is_some());
+    assert!(state.authz.granted_roles(&id).unwrap().is_empty());
+    assert!(state.entitlements.for_user(&id).unwrap().is_empty());
+
+    let query = AuditQuery {
+        user_id: Some(id),
+        limit: 1,
+        ..AuditQuery::default()
+    };
+    let entries = state.auth.audit().query(&query).unwrap();
+    assert_eq!(entries[0].action, AuditAction::AccountDeleted);
+}
+
+#[actix_web::test]
+async fn test_wrong_password_keeps_the_account() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    state
+        .authz
+        .grant(&USER_ID.to_string(), "support", &"root".to_string())
+        .unwrap();
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, delete_me(&session, "wrong-password").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "password");
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(state.auth.sessions().get_session(&session.id).is_some());
+    assert_eq!(state.authz.granted_roles(USER_ID).unwrap(), ["support"]);
+
+    let query = AuditQuery {
+        user_id: Some(USER_ID.to_string()),
+        limit: 1,
+        ..AuditQuery::default()
+    };
+    let entries = state.auth.audit().query(&query).unwrap();
+    assert_eq!(entries[0].action, AuditAction::AccountDeleted);
+    assert_eq!(entries[0].outcome, AuditOutcome::Failure);
+}
+
+#[actix_web::test]
+async fn test_accounts_without_password_close_with_an_emailed_code() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    // Signed in with OAuth: no password to re-check
+    let mut alice = user(USER_ID, "authenticated");
+    alice.email = "user@example.com".to_string();
+    let session = state
+        .auth
+        .sessions()
+        .create_session(alice, ClientInfo::default());
+    let svc = service!(state);
+    let delete = |body: Value| {
+        test::TestRequest::delete()
+            .uri("/user/me")
+            .cookie(Cookie::new("session_id", session.secret.clone()))
+            .set_json(body)
+            .to_request()
+    };
+
+    let resp = test::call_service(&svc, delete(json!({}))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "password");
+
+    let resp = test::call_service(&svc, delete(json!({ "code": "000000" }))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "code");
+    assert!(state.auth.sessions().get_session(&session.id).is_some());
+
+    let resp = test::call_service(&svc, delete(json!({ "code": CODE_OK }))).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    // Two code checks, then the admin delete
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert!(state.auth.sessions().get_session(&session.id).is_none());
+}
+
+#[actix_web::test]
+async fn test_session_that_just_passed_mfa_needs_no_other_proof() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    let session = state.auth.sessions().create_session_with_aal(
+        user(USER_ID, "authenticated"),
+        ClientInfo::default(),
+        Aal::Aal2,
+    );
+    let svc = service!(state);
+
+    let req = test::TestRequest::delete()
+        .uri("/user/me")
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .set_json(json!({}));
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+
+    // Only the admin delete
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(state.auth.sessions().get_session(&session.id).is_none());
+}
+
+#[actix_web::test]
+async fn test_deleted_local_account_cannot_log_in() {
+    let mut cfg = config("http://127.0.0.1:9");
+    cfg.auth_provider = AuthProviderKind::Local;
+    let svc = service!(app_with(cfg));
+
+    let register = test::TestRequest::post().uri("/auth/register").set_json(
+        json!({ "email": "dev@example.com", "password": PASSWORD, "username": "offline" }),
+    );
+    let resp = test::call_service(&svc, register.to_request()).await;
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    let req = test::TestRequest::delete()
+        .uri("/user/me")
+        .cookie(Cookie::new("session_id", cookie.value().to_string()))
+        .set_json(json!({ "password": PASSWORD }));
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+
+    let login = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "email": "dev@example.com", "password": PASSWORD }));
+    let resp = test::call_service(&svc, login.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,267 @@
+use super::support::{USER_ID, app, config, fake_supabase, user};
+use crate::api;
+use crate::domain::{AuditAction, AuditQuery, ClientInfo};
+use crate::infrastructure::{SupabaseAdmin, SupabaseClient};
+use actix_web::cookie::Cookie;
+use actix_web::http::StatusCode;
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+use std::sync::atomic::Ordering;
+use uuid::Uuid;
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn request(req: test::TestRequest, secret: &str) -> test::TestRequest {
+    req.cookie(Cookie::new("session_id", secret.to_string()))
+}
+
+#[actix_web::test]
+async fn test_support_reads_accounts_but_cannot_change_them() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    let support = state
+        .auth
+        .sessions()
+        .create_session(user("helper", "support"), ClientInfo::default());
+    let bob = state
+        .auth
+        .sessions()
+        .create_session(user("bob", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::get().uri("/admin/users?per_page=10"),
+        &support.secret,
+    );
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body["users"][0]["id"], USER_ID);
+    assert_eq!(body["page"], 1);
+    assert_eq!(body["per_page"], 10);
+    assert_eq!(body["total"], 1);
+
+    let req = request(
+        test::TestRequest::get().uri(&format!("/admin/users/{}", USER_ID)),
+        &support.secret,
+    );
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body["email"], "user@example.com");
+    assert_eq!(body["user_metadata"]["username"], "user");
+
+    let req = request(
+        test::TestRequest::get().uri(&format!("/admin/users/{}", Uuid::new_v4())),
+        &support.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    // Not a UUID: refused before it reaches a URL called with the service role
+    let before = calls.load(Ordering::SeqCst);
+    let req = request(
+        test::TestRequest::get().uri("/admin/users/..%2Ffactors"),
+        &support.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), before);
+
+    let req = request(
+        test::TestRequest::get().uri("/admin/users?per_page=0"),
+        &support.secret,
+    );
+    let resp = test::call_service(&svc, req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "per_page");
+
+    for (req, session) in [
+        (test::TestRequest::get().uri("/admin/users"), &bob),
+        (
+            test::TestRequest::put().uri(&format!("/admin/users/{}/ban", USER_ID)),
+            &support,
+        ),
+        (
+            test::TestRequest::delete().uri(&format!("/admin/users/{}", USER_ID)),
+            &support,
+        ),
+    ] {
+        let resp = test::call_service(&svc, request(req, &session.secret).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+}
+
+#[actix_web::test]
+async fn test_ban_ends_sessions_until_lifted() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let victim = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::put().uri(&format!("/admin/users/{}/ban", USER_ID)),
+        &admin.secret,
+    )
+    .set_json(json!({ "hours": 24 }));
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert!(body["banned_until"].is_string());
+    assert!(state.auth.sessions().get_session(&victim.id).is_none());
+
+    let req = request(
+        test::TestRequest::delete().uri(&format!("/admin/users/{}/ban", USER_ID)),
+        &admin.secret,
+    );
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert!(body["banned_until"].is_null());
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/users/nobody/ban"),
+        &admin.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let query = AuditQuery {
+        user_id: Some(USER_ID.to_string()),
+        limit: 10,
+        ..AuditQuery::default()
+    };
+    let details: Vec<(AuditAction, Option<String>)> = state
+        .auth
+        .audit()
+        .query(&query)
+        .unwrap()
+        .into_iter()
+        .map(|e| (e.action, e.detail))
+        .collect();
+    assert_eq!(
+        details,
+        [
+            (AuditAction::AdminUpdate, Some("unban by root".to_string())),
+            (AuditAction::SessionRevoked, Some("banned".to_string())),
+            (
+                AuditAction::AdminUpdate,
+                Some("ban 24h by root".to_string())
+            ),
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_metadata_update_merges_objects_only() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let svc = service!(state);
+
+    let patch = |body: Value| {
+        request(
+            test::TestRequest::patch().uri(&format!("/admin/users/{}/metadata", USER_ID)),
+            &admin.secret,
+        )
+        .set_json(body)
+    };
+
+    let req = patch(json!({ "app_metadata": { "roles": ["support"] } }));
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body["app_metadata"]["roles"], json!(["support"]));
+    assert_eq!(body["user_metadata"]["username"], "user");
+
+    for (body, field) in [
+        (json!({}), "app_metadata"),
+        (json!({ "user_metadata": "plain" }), "user_metadata"),
+    ] {
+        let resp = test::call_service(&svc, patch(body).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let error: Value = test::read_body_json(resp).await;
+        assert_eq!(error["field"], field);
+    }
+}
+
+#[actix_web::test]
+async fn test_admin_deletion_closes_the_account() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    state
+        .authz
+        .grant(&USER_ID.to_string(), "support", &"root".to_string())
+        .unwrap();
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::delete().uri(&format!("/admin/users/{}", USER_ID)),
+        &admin.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    assert!(state.auth.sessions().get_session(&session.id).is_none());
+    assert!(state.authz.granted_roles(USER_ID).unwrap().is_empty());
+
+    let query = AuditQuery {
+        user_id: Some(USER_ID.to_string()),
+        limit: 1,
+        ..AuditQuery::default()
+    };
+    let entries = state.auth.audit().query(&query).unwrap();
+    assert_eq!(entries[0].action, AuditAction::AccountDeleted);
+    assert_eq!(entries[0].detail.as_deref(), Some("by root"));
+
+    // A closure whose erase failed: the account is gone, its leftovers go now
+    let leftover = Uuid::new_v4().to_string();
+    state
+        .authz
+        .grant(&leftover, "support", &"root".to_string())
+        .unwrap();
+    let req = request(
+        test::TestRequest::delete().uri(&format!("/admin/users/{}", leftover)),
+        &admin.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    assert!(state.authz.granted_roles(&leftover).unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn test_service_role_key_stays_out_of_logs() {
+    let cfg = config("http://127.0.0.1:1");
+    for debug in [
+        format!("{:?}", SupabaseAdmin::new(&cfg)),
+        format!("{:?}", SupabaseClient::new(&cfg)),
+    ] {
+        assert!(!debug.contains(&cfg.sp_service_role), "{}", debug);
+    }
+}
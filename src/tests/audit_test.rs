@@ -0,0 +1,244 @@
+use super::support::{PASSWORD, REFRESH_OK, USER_ID, app, fake_supabase, user};
+use crate::api;
+use crate::domain::{
+    AuditAction, AuditEntry, AuditLog, AuditOutcome, AuditQuery, ClientInfo, User,
+};
+use crate::infrastructure::JsonlAuditLog;
+use crate::shared::time::now_secs;
+use actix_web::cookie::Cookie;
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+use std::io::Write;
+use std::net::SocketAddr;
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn login(password: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/login")
+        .peer_addr(SocketAddr::new("10.0.0.7".parse().unwrap(), 40000))
+        .insert_header((header::USER_AGENT, "audit-test"))
+        .set_json(json!({ "email": "user@example.com", "password": password }))
+}
+
+fn audit(query: &str, secret: &str) -> test::TestRequest {
+    test::TestRequest::get()
+        .uri(&format!("/admin/audit{}", query))
+        .cookie(Cookie::new("session_id", secret.to_string()))
+}
+
+fn entry(action: AuditAction, user_id: &str, at: u64) -> AuditEntry {
+    AuditEntry::new(action, AuditOutcome::Success, &ClientInfo::default(), at).user(user_id)
+}
+
+#[actix_web::test]
+async fn test_logins_and_logouts_are_audited() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, login("wrong-password").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let resp = test::call_service(&svc, login(PASSWORD).to_request()).await;
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    let req = test::TestRequest::post()
+        .uri("/auth/logout")
+        .cookie(Cookie::new("session_id", cookie.value().to_string()))
+        .to_request();
+    test::call_service(&svc, req).await;
+
+    let body: Value = test::call_and_read_body_json(
+        &svc,
+        audit("?email=User@example.com", &admin.secret).to_request(),
+    )
+    .await;
+    let events: Vec<(&str, &str)> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["action"].as_str().unwrap(),
+                e["outcome"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    // Newest first; the logout only knows the user ID
+    assert_eq!(events, [("login", "success"), ("login", "failure")]);
+    assert_eq!(body[0]["detail"], "password");
+    assert_eq!(body[0]["ip"], "10.0.0.7");
+    assert_eq!(body[0]["user_agent"], "audit-test");
+    assert_eq!(body[1]["detail"], "auth_invalid_credentials");
+    assert_eq!(body[1]["user_id"], Value::Null);
+
+    let user_id = body[0]["user_id"].as_str().unwrap().to_string();
+    let body: Value = test::call_and_read_body_json(
+        &svc,
+        audit(&format!("?user_id={}", user_id), &admin.secret).to_request(),
+    )
+    .await;
+    assert_eq!(body[0]["action"], "logout");
+    assert_eq!(body[1]["action"], "login");
+}
+
+#[actix_web::test]
+async fn test_audit_log_is_admin_only() {
+    let state = app("http://127.0.0.1:1");
+    let support = state
+        .auth
+        .sessions()
+        .create_session(user("sam", "support"), ClientInfo::default());
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, audit("", &support.secret).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = test::call_service(&svc, audit("?limit=0", &admin.secret).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "limit");
+}
+
+#[actix_web::test]
+async fn test_session_revocation_and_token_refresh_are_audited() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let expiring = User {
+        refresh_token: REFRESH_OK.to_string(),
+        expires_at: now_secs() + 60,
+        ..user(USER_ID, "authenticated")
+    };
+    let session = state
+        .auth
+        .sessions()
+        .create_session(expiring, ClientInfo::default());
+    let phone = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    // Refreshed on the way in
+    let req = test::TestRequest::delete()
+        .uri(&format!("/user/sessions/{}", phone.device_id))
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .to_request();
+    assert_eq!(
+        test::call_service(&svc, req).await.status(),
+        StatusCode::NO_CONTENT
+    );
+
+    let query = AuditQuery {
+        user_id: Some(USER_ID.to_string()),
+        limit: 10,
+        ..AuditQuery::default()
+    };
+    let entries = state.auth.audit().query(&query).unwrap();
+    let actions: Vec<AuditAction> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        [AuditAction::SessionRevoked, AuditAction::TokenRefresh]
+    );
+    assert_eq!(entries[0].detail.as_deref(), Some(phone.device_id.as_str()));
+}
+
+#[actix_web::test]
+async fn test_jsonl_log_filters_by_time_and_survives_reopen() {
+    let path = std::env::temp_dir().join(format!("lapp-audit-{}.jsonl", uuid::Uuid::new_v4()));
+    let path_str = path.to_str().unwrap();
+
+    let log = JsonlAuditLog::open(path_str).unwrap();
+    for (i, at) in [100, 200, 300, 400].into_iter().enumerate() {
+        let user_id = if i % 2 == 0 { "alice" } else { "bob" };
+        log.append(&entry(AuditAction::Login, user_id, at)).unwrap();
+    }
+    drop(log);
+
+    // A torn line from a crash is skipped, and appends go after it
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(b"{\"at\":50").unwrap();
+    file.write_all(b"\n").unwrap();
+    let log = JsonlAuditLog::open(path_str).unwrap();
+    log.append(&entry(AuditAction::Logout, "alice", 500))
+        .unwrap();
+
+    let query = AuditQuery {
+        user_id: Some("alice".to_string()),
+        since: Some(100),
+        until: Some(450),
+        limit: 10,
+        ..AuditQuery::default()
+    };
+    let times: Vec<u64> = log.query(&query).unwrap().iter().map(|e| e.at).collect();
+    assert_eq!(times, [300, 100]);
+
+    let latest = log
+        .query(&AuditQuery {
+            limit: 2,
+            ..AuditQuery::default()
+        })
+        .unwrap();
+    assert_eq!(latest[0], entry(AuditAction::Logout, "alice", 500));
+    assert_eq!(latest[1].at, 400);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[actix_web::test]
+async fn test_jsonl_appends_from_many_threads_stay_whole_lines() {
+    let path = std::env::temp_dir().join(format!("lapp-audit-{}.jsonl", uuid::Uuid::new_v4()));
+    let log = JsonlAuditLog::open(path.to_str().unwrap()).unwrap();
+
+    std::thread::scope(|s| {
+        for t in 0..8u64 {
+            let log = &log;
+            s.spawn(move || {
+                for i in 0..25 {
+                    log.append(&entry(AuditAction::Login, "alice", t * 100 + i))
+                        .unwrap();
+                    // Reads run alongside the writer, never behind it
+                    log.query(&AuditQuery {
+                        limit: 1,
+                        ..AuditQuery::default()
+                    })
+                    .unwrap();
+                }
+            });
+        }
+    });
+
+    let all = log
+        .query(&AuditQuery {
+            limit: 1000,
+            ..AuditQuery::default()
+        })
+        .unwrap();
+    assert_eq!(all.len(), 200);
+
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,172 @@
+use super::support::{app, user};
+use crate::api;
+use crate::domain::{ClientInfo, Permission};
+use crate::error::{AppError, AuthError};
+use actix_web::cookie::Cookie;
+use actix_web::http::StatusCode;
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn request(req: test::TestRequest, secret: &str) -> test::TestRequest {
+    req.cookie(Cookie::new("session_id", secret.to_string()))
+}
+
+#[actix_web::test]
+async fn test_admin_routes_are_forbidden_without_permission() {
+    let state = app("http://127.0.0.1:1");
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user("alice", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::get().uri("/admin/users/bob/roles"),
+        &session.secret,
+    );
+    let resp = test::call_service(&svc, req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "FORBIDDEN");
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/users/alice/roles/admin"),
+        &session.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    // Anonymous requests are still 401
+    let req = test::TestRequest::get()
+        .uri("/admin/users/bob/roles")
+        .to_request();
+    assert_eq!(
+        test::call_service(&svc, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_web::test]
+async fn test_admin_grants_and_revokes_roles() {
+    let state = app("http://127.0.0.1:1");
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let bob = state
+        .auth
+        .sessions()
+        .create_session(user("bob", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/users/bob/roles/support"),
+        &admin.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+
+    // Support may read roles but not manage them
+    let req = request(
+        test::TestRequest::get().uri("/admin/users/bob/roles"),
+        &bob.secret,
+    );
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body, json!({ "user_id": "bob", "roles": ["support"] }));
+    let req = request(
+        test::TestRequest::delete().uri("/admin/users/bob/roles/support"),
+        &bob.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    let req = request(
+        test::TestRequest::get().uri("/user/permissions"),
+        &bob.secret,
+    );
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(
+        body,
+        json!({ "roles": ["authenticated", "support"], "permissions": ["users:read"] })
+    );
+
+    let req = request(
+        test::TestRequest::delete().uri("/admin/users/bob/roles/support"),
+        &admin.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    let req = request(
+        test::TestRequest::delete().uri("/admin/users/bob/roles/support"),
+        &admin.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let req = request(
+        test::TestRequest::get().uri("/admin/users/bob/roles"),
+        &bob.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[actix_web::test]
+async fn test_unknown_roles_cannot_be_granted() {
+    let state = app("http://127.0.0.1:1");
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/users/bob/roles/superuser"),
+        &admin.secret,
+    );
+    let resp = test::call_service(&svc, req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "role");
+}
+
+#[actix_web::test]
+async fn test_app_metadata_roles_grant_permissions() {
+    let state = app("http://127.0.0.1:1");
+    let mut carol = user("carol", "authenticated");
+    carol.roles = vec!["support".to_string()];
+
+    assert!(state.authz.require(&carol, Permission::UsersRead).is_ok());
+    let denied = state.authz.require(&carol, Permission::RolesManage);
+    assert!(matches!(
+        denied,
+        Err(AppError::Auth(AuthError::Forbidden {
+            permission: "roles:manage"
+        }))
+    ));
+    assert!(state.authz.has_role(&carol, "support").unwrap());
+    assert!(state.authz.granted_roles("carol").unwrap().is_empty());
+}
@@ -0,0 +1,140 @@
+use super::support::{PASSWORD, app, fake_supabase, jwt, user};
+use crate::api;
+use crate::domain::ClientInfo;
+use crate::shared::time::now_secs;
+use actix_web::cookie::Cookie;
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+use std::sync::atomic::Ordering;
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn me(authorization: &str) -> test::TestRequest {
+    test::TestRequest::get()
+        .uri("/user/me")
+        .insert_header((header::AUTHORIZATION, authorization.to_string()))
+}
+
+#[actix_web::test]
+async fn test_token_mode_login_returns_bearer_token() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let login = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "email": "user@example.com", "password": PASSWORD, "mode": "token" }))
+        .to_request();
+    let resp = test::call_service(&svc, login).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::SET_COOKIE).is_none());
+
+    let body: Value = test::read_body_json(resp).await;
+    let token = body["token"].as_str().unwrap();
+
+    let resp = test::call_service(&svc, me(&format!("Bearer {}", token)).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["email"], "user@example.com");
+}
+
+#[actix_web::test]
+async fn test_cookie_mode_is_the_default() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let login = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "email": "user@example.com", "password": PASSWORD }))
+        .to_request();
+    let resp = test::call_service(&svc, login).await;
+    assert!(resp.headers().get(header::SET_COOKIE).is_some());
+
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body.get("token").is_none());
+}
+
+#[actix_web::test]
+async fn test_supabase_jwt_is_accepted_as_bearer() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(
+        &svc,
+        me(&format!("Bearer {}", jwt(now_secs() + 600, true))).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["username"], "user");
+
+    let forged = test::call_service(
+        &svc,
+        me(&format!("Bearer {}", jwt(now_secs() + 600, false))).to_request(),
+    )
+    .await;
+    assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_expired_jwt_is_refused_without_asking_supabase() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(
+        &svc,
+        me(&format!("Bearer {}", jwt(now_secs() - 1, true))).to_request(),
+    )
+    .await;
+
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
+
+#[actix_web::test]
+async fn test_malformed_authorization_does_not_fall_back_to_cookie() {
+    let state = app("http://127.0.0.1:1");
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user("alice", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = me("Basic dXNlcjpwYXNz")
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .to_request();
+    let resp = test::call_service(&svc, req).await;
+
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_logout_with_bearer_token() {
+    let state = app("http://127.0.0.1:1");
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user("alice", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let logout = test::TestRequest::post()
+        .uri("/auth/logout")
+        .insert_header((header::AUTHORIZATION, format!("Bearer {}", session.secret)))
+        .to_request();
+    test::call_service(&svc, logout).await;
+
+    assert!(state.auth.sessions().get_session(&session.id).is_none());
+}
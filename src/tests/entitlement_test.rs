@@ -0,0 +1,248 @@
+use super::support::{USER_ID, app, fake_supabase, jwt, user};
+use crate::api;
+use crate::domain::ClientInfo;
+use crate::shared::time::now_secs;
+use actix_web::cookie::Cookie;
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+use std::sync::atomic::Ordering;
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn request(req: test::TestRequest, secret: &str) -> test::TestRequest {
+    req.cookie(Cookie::new("session_id", secret.to_string()))
+}
+
+#[actix_web::test]
+async fn test_app_routes_need_an_entitlement() {
+    let state = app("http://127.0.0.1:1");
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let bob = state
+        .auth
+        .sessions()
+        .create_session(user("bob", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = test::TestRequest::get()
+        .uri("/apps/collection")
+        .to_request();
+    assert_eq!(
+        test::call_service(&svc, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+    // The catalog itself is public
+    let req = test::TestRequest::get().uri("/apps").to_request();
+    let body: Value = test::call_and_read_body_json(&svc, req).await;
+    assert_eq!(body[0]["id"], "collection");
+    assert!(body[0]["access"].is_null());
+
+    // Admins are no exception: access to apps is granted per user
+    for session in [&admin, &bob] {
+        let req = request(
+            test::TestRequest::get().uri("/apps/collection"),
+            &session.secret,
+        );
+        let resp = test::call_service(&svc, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let error: Value = test::read_body_json(resp).await;
+        assert_eq!(error["code"], "APP_ACCESS_DENIED");
+    }
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/apps/collection/entitlements/bob"),
+        &admin.secret,
+    );
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body["status"], "granted");
+    assert_eq!(body["updated_by"], "root");
+
+    let req = request(
+        test::TestRequest::get().uri("/apps/collection"),
+        &bob.secret,
+    );
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body["id"], "collection");
+    assert_eq!(body["access"]["active"], true);
+
+    let req = request(test::TestRequest::get().uri("/apps"), &bob.secret);
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body[0]["id"], "collection");
+    assert_eq!(body[0]["access"]["status"], "granted");
+
+    let req = request(
+        test::TestRequest::delete().uri("/admin/apps/collection/entitlements/bob"),
+        &admin.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    let req = request(
+        test::TestRequest::delete().uri("/admin/apps/collection/entitlements/bob"),
+        &admin.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let req = request(
+        test::TestRequest::get().uri("/apps/collection"),
+        &bob.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[actix_web::test]
+async fn test_trials_expire() {
+    let state = app("http://127.0.0.1:1");
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let bob = state
+        .auth
+        .sessions()
+        .create_session(user("bob", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/apps/collection/entitlements/bob"),
+        &admin.secret,
+    )
+    .set_json(json!({ "trial_days": 14 }));
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body["status"], "trial");
+    let expires_at = body["expires_at"].as_u64().unwrap();
+    assert!(expires_at > now_secs() + 13 * 24 * 3600);
+
+    let req = request(
+        test::TestRequest::get().uri("/apps/collection"),
+        &bob.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::OK
+    );
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/apps/collection/entitlements/bob"),
+        &admin.secret,
+    )
+    .set_json(json!({ "trial_days": 0 }));
+    let resp = test::call_service(&svc, req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "trial_days");
+}
+
+#[actix_web::test]
+async fn test_expired_trial_is_refused_with_its_own_code() {
+    let state = app("http://127.0.0.1:1");
+    let bob = state
+        .auth
+        .sessions()
+        .create_session(user("bob", "authenticated"), ClientInfo::default());
+    state
+        .entitlements
+        .grant(
+            "collection",
+            &"bob".to_string(),
+            Some(0),
+            &"root".to_string(),
+        )
+        .unwrap();
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::get().uri("/apps/collection"),
+        &bob.secret,
+    );
+    let resp = test::call_service(&svc, req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "APP_TRIAL_EXPIRED");
+}
+
+#[actix_web::test]
+async fn test_entitlements_are_managed_by_admins_only() {
+    let state = app("http://127.0.0.1:1");
+    let support = state
+        .auth
+        .sessions()
+        .create_session(user("sam", "support"), ClientInfo::default());
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let svc = service!(state);
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/apps/collection/entitlements/sam"),
+        &support.secret,
+    );
+    assert_eq!(
+        test::call_service(&svc, req.to_request()).await.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    // Support may see who has access
+    let req = request(
+        test::TestRequest::get().uri("/admin/apps/collection/entitlements"),
+        &support.secret,
+    );
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body, json!([]));
+
+    let req = request(
+        test::TestRequest::put().uri("/admin/apps/unknown/entitlements/sam"),
+        &admin.secret,
+    );
+    let resp = test::call_service(&svc, req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "app_id");
+}
+
+#[actix_web::test]
+async fn test_app_routes_authenticate_once() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    state
+        .entitlements
+        .grant(
+            "collection",
+            &USER_ID.to_string(),
+            None,
+            &"root".to_string(),
+        )
+        .unwrap();
+    let svc = service!(state);
+
+    // Without a local JWT secret, every authentication asks Supabase
+    let req = test::TestRequest::get()
+        .uri("/apps/collection")
+        .insert_header((
+            header::AUTHORIZATION,
+            format!("Bearer {}", jwt(now_secs() + 600, true)),
+        ));
+    let body: Value = test::call_and_read_body_json(&svc, req.to_request()).await;
+    assert_eq!(body["access"]["status"], "granted");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
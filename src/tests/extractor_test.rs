@@ -0,0 +1,139 @@
+use super::support::{app, user};
+use crate::api::extractors::{Admin, AuthenticatedUser, OptionalUser, RequireRole};
+use crate::domain::ClientInfo;
+use actix_web::cookie::Cookie;
+use actix_web::http::StatusCode;
+use actix_web::{App as ActixApp, HttpResponse, test, web};
+use serde_json::Value;
+
+async fn whoami(auth: AuthenticatedUser) -> HttpResponse {
+    HttpResponse::Ok().body(auth.user().id.clone())
+}
+
+async fn maybe(user: OptionalUser) -> HttpResponse {
+    let name = user
+        .0
+        .map(|u| u.user().id.clone())
+        .unwrap_or_else(|| "anonymous".to_string());
+    HttpResponse::Ok().body(name)
+}
+
+async fn admin_only(admin: RequireRole<Admin>) -> HttpResponse {
+    HttpResponse::Ok().body(admin.user().id.clone())
+}
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .route("/whoami", web::get().to(whoami))
+                .route("/maybe", web::get().to(maybe))
+                .route("/admin", web::get().to(admin_only)),
+        )
+        .await
+    };
+}
+
+fn get(path: &str, secret: Option<&str>) -> test::TestRequest {
+    let req = test::TestRequest::get().uri(path);
+    match secret {
+        Some(id) => req.cookie(Cookie::new("session_id", id.to_string())),
+        None => req,
+    }
+}
+
+#[actix_web::test]
+async fn test_authenticated_user_resolves_session() {
+    let state = app("http://127.0.0.1:1");
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user("alice", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let body =
+        test::call_and_read_body(&svc, get("/whoami", Some(&session.secret)).to_request()).await;
+    assert_eq!(body, "alice");
+}
+
+#[actix_web::test]
+async fn test_missing_or_unknown_session_is_unauthorized_json() {
+    let state = app("http://127.0.0.1:1");
+    let svc = service!(state);
+
+    for session_id in [None, Some("not-a-session")] {
+        let resp = test::call_service(&svc, get("/whoami", session_id).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "AUTH_UNAUTHENTICATED");
+    }
+}
+
+#[actix_web::test]
+async fn test_revoked_session_is_rejected() {
+    let state = app("http://127.0.0.1:1");
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user("alice", "authenticated"), ClientInfo::default());
+    state.auth.sessions().delete_session(&session.id);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, get("/whoami", Some(&session.secret)).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_optional_user_accepts_anonymous() {
+    let state = app("http://127.0.0.1:1");
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user("alice", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let anonymous = test::call_and_read_body(&svc, get("/maybe", None).to_request()).await;
+    let known =
+        test::call_and_read_body(&svc, get("/maybe", Some(&session.secret)).to_request()).await;
+
+    assert_eq!(anonymous, "anonymous");
+    assert_eq!(known, "alice");
+}
+
+#[actix_web::test]
+async fn test_require_role_checks_user_role() {
+    let state = app("http://127.0.0.1:1");
+    let member = state
+        .auth
+        .sessions()
+        .create_session(user("alice", "authenticated"), ClientInfo::default());
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let svc = service!(state);
+
+    let denied = test::call_service(&svc, get("/admin", Some(&member.secret)).to_request()).await;
+    assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+    let body: Value = test::read_body_json(denied).await;
+    assert_eq!(body["code"], "AUTH_INSUFFICIENT_ROLE");
+
+    let allowed =
+        test::call_and_read_body(&svc, get("/admin", Some(&admin.secret)).to_request()).await;
+    assert_eq!(allowed, "root");
+
+    let anonymous = test::call_service(&svc, get("/admin", None).to_request()).await;
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_missing_app_data_is_an_internal_error() {
+    let svc = test::init_service(ActixApp::new().route("/whoami", web::get().to(whoami))).await;
+
+    let resp = test::call_service(&svc, get("/whoami", Some("any")).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "INTERNAL_ERROR");
+}
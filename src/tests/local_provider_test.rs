@@ -0,0 +1,216 @@
+use super::support::{PASSWORD, app_with, config, user};
+use crate::api;
+use crate::domain::{AuthProvider, ClientInfo, session_key};
+use crate::error::{AppError, AuthError};
+use crate::infrastructure::{AuthProviderKind, LocalProvider};
+use actix_web::cookie::Cookie;
+use actix_web::http::StatusCode;
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+/// Local provider, Supabase URL pointing nowhere: any call to it would fail
+fn local_app() -> crate::app::App {
+    let mut cfg = config("http://127.0.0.1:9");
+    cfg.auth_provider = AuthProviderKind::Local;
+    app_with(cfg)
+}
+
+fn register(email: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/register")
+        .set_json(json!({ "email": email, "password": PASSWORD, "username": "offline" }))
+}
+
+fn login(email: &str, password: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "email": email, "password": password }))
+}
+
+#[actix_web::test]
+async fn test_register_login_logout_without_supabase() {
+    let svc = service!(local_app());
+
+    let resp = test::call_service(&svc, register("dev@example.com").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    // Emails are matched case-insensitively
+    let resp = test::call_service(&svc, login("Dev@Example.com", PASSWORD).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    let cookie = Cookie::new("session_id", cookie.value().to_string());
+
+    let req = test::TestRequest::get()
+        .uri("/user/me")
+        .cookie(cookie.clone())
+        .to_request();
+    let me: Value = test::call_and_read_body_json(&svc, req).await;
+    assert_eq!(me["email"], "dev@example.com");
+    assert_eq!(me["username"], "offline");
+
+    let req = test::TestRequest::post()
+        .uri("/auth/logout")
+        .cookie(cookie.clone())
+        .to_request();
+    assert!(test::call_service(&svc, req).await.status().is_success());
+    let req = test::TestRequest::get()
+        .uri("/user/me")
+        .cookie(cookie)
+        .to_request();
+    assert_eq!(
+        test::call_service(&svc, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_web::test]
+async fn test_wrong_password_unknown_email_and_duplicate_signup_are_refused() {
+    let svc = service!(local_app());
+    test::call_service(&svc, register("dev@example.com").to_request()).await;
+
+    for (email, password) in [
+        ("dev@example.com", "wrong-password"),
+        ("nobody@example.com", PASSWORD),
+    ] {
+        let resp = test::call_service(&svc, login(email, password).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let error: Value = test::read_body_json(resp).await;
+        assert_eq!(error["code"], "AUTH_INVALID_CREDENTIALS");
+    }
+
+    let resp = test::call_service(&svc, register("DEV@example.com").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "email");
+}
+
+#[actix_web::test]
+async fn test_supabase_only_flows_answer_not_implemented() {
+    let state = local_app();
+    let svc = service!(state);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/otp")
+        .set_json(json!({ "email": "dev@example.com" }))
+        .to_request();
+    let resp = test::call_service(&svc, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_IMPLEMENTED);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "AUTH_PROVIDER_UNSUPPORTED");
+
+    // No MFA with the local provider: nothing enrolled rather than an error
+    let resp = test::call_service(&svc, register("dev@example.com").to_request()).await;
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    let req = test::TestRequest::get()
+        .uri("/user/mfa")
+        .cookie(Cookie::new("session_id", cookie.value().to_string()))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&svc, req).await;
+    assert_eq!(body["factors"], json!([]));
+}
+
+#[actix_web::test]
+async fn test_refresh_tokens_rotate_and_die_with_logout() {
+    let provider = LocalProvider::open(":memory:").unwrap();
+    let signup = provider
+        .register("dev@example.com", PASSWORD, "offline", None)
+        .await
+        .unwrap();
+    let crate::domain::SignupOutcome::Session(user) = signup else {
+        panic!("local signups need no confirmation");
+    };
+
+    let refreshed = provider.refresh(&user.refresh_token).await.unwrap();
+    assert_eq!(refreshed.id, user.id);
+    assert_ne!(refreshed.access_token, user.access_token);
+
+    // Spent
+    let reused = provider.refresh(&user.refresh_token).await;
+    assert!(matches!(
+        reused,
+        Err(AppError::Auth(AuthError::Unauthenticated))
+    ));
+
+    provider.logout(&refreshed.access_token).await;
+    let after_logout = provider.refresh(&refreshed.refresh_token).await;
+    assert!(matches!(
+        after_logout,
+        Err(AppError::Auth(AuthError::Unauthenticated))
+    ));
+
+    // Passkey logins go through `login_as`
+    assert_eq!(
+        provider.login_as(&user.id).await.unwrap().user.email,
+        "dev@example.com"
+    );
+    let unknown = provider.login_as("no-such-user").await;
+    assert!(matches!(
+        unknown,
+        Err(AppError::Auth(AuthError::Unauthenticated))
+    ));
+}
+
+#[actix_web::test]
+async fn test_admin_closes_a_local_account() {
+    let state = local_app();
+    let admin = state
+        .auth
+        .sessions()
+        .create_session(user("root", "admin"), ClientInfo::default());
+    let svc = service!(state);
+    test::call_service(&svc, register("dev@example.com").to_request()).await;
+    let resp = test::call_service(&svc, login("dev@example.com", PASSWORD).to_request()).await;
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    let session = state
+        .auth
+        .sessions()
+        .get_session(&session_key(cookie.value()))
+        .unwrap();
+    let user_id = session.user.id;
+    state
+        .authz
+        .grant(&user_id, "support", &"root".to_string())
+        .unwrap();
+
+    let delete = || {
+        test::TestRequest::delete()
+            .uri(&format!("/admin/users/{}", user_id))
+            .cookie(Cookie::new("session_id", admin.secret.clone()))
+            .to_request()
+    };
+    assert_eq!(
+        test::call_service(&svc, delete()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    assert!(state.authz.granted_roles(&user_id).unwrap().is_empty());
+    let resp = test::call_service(&svc, login("dev@example.com", PASSWORD).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    assert_eq!(
+        test::call_service(&svc, delete()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,234 @@
+use super::support::{PASSWORD, app_with, config, fake_supabase};
+use crate::api;
+use crate::domain::{ClientInfo, ThrottlePolicy};
+use crate::error::{AppError, AuthError};
+use crate::infrastructure::SqliteAttempts;
+use crate::services::LoginThrottle;
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn login(email: &str, password: &str, ip: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/login")
+        .peer_addr(SocketAddr::new(ip.parse().unwrap(), 40000))
+        .set_json(json!({ "email": email, "password": password }))
+}
+
+fn retry_after(resp: &actix_web::dev::ServiceResponse) -> u64 {
+    resp.headers()
+        .get(header::RETRY_AFTER)
+        .expect("Retry-After header")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn test_account_locks_after_repeated_failures() {
+    let (url, calls) = fake_supabase();
+    let mut cfg = config(&url);
+    cfg.login_account_attempts = 3;
+    cfg.login_delay_after = 0;
+    let svc = service!(app_with(cfg));
+
+    for ip in ["10.0.0.1", "10.0.0.2", "10.0.0.3"] {
+        let resp = test::call_service(
+            &svc,
+            login("user@example.com", "wrong-password", ip).to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let tried = calls.load(Ordering::SeqCst);
+
+    // Locked even with the right password, from any IP, without asking Supabase
+    let resp = test::call_service(
+        &svc,
+        login("USER@example.com", PASSWORD, "10.0.0.4").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!((899..=900).contains(&retry_after(&resp)));
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "ACCOUNT_LOCKED");
+    assert_eq!(calls.load(Ordering::SeqCst), tried);
+
+    let resp = test::call_service(
+        &svc,
+        login("other@example.com", PASSWORD, "10.0.0.1").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_concurrent_attempts_cannot_exceed_the_limit() {
+    let (url, calls) = fake_supabase();
+    let mut cfg = config(&url);
+    cfg.login_account_attempts = 3;
+    cfg.login_delay_after = 0;
+    let state = app_with(cfg);
+
+    // The fake Supabase answers after 50ms: every attempt is in flight before any fails
+    let attempt = || {
+        state
+            .auth
+            .login("user@example.com", "wrong-password", ClientInfo::default())
+    };
+    let results = tokio::join!(
+        attempt(),
+        attempt(),
+        attempt(),
+        attempt(),
+        attempt(),
+        attempt()
+    );
+    let results = [
+        results.0, results.1, results.2, results.3, results.4, results.5,
+    ];
+
+    let wrong = results
+        .iter()
+        .filter(|r| matches!(r, Err(AppError::Auth(AuthError::InvalidCredentials))))
+        .count();
+    let locked = results
+        .iter()
+        .filter(|r| matches!(r, Err(AppError::Auth(AuthError::AccountLocked { .. }))))
+        .count();
+    assert_eq!((wrong, locked), (3, 3));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[actix_web::test]
+async fn test_failures_slow_down_further_attempts() {
+    let (url, _) = fake_supabase();
+    let svc = service!(app_with(config(&url)));
+
+    for _ in 0..2 {
+        let resp = test::call_service(
+            &svc,
+            login("user@example.com", "wrong-password", "10.0.0.1").to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    let resp = test::call_service(
+        &svc,
+        login("user@example.com", PASSWORD, "10.0.0.1").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!((1..=2).contains(&retry_after(&resp)));
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "RATE_LIMITED");
+}
+
+#[actix_web::test]
+async fn test_ip_is_rate_limited_across_accounts() {
+    let (url, _) = fake_supabase();
+    let mut cfg = config(&url);
+    cfg.login_ip_attempts = 3;
+    let svc = service!(app_with(cfg));
+
+    for email in ["a@example.com", "b@example.com", "c@example.com"] {
+        let resp = test::call_service(
+            &svc,
+            login(email, "wrong-password", "10.0.0.1").to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    let resp = test::call_service(
+        &svc,
+        login("d@example.com", PASSWORD, "10.0.0.1").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(retry_after(&resp) > 0);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "RATE_LIMITED");
+
+    let resp = test::call_service(
+        &svc,
+        login("d@example.com", PASSWORD, "10.0.0.2").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_successful_login_resets_account_failures() {
+    let (url, _) = fake_supabase();
+    let mut cfg = config(&url);
+    cfg.login_account_attempts = 3;
+    cfg.login_delay_after = 0;
+    let svc = service!(app_with(cfg));
+
+    for password in [
+        "wrong-password",
+        "wrong-password",
+        PASSWORD,
+        "wrong-password",
+        "wrong-password",
+    ] {
+        let resp = test::call_service(
+            &svc,
+            login("user@example.com", password, "10.0.0.1").to_request(),
+        )
+        .await;
+        assert_ne!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+    let resp = test::call_service(
+        &svc,
+        login("user@example.com", PASSWORD, "10.0.0.1").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_lockout_survives_restart() {
+    let path = std::env::temp_dir().join(format!("lapp-throttle-{}.db", uuid::Uuid::new_v4()));
+    let path = path.to_str().unwrap();
+    let policy = ThrottlePolicy {
+        window: 900,
+        account_attempts: 2,
+        ip_attempts: 50,
+        lockout: 900,
+        delay_after: 0,
+    };
+
+    let throttle = LoginThrottle::new(policy, Arc::new(SqliteAttempts::open(path).unwrap()));
+    for _ in 0..2 {
+        let attempt = throttle.reserve("user@example.com", None).unwrap();
+        throttle.record_failure(attempt).unwrap();
+    }
+    drop(throttle);
+
+    let restarted = LoginThrottle::new(policy, Arc::new(SqliteAttempts::open(path).unwrap()));
+    let locked = restarted.reserve("user@example.com", Some("10.0.0.1"));
+    assert!(matches!(
+        locked,
+        Err(AppError::Auth(AuthError::AccountLocked { retry_after })) if retry_after > 0
+    ));
+
+    let _ = std::fs::remove_file(path);
+}
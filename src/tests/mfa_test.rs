@@ -0,0 +1,266 @@
+use super::support::{
+    CODE_OK, FACTOR_ID, MFA_EMAIL, MFA_TOKEN_HASH, PASSWORD, USER_ID, app, app_with, config,
+    fake_supabase, jwt, user,
+};
+use crate::api;
+use crate::domain::{Aal, ClientInfo, session_key};
+use crate::shared::time::now_secs;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn login(email: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "email": email, "password": PASSWORD }))
+}
+
+fn mfa_verify(mfa_token: &Value, code: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/mfa/verify")
+        .set_json(json!({ "mfa_token": mfa_token, "code": code }))
+}
+
+#[actix_web::test]
+async fn test_login_with_totp_factor_needs_second_step() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, login(MFA_EMAIL).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::SET_COOKIE).is_none());
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "mfa_required");
+    // Unverified factors are not offered
+    assert_eq!(
+        body["factors"],
+        json!([{ "id": FACTOR_ID, "type": "totp", "friendly_name": "Phone" }])
+    );
+    let mfa_token = &body["mfa_token"];
+
+    let resp = test::call_service(&svc, mfa_verify(mfa_token, "000000").to_request()).await;
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "AUTH_INVALID_OTP");
+
+    // The pending login survives a wrong code
+    let resp = test::call_service(&svc, mfa_verify(mfa_token, CODE_OK).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    let session = state
+        .auth
+        .sessions()
+        .get_session(&session_key(cookie.value()))
+        .unwrap();
+    assert_eq!(session.aal, Aal::Aal2);
+
+    // ...but is used up by a successful one
+    let resp = test::call_service(&svc, mfa_verify(mfa_token, CODE_OK).to_request()).await;
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "AUTH_INVALID_OTP");
+}
+
+#[actix_web::test]
+async fn test_browser_login_keeps_mfa_token_out_of_the_redirect() {
+    let (url, _) = fake_supabase();
+    let mut state = app(&url);
+    state.config.email_confirm_redirect = "https://app.example.com/welcome".to_string();
+    let svc = service!(state);
+
+    let uri = format!("/auth/confirm?token_hash={}&type=magiclink", MFA_TOKEN_HASH);
+    let resp = test::call_service(&svc, test::TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        resp.headers().get(header::LOCATION).unwrap(),
+        "https://app.example.com/welcome?mfa=required"
+    );
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "mfa_token")
+        .unwrap();
+    assert_eq!(cookie.http_only(), Some(true));
+    assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+    assert_eq!(cookie.path(), Some("/auth/mfa/verify"));
+
+    let req = test::TestRequest::post()
+        .uri("/auth/mfa/verify")
+        .cookie(Cookie::new("mfa_token", cookie.value().to_string()))
+        .set_json(json!({ "code": CODE_OK }));
+    let resp = test::call_service(&svc, req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let session = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    assert_eq!(
+        state
+            .auth
+            .sessions()
+            .get_session(&session_key(session.value()))
+            .unwrap()
+            .aal,
+        Aal::Aal2
+    );
+    let cleared = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "mfa_token")
+        .unwrap();
+    assert!(cleared.value().is_empty());
+}
+
+#[actix_web::test]
+async fn test_pending_login_is_dropped_after_too_many_wrong_codes() {
+    let (url, _) = fake_supabase();
+    let mut cfg = config(&url);
+    cfg.login_delay_after = 100;
+    cfg.login_account_attempts = 100;
+    let svc = service!(app_with(cfg));
+
+    let body: Value = test::call_and_read_body_json(&svc, login(MFA_EMAIL).to_request()).await;
+    let mfa_token = &body["mfa_token"];
+    for _ in 0..5 {
+        let resp = test::call_service(&svc, mfa_verify(mfa_token, "000000").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let resp = test::call_service(&svc, mfa_verify(mfa_token, CODE_OK).to_request()).await;
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "AUTH_INVALID_OTP");
+}
+
+#[actix_web::test]
+async fn test_wrong_codes_count_against_the_account() {
+    let (url, _) = fake_supabase();
+    let mut cfg = config(&url);
+    cfg.login_account_attempts = 2;
+    let svc = service!(app_with(cfg));
+
+    let body: Value = test::call_and_read_body_json(&svc, login(MFA_EMAIL).to_request()).await;
+    let mfa_token = &body["mfa_token"];
+    for _ in 0..2 {
+        test::call_service(&svc, mfa_verify(mfa_token, "000000").to_request()).await;
+    }
+
+    let resp = test::call_service(&svc, mfa_verify(mfa_token, CODE_OK).to_request()).await;
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "ACCOUNT_LOCKED");
+    let resp = test::call_service(&svc, login(MFA_EMAIL).to_request()).await;
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "ACCOUNT_LOCKED");
+}
+
+#[actix_web::test]
+async fn test_login_without_factor_opens_aal1_session() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, login("user@example.com").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+
+    let req = test::TestRequest::get()
+        .uri("/user/sessions")
+        .cookie(Cookie::new("session_id", cookie.value().to_string()))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&svc, req).await;
+    assert_eq!(body[0]["aal"], "aal1");
+}
+
+#[actix_web::test]
+async fn test_enroll_then_verify_steps_session_up_to_aal2() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let mut alice = user(USER_ID, "authenticated");
+    alice.access_token = jwt(now_secs() + 3600, true);
+    let session = state
+        .auth
+        .sessions()
+        .create_session(alice, ClientInfo::default());
+    let svc = service!(state);
+    let cookie = Cookie::new("session_id", session.secret.clone());
+
+    let req = test::TestRequest::post()
+        .uri("/user/mfa/enroll")
+        .cookie(cookie.clone())
+        .set_json(json!({ "friendly_name": "Phone" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&svc, req).await;
+    assert_eq!(body["factor_id"], "factor-new");
+    assert!(body["uri"].as_str().unwrap().starts_with("otpauth://totp/"));
+
+    // Removing a factor needs aal2
+    let unenroll = || {
+        test::TestRequest::delete()
+            .uri(&format!("/user/mfa/{}", FACTOR_ID))
+            .cookie(cookie.clone())
+            .to_request()
+    };
+    let resp = test::call_service(&svc, unenroll()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "AUTH_MFA_REQUIRED");
+
+    let req = test::TestRequest::post()
+        .uri("/user/mfa/verify")
+        .cookie(cookie.clone())
+        .set_json(json!({ "factor_id": "factor-new", "code": CODE_OK }))
+        .to_request();
+    let resp = test::call_service(&svc, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let elevated = state.auth.sessions().get_session(&session.id).unwrap();
+    assert_eq!(elevated.aal, Aal::Aal2);
+    assert_eq!(elevated.user.access_token, "access-new");
+
+    let resp = test::call_service(&svc, unenroll()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+}
+
+#[actix_web::test]
+async fn test_bearer_jwt_aal_claim_is_honoured() {
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+    let (url, _) = fake_supabase();
+    let svc = service!(app(&url));
+
+    let claims = json!({ "sub": USER_ID, "exp": now_secs() + 3600, "aal": "aal2" });
+    let token = format!(
+        "eyJhbGciOiJIUzI1NiJ9.{}.valid",
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+    let unenroll = |token: &str| {
+        test::TestRequest::delete()
+            .uri(&format!("/user/mfa/{}", FACTOR_ID))
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request()
+    };
+
+    let resp = test::call_service(&svc, unenroll(&jwt(now_secs() + 3600, true))).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let resp = test::call_service(&svc, unenroll(&token)).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+}
@@ -1 +1,27 @@
+mod support;
+
+mod account_change_test;
+mod account_deletion_test;
+mod admin_users_test;
+mod audit_test;
+mod authz_test;
+mod bearer_auth_test;
+mod entitlement_test;
+mod extractor_test;
+mod jwt_verifier_test;
+mod local_provider_test;
+mod login_throttle_test;
+mod mfa_test;
+mod oauth_test;
+mod otp_login_test;
+mod passkey_test;
+mod password_reset_test;
+mod phone_test;
+mod session_encryption_test;
+mod session_expiry_test;
+mod session_file_test;
+mod session_store_test;
+mod signup_confirmation_test;
+mod sms_login_test;
 mod supabase_login_test;
+mod token_refresh_test;
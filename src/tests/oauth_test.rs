@@ -0,0 +1,124 @@
+use super::support::{OAUTH_CODE_OK, app, fake_supabase};
+use crate::api;
+use crate::infrastructure::oauth::code_challenge;
+use actix_web::cookie::Cookie;
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::Value;
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn callback(query: &str, flow: Option<&str>) -> test::TestRequest {
+    let req = test::TestRequest::get().uri(&format!("/auth/callback?{}", query));
+    match flow {
+        Some(id) => req.cookie(Cookie::new("oauth_flow", id.to_string())),
+        None => req,
+    }
+}
+
+#[actix_web::test]
+async fn test_code_challenge_matches_rfc7636() {
+    assert_eq!(
+        code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+        "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+    );
+}
+
+#[actix_web::test]
+async fn test_oauth_redirect_and_callback_create_session() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let start = test::TestRequest::get()
+        .uri("/auth/oauth/github")
+        .to_request();
+    let resp = test::call_service(&svc, start).await;
+    assert_eq!(resp.status(), StatusCode::FOUND);
+
+    let location = resp
+        .headers()
+        .get(header::LOCATION)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(location.starts_with(&format!("{}/auth/v1/authorize?provider=github", url)));
+    assert!(location.contains("code_challenge_method=s256"));
+    assert!(location.contains("redirect_to=http%3A%2F%2Flocalhost%3A8080%2Fauth%2Fcallback"));
+    let flow = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "oauth_flow")
+        .unwrap();
+    let flow = flow.value().to_string();
+
+    let query = format!("code={}", OAUTH_CODE_OK);
+    let resp = test::call_service(&svc, callback(&query, Some(&flow)).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.response().cookies().any(|c| c.name() == "session_id"));
+
+    // A flow completes once
+    let resp = test::call_service(&svc, callback(&query, Some(&flow)).to_request()).await;
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "AUTH_OAUTH_STATE");
+}
+
+#[actix_web::test]
+async fn test_oauth_callback_without_flow_cookie_is_rejected() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let query = format!("code={}", OAUTH_CODE_OK);
+    let resp = test::call_service(&svc, callback(&query, None).to_request()).await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "AUTH_OAUTH_STATE");
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+}
+
+#[actix_web::test]
+async fn test_oauth_denied_by_user_fails() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let start = test::TestRequest::get()
+        .uri("/auth/oauth/google")
+        .to_request();
+    let resp = test::call_service(&svc, start).await;
+    let flow = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "oauth_flow")
+        .unwrap();
+
+    let query = "error=access_denied&error_description=User+denied";
+    let resp = test::call_service(&svc, callback(query, Some(flow.value())).to_request()).await;
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "AUTH_OAUTH_FAILED");
+}
+
+#[actix_web::test]
+async fn test_oauth_unknown_provider_is_rejected() {
+    let state = app("http://127.0.0.1:1");
+    let svc = service!(state);
+
+    let start = test::TestRequest::get()
+        .uri("/auth/oauth/myspace")
+        .to_request();
+    let resp = test::call_service(&svc, start).await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
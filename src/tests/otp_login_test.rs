@@ -0,0 +1,117 @@
+use super::support::{CODE_OK, RATE_LIMITED_EMAIL, TOKEN_HASH_OK, app, fake_supabase};
+use crate::api;
+use crate::domain::session_key;
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn verify(code: &str, mode: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/verify-otp")
+        .set_json(json!({ "email": "user@example.com", "code": code, "mode": mode }))
+}
+
+#[actix_web::test]
+async fn test_otp_request_response_is_uniform() {
+    let (url, calls) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let mut bodies = Vec::new();
+    for email in ["user@example.com", RATE_LIMITED_EMAIL] {
+        let req = test::TestRequest::post()
+            .uri("/auth/otp")
+            .set_json(json!({ "email": email }))
+            .to_request();
+        let resp = test::call_service(&svc, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        bodies.push(test::read_body(resp).await);
+    }
+
+    assert_eq!(bodies[0], bodies[1]);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[actix_web::test]
+async fn test_verify_otp_code_creates_session() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, verify(CODE_OK, "cookie").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::SET_COOKIE).is_some());
+
+    let resp = test::call_service(&svc, verify(CODE_OK, "token").to_request()).await;
+    let body: Value = test::read_body_json(resp).await;
+    let token = body["token"].as_str().unwrap();
+    assert!(
+        state
+            .auth
+            .sessions()
+            .get_session(&session_key(token))
+            .is_some()
+    );
+}
+
+#[actix_web::test]
+async fn test_verify_otp_wrong_code_is_rejected() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, verify("000000", "cookie").to_request()).await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert!(resp.headers().get(header::SET_COOKIE).is_none());
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "AUTH_INVALID_OTP");
+}
+
+#[actix_web::test]
+async fn test_repeated_wrong_codes_are_rate_limited() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    for _ in 0..2 {
+        let resp = test::call_service(&svc, verify("000000", "cookie").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    // Even the right code has to wait now
+    let resp = test::call_service(&svc, verify(CODE_OK, "cookie").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().get(header::RETRY_AFTER).is_some());
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "RATE_LIMITED");
+}
+
+#[actix_web::test]
+async fn test_magic_link_logs_in() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/auth/confirm?token_hash={}&type=magiclink",
+            TOKEN_HASH_OK
+        ))
+        .to_request();
+    let resp = test::call_service(&svc, req).await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::SET_COOKIE).is_some());
+}
@@ -0,0 +1,450 @@
+use super::support::{PASSWORD, USER_ID, app, fake_supabase, jwt, user};
+use crate::api;
+use crate::app::App;
+use crate::domain::{AuditAction, AuditQuery, ClientInfo, Session, session_key};
+use crate::shared::time::now_secs;
+use actix_web::cookie::Cookie;
+use actix_web::http::StatusCode;
+use actix_web::{App as ActixApp, test, web};
+use aes_gcm::aead::OsRng;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use ciborium::Value as Cbor;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+const ORIGIN: &str = "http://localhost:3000";
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+/// Software authenticator: one ES256 credential, answers like a browser's `toJSON()`
+#[derive(Clone)]
+struct Authenticator {
+    key: SigningKey,
+    credential_id: Vec<u8>,
+    origin: String,
+    counter: u32,
+    /// Flags set in authenticator data: UP | UV by default
+    flags: u8,
+}
+
+impl Authenticator {
+    fn new() -> Self {
+        Self {
+            key: SigningKey::random(&mut OsRng),
+            credential_id: uuid::Uuid::new_v4().as_bytes().to_vec(),
+            origin: ORIGIN.to_string(),
+            counter: 0,
+            flags: 0x01 | 0x04,
+        }
+    }
+
+    fn credential_id(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.credential_id)
+    }
+
+    fn client_data(&self, kind: &str, options: &Value) -> Vec<u8> {
+        json!({
+            "type": kind,
+            "challenge": options["publicKey"]["challenge"],
+            "origin": self.origin,
+            "crossOrigin": false
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn authenticator_data(&self, rp_id: &str, attested: bool) -> Vec<u8> {
+        let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(self.flags | if attested { 0x40 } else { 0 });
+        data.extend_from_slice(&self.counter.to_be_bytes());
+        if attested {
+            let point = self.key.verifying_key().to_encoded_point(false);
+            let int = |i: i64| Cbor::Integer(i.into());
+            let cose_key = Cbor::Map(vec![
+                (int(1), int(2)),
+                (int(3), int(-7)),
+                (int(-1), int(1)),
+                (int(-2), Cbor::Bytes(point.x().unwrap().to_vec())),
+                (int(-3), Cbor::Bytes(point.y().unwrap().to_vec())),
+            ]);
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            data.extend_from_slice(&self.credential_id);
+            ciborium::into_writer(&cose_key, &mut data).unwrap();
+        }
+        data
+    }
+
+    /// `navigator.credentials.create()`
+    fn create(&self, options: &Value) -> Value {
+        let rp_id = options["publicKey"]["rp"]["id"].as_str().unwrap();
+        let attestation = Cbor::Map(vec![
+            (Cbor::Text("fmt".into()), Cbor::Text("none".into())),
+            (Cbor::Text("attStmt".into()), Cbor::Map(vec![])),
+            (
+                Cbor::Text("authData".into()),
+                Cbor::Bytes(self.authenticator_data(rp_id, true)),
+            ),
+        ]);
+        let mut attestation_object = Vec::new();
+        ciborium::into_writer(&attestation, &mut attestation_object).unwrap();
+
+        json!({
+            "id": self.credential_id(),
+            "rawId": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": URL_SAFE_NO_PAD.encode(self.client_data("webauthn.create", options)),
+                "attestationObject": URL_SAFE_NO_PAD.encode(attestation_object)
+            }
+        })
+    }
+
+    /// `navigator.credentials.get()`, bumping the signature counter
+    fn get(&mut self, options: &Value) -> Value {
+        self.counter += 1;
+        let rp_id = options["publicKey"]["rpId"].as_str().unwrap();
+        let authenticator_data = self.authenticator_data(rp_id, false);
+        let client_data = self.client_data("webauthn.get", options);
+
+        let mut signed = authenticator_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&client_data));
+        let signature: Signature = self.key.sign(&signed);
+
+        json!({
+            "id": self.credential_id(),
+            "rawId": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": URL_SAFE_NO_PAD.encode(client_data),
+                "authenticatorData": URL_SAFE_NO_PAD.encode(authenticator_data),
+                "signature": URL_SAFE_NO_PAD.encode(signature.to_der().as_bytes()),
+                "userHandle": URL_SAFE_NO_PAD.encode(USER_ID)
+            }
+        })
+    }
+}
+
+/// Logged-in `USER_ID`, whose access token the fake Supabase accepts
+fn logged_in(state: &App) -> Session {
+    let mut alice = user(USER_ID, "authenticated");
+    alice.access_token = jwt(now_secs() + 3600, true);
+    state
+        .auth
+        .sessions()
+        .create_session(alice, ClientInfo::default())
+}
+
+/// Run a registration ceremony, re-authenticating with the password, returning the status of
+/// the final step
+macro_rules! register {
+    ($svc:expr, $session:expr, $authenticator:expr) => {{
+        let cookie = Cookie::new("session_id", $session.secret.clone());
+        let req = test::TestRequest::post()
+            .uri("/user/passkeys/options")
+            .cookie(cookie.clone())
+            .set_json(json!({ "password": PASSWORD }))
+            .to_request();
+        let options: Value = test::call_and_read_body_json(&$svc, req).await;
+
+        let req = test::TestRequest::post()
+            .uri("/user/passkeys")
+            .cookie(cookie)
+            .set_json(json!({
+                "ceremony_id": options["ceremony_id"],
+                "name": "Laptop",
+                "credential": $authenticator.create(&options)
+            }))
+            .to_request();
+        test::call_service(&$svc, req).await
+    }};
+}
+
+/// Fetch login options and answer them with `$answer(&options)`
+macro_rules! login {
+    ($svc:expr, $answer:expr) => {{
+        let req = test::TestRequest::post().uri("/auth/passkey/options").to_request();
+        let options: Value = test::call_and_read_body_json(&$svc, req).await;
+        let req = test::TestRequest::post()
+            .uri("/auth/passkey/login")
+            .set_json(json!({ "ceremony_id": options["ceremony_id"], "credential": $answer(&options) }))
+            .to_request();
+        test::call_service(&$svc, req).await
+    }};
+}
+
+#[actix_web::test]
+async fn test_passkey_registration_then_login_opens_session() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let session = logged_in(&state);
+    let svc = service!(state);
+    let mut authenticator = Authenticator::new();
+
+    let resp = register!(svc, session, authenticator);
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["id"], authenticator.credential_id());
+    assert_eq!(body["name"], "Laptop");
+
+    // The new passkey is excluded from the next registration
+    let req = test::TestRequest::post()
+        .uri("/user/passkeys/options")
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .set_json(json!({ "password": PASSWORD }))
+        .to_request();
+    let options: Value = test::call_and_read_body_json(&svc, req).await;
+    assert_eq!(
+        options["publicKey"]["excludeCredentials"][0]["id"],
+        authenticator.credential_id()
+    );
+
+    let resp = login!(svc, |options: &Value| authenticator.get(options));
+    assert_eq!(resp.status(), StatusCode::OK);
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == "session_id")
+        .unwrap();
+    let created = state
+        .auth
+        .sessions()
+        .get_session(&session_key(cookie.value()))
+        .unwrap();
+    assert_eq!(created.user.id, USER_ID);
+    // Tokens minted without the password are on record
+    let query = AuditQuery {
+        user_id: Some(USER_ID.to_string()),
+        limit: 10,
+        ..AuditQuery::default()
+    };
+    let entries = state.auth.audit().query(&query).unwrap();
+    let issued = entries
+        .iter()
+        .find(|e| e.action == AuditAction::ServiceSignIn)
+        .unwrap();
+    assert_eq!(issued.detail.as_deref(), Some("passkey via supabase"));
+
+    let req = test::TestRequest::get()
+        .uri("/user/passkeys")
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&svc, req).await;
+    assert!(list[0]["last_used_at"].is_u64());
+}
+
+#[actix_web::test]
+async fn test_passkey_registration_needs_fresh_proof_from_the_same_session() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let session = logged_in(&state);
+    let other = logged_in(&state);
+    let svc = service!(state);
+    let authenticator = Authenticator::new();
+
+    let options = |session: &Session, body: Value| {
+        test::TestRequest::post()
+            .uri("/user/passkeys/options")
+            .cookie(Cookie::new("session_id", session.secret.clone()))
+            .set_json(body)
+            .to_request()
+    };
+    for body in [json!({}), json!({ "password": "wrong-password" })] {
+        let resp = test::call_service(&svc, options(&session, body)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let error: Value = test::read_body_json(resp).await;
+        assert_eq!(error["field"], "password");
+    }
+
+    // A ceremony opened by one session cannot be finished by another
+    let options: Value =
+        test::call_and_read_body_json(&svc, options(&session, json!({ "password": PASSWORD })))
+            .await;
+    let req = test::TestRequest::post()
+        .uri("/user/passkeys")
+        .cookie(Cookie::new("session_id", other.secret.clone()))
+        .set_json(json!({ "ceremony_id": options["ceremony_id"], "credential": authenticator.create(&options) }))
+        .to_request();
+    assert_eq!(
+        test::call_service(&svc, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert!(state.passkeys.list(USER_ID).unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn test_passkey_login_rejects_foreign_origin_and_cloned_authenticator() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let session = logged_in(&state);
+    let svc = service!(state);
+    let mut authenticator = Authenticator::new();
+    let resp = register!(svc, session, authenticator);
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let mut phished = authenticator.clone();
+    phished.origin = "https://lapp-login.example".to_string();
+    let resp = login!(svc, |options: &Value| phished.get(options));
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["code"], "AUTH_PASSKEY_REJECTED");
+
+    let mut clone = authenticator.clone();
+    let resp = login!(svc, |options: &Value| authenticator.get(options));
+    assert_eq!(resp.status(), StatusCode::OK);
+    // Same key, counter not advanced past the one just used
+    let resp = login!(svc, |options: &Value| clone.get(options));
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_passkey_answer_cannot_be_replayed() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let session = logged_in(&state);
+    let svc = service!(state);
+    let mut authenticator = Authenticator::new();
+    register!(svc, session, authenticator);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/passkey/options")
+        .to_request();
+    let options: Value = test::call_and_read_body_json(&svc, req).await;
+    let body =
+        json!({ "ceremony_id": options["ceremony_id"], "credential": authenticator.get(&options) });
+    let login = || {
+        test::TestRequest::post()
+            .uri("/auth/passkey/login")
+            .set_json(&body)
+            .to_request()
+    };
+
+    assert_eq!(
+        test::call_service(&svc, login()).await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        test::call_service(&svc, login()).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_web::test]
+async fn test_passkey_without_user_verification_is_refused() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let session = logged_in(&state);
+    let svc = service!(state);
+    let mut authenticator = Authenticator::new();
+    authenticator.flags = 0x01;
+
+    let resp = register!(svc, session, authenticator);
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::get()
+        .uri("/user/passkeys")
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&svc, req).await;
+    assert_eq!(list, json!([]));
+}
+
+#[actix_web::test]
+async fn test_removed_passkey_can_no_longer_log_in() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let session = logged_in(&state);
+    let svc = service!(state);
+    let mut authenticator = Authenticator::new();
+    register!(svc, session, authenticator);
+
+    let remove = || {
+        test::TestRequest::delete()
+            .uri(&format!("/user/passkeys/{}", authenticator.credential_id()))
+            .cookie(Cookie::new("session_id", session.secret.clone()))
+            .to_request()
+    };
+    assert_eq!(
+        test::call_service(&svc, remove()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        test::call_service(&svc, remove()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let resp = login!(svc, |options: &Value| authenticator.get(options));
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_passkey_is_proof_enough_to_close_the_account() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let registered_from = logged_in(&state);
+    let session = logged_in(&state);
+    let svc = service!(state);
+    let mut authenticator = Authenticator::new();
+    assert_eq!(
+        register!(svc, registered_from, authenticator).status(),
+        StatusCode::CREATED
+    );
+
+    let delete = |session: &Session, options: &Value, credential: Value| {
+        test::TestRequest::delete()
+            .uri("/user/me")
+            .cookie(Cookie::new("session_id", session.secret.clone()))
+            .set_json(json!({ "passkey": { "ceremony_id": options["ceremony_id"], "credential": credential } }))
+            .to_request()
+    };
+
+    // Someone else's passkey proves nothing
+    let mut stranger = Authenticator::new();
+    let req = test::TestRequest::post()
+        .uri("/auth/passkey/options")
+        .to_request();
+    let options: Value = test::call_and_read_body_json(&svc, req).await;
+    let resp = test::call_service(&svc, delete(&session, &options, stranger.get(&options))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "passkey");
+
+    // Nor does one added by the session asking: it may be a hijacker's own
+    let req = test::TestRequest::post()
+        .uri("/auth/passkey/options")
+        .to_request();
+    let options: Value = test::call_and_read_body_json(&svc, req).await;
+    let resp = test::call_service(
+        &svc,
+        delete(&registered_from, &options, authenticator.get(&options)),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(resp).await;
+    assert_eq!(error["field"], "passkey");
+
+    let req = test::TestRequest::post()
+        .uri("/auth/passkey/options")
+        .to_request();
+    let options: Value = test::call_and_read_body_json(&svc, req).await;
+    let resp = test::call_service(
+        &svc,
+        delete(&session, &options, authenticator.get(&options)),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert!(state.auth.sessions().get_session(&session.id).is_none());
+    assert!(state.passkeys.list(USER_ID).unwrap().is_empty());
+}
@@ -0,0 +1,164 @@
+use super::support::{
+    CODE_OK, RATE_LIMITED_EMAIL, TOKEN_HASH_OK, USER_ID, WEAK_PASSWORD, app, fake_supabase, user,
+};
+use crate::api;
+use crate::domain::ClientInfo;
+use actix_web::http::StatusCode;
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn post(path: &str, body: Value) -> test::TestRequest {
+    test::TestRequest::post().uri(path).set_json(body)
+}
+
+#[actix_web::test]
+async fn test_forgot_password_response_is_uniform() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let mut bodies = Vec::new();
+    for email in ["user@example.com", "nobody@example.com", RATE_LIMITED_EMAIL] {
+        let resp = test::call_service(
+            &svc,
+            post("/auth/forgot-password", json!({ "email": email })).to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        bodies.push(test::read_body(resp).await);
+    }
+
+    assert!(bodies.windows(2).all(|w| w[0] == w[1]));
+}
+
+#[actix_web::test]
+async fn test_forgot_password_reports_unreachable_supabase() {
+    let state = app("http://127.0.0.1:1");
+    let svc = service!(state);
+
+    let resp = test::call_service(
+        &svc,
+        post(
+            "/auth/forgot-password",
+            json!({ "email": "user@example.com" }),
+        )
+        .to_request(),
+    )
+    .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+}
+
+#[actix_web::test]
+async fn test_reset_password_revokes_every_session() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let laptop = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let phone = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let other = state
+        .auth
+        .sessions()
+        .create_session(user("user-2", "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let resp = test::call_service(
+        &svc,
+        post(
+            "/auth/reset-password",
+            json!({ "token_hash": TOKEN_HASH_OK, "password": "new-password" }),
+        )
+        .to_request(),
+    )
+    .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(state.auth.sessions().get_session(&laptop.id).is_none());
+    assert!(state.auth.sessions().get_session(&phone.id).is_none());
+    assert!(state.auth.sessions().get_session(&other.id).is_some());
+}
+
+#[actix_web::test]
+async fn test_reset_password_with_email_code() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let body = json!({ "email": "user@example.com", "code": CODE_OK, "password": "new-password" });
+    let resp = test::call_service(&svc, post("/auth/reset-password", body).to_request()).await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_reset_password_rejects_invalid_token() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    for body in [
+        json!({ "token_hash": "used-or-forged", "password": "new-password" }),
+        json!({ "email": "nobody@example.com", "code": CODE_OK, "password": "new-password" }),
+    ] {
+        let resp = test::call_service(&svc, post("/auth/reset-password", body).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "AUTH_INVALID_RESET_TOKEN");
+    }
+}
+
+#[actix_web::test]
+async fn test_reset_password_requires_proof() {
+    let state = app("http://127.0.0.1:1");
+    let svc = service!(state);
+
+    let body = json!({ "email": "user@example.com", "password": "new-password" });
+    let resp = test::call_service(&svc, post("/auth/reset-password", body).to_request()).await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["field"], "token_hash");
+}
+
+#[actix_web::test]
+async fn test_rejected_password_keeps_sessions() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let session = state
+        .auth
+        .sessions()
+        .create_session(user(USER_ID, "authenticated"), ClientInfo::default());
+    let svc = service!(state);
+
+    let resp = test::call_service(
+        &svc,
+        post(
+            "/auth/reset-password",
+            json!({ "token_hash": TOKEN_HASH_OK, "password": WEAK_PASSWORD }),
+        )
+        .to_request(),
+    )
+    .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["field"], "password");
+    assert!(state.auth.sessions().get_session(&session.id).is_some());
+}
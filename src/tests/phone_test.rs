@@ -0,0 +1,48 @@
+use super::support::PHONE_OK;
+use crate::shared::phone::{PhoneNumber, normalize_e164};
+
+#[test]
+fn test_phone_from_parts_normalizes_to_e164() {
+    let cases = [
+        ("+33", "06 12 34 56 78", "+33612345678"),
+        ("0033", "(0)6-12.34.56.78", "+33612345678"),
+        ("1", "415 555 2671", "+14155552671"),
+        // Italian numbers keep their leading 0
+        ("+39", "06 1234 5678", "+390612345678"),
+    ];
+    for (country_code, number, expected) in cases {
+        assert_eq!(
+            PhoneNumber::from_parts(country_code, number)
+                .unwrap()
+                .e164(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_phone_from_parts_rejects_invalid_numbers() {
+    let cases = [
+        ("+33", "12"),
+        ("+0", "612345678"),
+        ("+1234", "5552671"),
+        ("+33", "06 12 AB"),
+        ("+44", "1234567890123456"),
+    ];
+    for (country_code, number) in cases {
+        assert!(
+            PhoneNumber::from_parts(country_code, number).is_err(),
+            "{} {}",
+            country_code,
+            number
+        );
+    }
+}
+
+#[test]
+fn test_normalize_e164_requires_international_format() {
+    assert_eq!(normalize_e164("+33 6 12 34 56 78").unwrap(), PHONE_OK);
+    assert_eq!(normalize_e164("0033612345678").unwrap(), PHONE_OK);
+    assert!(normalize_e164("0612345678").is_err());
+    assert!(normalize_e164("+33 6 12 34 56 78 90 12 34").is_err());
+}
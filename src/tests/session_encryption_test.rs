@@ -0,0 +1,186 @@
+use crate::domain::{
+    Aal, ClientInfo, Session, SessionBackend, SessionPolicy, SessionStore, User, WriteBehind,
+    session_key,
+};
+use crate::infrastructure::crypto::TokenCipher;
+use crate::infrastructure::session::{EncryptedBackend, FileBackend};
+use crate::shared::time::now_secs;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as B64;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!("lapp-sessions-{}.csv", uuid::Uuid::new_v4()))
+}
+
+fn cleanup(path: &PathBuf) {
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(format!("{}.journal", path.display()));
+}
+
+fn key(byte: u8) -> String {
+    B64.encode([byte; 32])
+}
+
+fn cipher(spec: &str, active: &str) -> TokenCipher {
+    TokenCipher::from_spec(spec, active).unwrap()
+}
+
+fn session() -> Session {
+    Session::new(
+        User {
+            id: "user-1".to_string(),
+            email: "user@example.com".to_string(),
+            username: "user".to_string(),
+            role: "authenticated".to_string(),
+            roles: Vec::new(),
+            access_token: "secret-access-token".to_string(),
+            refresh_token: "secret-refresh-token".to_string(),
+            expires_at: now_secs() + 3600,
+        },
+        ClientInfo::default(),
+        Aal::Aal1,
+    )
+}
+
+fn encrypted(path: &PathBuf, cipher: TokenCipher) -> EncryptedBackend {
+    EncryptedBackend::new(Arc::new(FileBackend::new(path)), cipher)
+}
+
+#[test]
+fn test_tokens_are_not_written_in_plaintext() {
+    let path = temp_path();
+    let backend = encrypted(&path, cipher(&format!("k1:{}", key(1)), "k1"));
+    let original = session();
+
+    backend.compact(std::slice::from_ref(&original)).unwrap();
+    let raw = std::fs::read_to_string(&path).unwrap();
+    let loaded = backend.load().unwrap();
+    cleanup(&path);
+
+    assert!(!raw.contains("secret-access-token"));
+    assert!(!raw.contains("secret-refresh-token"));
+    assert!(raw.contains("enc:v1:k1:"));
+    assert_eq!(loaded[0].user.access_token, original.user.access_token);
+    assert_eq!(loaded[0].user.refresh_token, original.user.refresh_token);
+}
+
+#[test]
+fn test_rotated_key_reencrypts_on_load() {
+    let path = temp_path();
+    encrypted(&path, cipher(&format!("k1:{}", key(1)), "k1"))
+        .compact(&[session()])
+        .unwrap();
+
+    // k2 becomes active, k1 stays configured for reading
+    let rotated = cipher(&format!("k1:{},k2:{}", key(1), key(2)), "k2");
+    let store = SessionStore::new(
+        SessionPolicy::default(),
+        Arc::new(encrypted(&path, rotated)),
+        WriteBehind::default(),
+    );
+    let raw = std::fs::read_to_string(&path).unwrap();
+    cleanup(&path);
+
+    assert_eq!(store.list_user_sessions("user-1").len(), 1);
+    assert!(raw.contains("enc:v1:k2:"));
+    assert!(!raw.contains("enc:v1:k1:"));
+}
+
+#[test]
+fn test_plaintext_sessions_are_migrated() {
+    let path = temp_path();
+    FileBackend::new(&path).compact(&[session()]).unwrap();
+
+    let store = SessionStore::new(
+        SessionPolicy::default(),
+        Arc::new(encrypted(&path, cipher(&format!("k1:{}", key(1)), "k1"))),
+        WriteBehind::default(),
+    );
+    let raw = std::fs::read_to_string(&path).unwrap();
+    cleanup(&path);
+
+    let sessions = store.list_user_sessions("user-1");
+    assert_eq!(sessions[0].user.access_token, "secret-access-token");
+    assert!(!raw.contains("secret-access-token"));
+}
+
+#[test]
+fn test_session_secret_is_not_written() {
+    let path = temp_path();
+    let backend = encrypted(&path, cipher(&format!("k1:{}", key(1)), "k1"));
+    let original = session();
+
+    backend.compact(std::slice::from_ref(&original)).unwrap();
+    let raw = std::fs::read_to_string(&path).unwrap();
+    let loaded = backend.load().unwrap();
+    cleanup(&path);
+
+    assert!(!raw.contains(&original.secret));
+    assert!(raw.contains(&original.id));
+    assert_eq!(loaded[0].id, session_key(&original.secret));
+    assert!(loaded[0].secret.is_empty());
+}
+
+#[test]
+fn test_raw_session_ids_are_hashed_on_load() {
+    let path = temp_path();
+    let mut legacy = session();
+    legacy.id = "legacy-cookie-secret".to_string();
+    encrypted(&path, cipher(&format!("k1:{}", key(1)), "k1"))
+        .compact(&[legacy])
+        .unwrap();
+
+    let store = SessionStore::new(
+        SessionPolicy::default(),
+        Arc::new(encrypted(&path, cipher(&format!("k1:{}", key(1)), "k1"))),
+        WriteBehind::default(),
+    );
+    let raw = std::fs::read_to_string(&path).unwrap();
+    cleanup(&path);
+
+    assert!(
+        store
+            .get_session(&session_key("legacy-cookie-secret"))
+            .is_some()
+    );
+    assert!(!raw.contains("legacy-cookie-secret"));
+}
+
+#[test]
+fn test_unknown_key_drops_session() {
+    let path = temp_path();
+    encrypted(&path, cipher(&format!("old:{}", key(1)), "old"))
+        .compact(&[session()])
+        .unwrap();
+
+    let loaded = encrypted(&path, cipher(&format!("new:{}", key(2)), "new"))
+        .load()
+        .unwrap();
+    cleanup(&path);
+
+    assert!(loaded.is_empty());
+}
+
+#[test]
+fn test_ciphertext_is_bound_to_its_session() {
+    let cipher = cipher(&format!("k1:{}", key(1)), "k1");
+    let sealed = cipher.seal("token", "session-a:access_token");
+
+    assert_eq!(
+        cipher
+            .open(&sealed, "session-a:access_token")
+            .unwrap()
+            .plaintext,
+        "token"
+    );
+    assert!(cipher.open(&sealed, "session-b:access_token").is_err());
+}
+
+#[test]
+fn test_invalid_key_spec_is_rejected() {
+    assert!(TokenCipher::from_spec("k1:not-base64!", "k1").is_err());
+    assert!(TokenCipher::from_spec(&format!("k1:{}", B64.encode([0u8; 16])), "k1").is_err());
+    assert!(TokenCipher::from_spec(&format!("k1:{}", key(1)), "k2").is_err());
+}
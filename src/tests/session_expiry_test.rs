@@ -0,0 +1,86 @@
+use crate::domain::{Aal, ClientInfo, Session, SessionLimit, SessionPolicy, User};
+
+fn user(expires_at: u64) -> User {
+    User {
+        id: "user-1".to_string(),
+        email: "user@example.com".to_string(),
+        username: "user".to_string(),
+        role: "authenticated".to_string(),
+        roles: Vec::new(),
+        access_token: "access".to_string(),
+        refresh_token: "refresh".to_string(),
+        expires_at,
+    }
+}
+
+fn session(created_at: u64, last_seen: u64, expires_at: u64) -> Session {
+    Session {
+        id: "session-1".to_string(),
+        secret: String::new(),
+        device_id: "device-1".to_string(),
+        user: user(expires_at),
+        created_at,
+        last_seen,
+        client: ClientInfo::default(),
+        aal: Aal::Aal1,
+        verified_at: created_at,
+    }
+}
+
+const POLICY: SessionPolicy = SessionPolicy {
+    idle_ttl: 60,
+    absolute_ttl: 600,
+    limit: SessionLimit::Unlimited,
+};
+
+#[test]
+fn test_session_alive_within_ttls() {
+    let s = session(1_000, 1_030, 10_000);
+    assert!(!s.is_expired(&POLICY, 1_050));
+}
+
+#[test]
+fn test_session_expires_when_idle() {
+    let s = session(1_000, 1_000, 10_000);
+    assert!(s.is_expired(&POLICY, 1_060));
+}
+
+#[test]
+fn test_session_expires_after_absolute_lifetime_even_if_active() {
+    let s = session(1_000, 1_599, 10_000);
+    assert!(s.is_expired(&POLICY, 1_600));
+}
+
+#[test]
+fn test_expired_supabase_token_needs_refresh_but_keeps_session() {
+    let s = session(1_000, 1_000, 1_010);
+    assert!(!s.is_expired(&POLICY, 1_010));
+    assert!(s.needs_refresh(0, 1_010));
+}
+
+#[test]
+fn test_token_refreshed_within_margin() {
+    let s = session(1_000, 1_000, 1_100);
+    assert!(!s.needs_refresh(30, 1_060));
+    assert!(s.needs_refresh(30, 1_070));
+}
+
+#[test]
+fn test_session_limit_parsing() {
+    assert_eq!("single".parse(), Ok(SessionLimit::Single));
+    assert_eq!("Unlimited".parse(), Ok(SessionLimit::Unlimited));
+    assert_eq!("1".parse(), Ok(SessionLimit::Single));
+    assert_eq!("5".parse(), Ok(SessionLimit::MostRecent(5)));
+    assert!("0".parse::<SessionLimit>().is_err());
+    assert!("many".parse::<SessionLimit>().is_err());
+}
+
+#[test]
+fn test_only_a_recent_second_factor_counts_as_fresh_proof() {
+    let mut s = session(1_000, 1_000, 10_000);
+    assert!(!s.recently_verified_aal2(300, 1_010));
+
+    s.aal = Aal::Aal2;
+    assert!(s.recently_verified_aal2(300, 1_010));
+    assert!(!s.recently_verified_aal2(300, 1_300));
+}
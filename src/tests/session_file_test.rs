@@ -0,0 +1,166 @@
+use crate::domain::{Aal, ClientInfo, Session, SessionBackend, SessionOp, User};
+use crate::infrastructure::session::FileBackend;
+use crate::shared::time::now_secs;
+use std::path::PathBuf;
+
+fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!("lapp-sessions-{}.csv", uuid::Uuid::new_v4()))
+}
+
+fn session(username: &str, user_agent: &str) -> Session {
+    Session::new(
+        User {
+            id: "user-1".to_string(),
+            email: "o'brien,jr@example.com".to_string(),
+            username: username.to_string(),
+            role: "authenticated".to_string(),
+            roles: Vec::new(),
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            expires_at: now_secs() + 3600,
+        },
+        ClientInfo {
+            ip: None,
+            user_agent: Some(user_agent.to_string()),
+        },
+        Aal::Aal1,
+    )
+}
+
+#[test]
+fn test_fields_with_commas_quotes_and_newlines_round_trip() {
+    let path = temp_path();
+    let backend = FileBackend::new(&path);
+    let original = session("Doe, \"JD\"\nJohn", "Mozilla/5.0 (KHTML, like Gecko)");
+
+    backend.compact(std::slice::from_ref(&original)).unwrap();
+    let loaded = backend.load().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].user.username, original.user.username);
+    assert_eq!(loaded[0].user.email, original.user.email);
+    assert_eq!(loaded[0].client.user_agent, original.client.user_agent);
+    assert_eq!(loaded[0].client.ip, None);
+}
+
+#[test]
+fn test_corrupt_rows_are_skipped() {
+    let path = temp_path();
+    let backend = FileBackend::new(&path);
+    backend.compact(&[session("alice", "ua")]).unwrap();
+
+    let mut contents = std::fs::read_to_string(&path).unwrap();
+    contents.push_str("only,three,fields\n");
+    contents.push_str("sid,uid,e,u,r,a,r,not-a-number,1,1,dev,,\n");
+    std::fs::write(&path, contents).unwrap();
+
+    let loaded = backend.load().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].user.username, "alice");
+}
+
+#[test]
+fn test_save_leaves_no_temp_file_and_replaces_content() {
+    let path = temp_path();
+    let backend = FileBackend::new(&path);
+    backend
+        .compact(&[session("alice", "ua"), session("bob", "ua")])
+        .unwrap();
+    backend.compact(&[session("carol", "ua")]).unwrap();
+
+    let loaded = backend.load().unwrap();
+    let dir_entries: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .contains(&*path.file_name().unwrap().to_string_lossy())
+        })
+        .collect();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(dir_entries.len(), 1);
+}
+
+#[test]
+fn test_legacy_unversioned_file_is_migrated() {
+    let path = temp_path();
+    std::fs::write(
+        &path,
+        "session_id,user_id,email,username,role,access_token,refresh_token,expires_at\n\
+         sid-1,uid-1,a@example.com,alice,authenticated,at,rt,99999999999\n\
+         garbage\n",
+    )
+    .unwrap();
+
+    let loaded = FileBackend::new(&path).load().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].id, "sid-1");
+    assert_eq!(loaded[0].user.username, "alice");
+}
+
+#[test]
+fn test_unknown_format_version_is_rejected() {
+    let path = temp_path();
+    std::fs::write(&path, "#lapp-sessions v99\n").unwrap();
+
+    let result = FileBackend::new(&path).load();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_torn_journal_line_is_skipped() {
+    let path = temp_path();
+    let backend = FileBackend::new(&path);
+    let s1 = session("alice", "ua");
+    backend
+        .apply(&[SessionOp::Upsert(Box::new(s1.clone()))])
+        .unwrap();
+
+    let mut journal = path.as_os_str().to_owned();
+    journal.push(".journal");
+    let mut contents = std::fs::read_to_string(&journal).unwrap();
+    contents.push_str("{\"op\":\"upsert\",\"session_id\":\"half-writ");
+    std::fs::write(&journal, contents).unwrap();
+
+    let loaded = backend.load().unwrap();
+    let _ = std::fs::remove_file(&journal);
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].id, s1.id);
+}
+
+#[test]
+fn test_append_after_torn_journal_line_is_kept() {
+    let path = temp_path();
+    let backend = FileBackend::new(&path);
+    let s1 = session("alice", "ua");
+    backend
+        .apply(&[SessionOp::Upsert(Box::new(s1.clone()))])
+        .unwrap();
+
+    let mut journal = path.as_os_str().to_owned();
+    journal.push(".journal");
+    let mut contents = std::fs::read_to_string(&journal).unwrap();
+    contents.push_str("{\"op\":\"upsert\",\"session_id\":\"half-writ");
+    std::fs::write(&journal, contents).unwrap();
+
+    let s2 = session("bob", "ua");
+    backend
+        .apply(&[SessionOp::Upsert(Box::new(s2.clone()))])
+        .unwrap();
+    let loaded = backend.load().unwrap();
+    let _ = std::fs::remove_file(&journal);
+
+    assert_eq!(loaded.len(), 2);
+    assert!(loaded.iter().any(|s| s.id == s2.id));
+}
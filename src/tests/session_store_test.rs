@@ -0,0 +1,223 @@
+use super::support::{app_with, config};
+use crate::domain::{
+    Aal, ClientInfo, SessionBackend, SessionLimit, SessionOp, SessionPolicy, SessionStore, User,
+    WriteBehind,
+};
+use crate::infrastructure::session::{FileBackend, MemoryBackend, SqliteBackend};
+use crate::shared::time::now_secs;
+use std::sync::Arc;
+
+fn user(id: &str) -> User {
+    User {
+        id: id.to_string(),
+        email: format!("{}@example.com", id),
+        username: id.to_string(),
+        role: "authenticated".to_string(),
+        roles: Vec::new(),
+        access_token: format!("access-{}", id),
+        refresh_token: format!("refresh-{}", id),
+        expires_at: now_secs() + 3600,
+    }
+}
+
+fn client(ua: &str) -> ClientInfo {
+    ClientInfo {
+        ip: Some("127.0.0.1".to_string()),
+        user_agent: Some(ua.to_string()),
+    }
+}
+
+fn store(limit: SessionLimit, backend: Arc<dyn SessionBackend>) -> SessionStore {
+    SessionStore::new(
+        SessionPolicy {
+            limit,
+            ..SessionPolicy::default()
+        },
+        backend,
+        WriteBehind::default(),
+    )
+}
+
+#[test]
+fn test_sessions_coexist_when_unlimited() {
+    let store = store(SessionLimit::Unlimited, Arc::new(MemoryBackend));
+    let laptop = store.create_session(user("alice"), client("laptop"));
+    let phone = store.create_session(user("alice"), client("phone"));
+
+    assert!(store.get_session(&laptop.id).is_some());
+    assert!(store.get_session(&phone.id).is_some());
+    assert_eq!(store.list_user_sessions("alice").len(), 2);
+}
+
+#[test]
+fn test_single_session_limit_evicts_previous_login() {
+    let store = store(SessionLimit::Single, Arc::new(MemoryBackend));
+    let laptop = store.create_session(user("alice"), client("laptop"));
+    let phone = store.create_session(user("alice"), client("phone"));
+
+    assert!(store.get_session(&laptop.id).is_none());
+    assert!(store.get_session(&phone.id).is_some());
+}
+
+#[actix_web::test]
+async fn test_app_state_takes_session_policy_from_config() {
+    let mut cfg = config("http://127.0.0.1:1");
+    cfg.session_limit = SessionLimit::Single;
+    let state = app_with(cfg);
+    let laptop = state
+        .auth
+        .sessions()
+        .create_session(user("alice"), client("laptop"));
+    let phone = state
+        .auth
+        .sessions()
+        .create_session(user("alice"), client("phone"));
+
+    assert!(state.auth.sessions().get_session(&laptop.id).is_none());
+    assert!(state.auth.sessions().get_session(&phone.id).is_some());
+}
+
+#[test]
+fn test_most_recent_limit_keeps_n_sessions() {
+    let store = store(SessionLimit::MostRecent(2), Arc::new(MemoryBackend));
+    for device in ["a", "b", "c"] {
+        store.create_session(user("alice"), client(device));
+    }
+
+    assert_eq!(store.list_user_sessions("alice").len(), 2);
+}
+
+#[test]
+fn test_revoke_by_device_id_is_scoped_to_owner() {
+    let store = store(SessionLimit::Unlimited, Arc::new(MemoryBackend));
+    let alice = store.create_session(user("alice"), client("laptop"));
+
+    assert!(
+        store
+            .delete_user_session("mallory", &alice.device_id)
+            .is_none()
+    );
+    assert!(
+        store
+            .delete_user_session("alice", &alice.device_id)
+            .is_some()
+    );
+    assert!(store.get_session(&alice.id).is_none());
+}
+
+#[test]
+fn test_sqlite_backend_round_trip() {
+    let backend = SqliteBackend::open(":memory:").unwrap();
+    let mut alice = user("alice");
+    alice.roles = vec!["admin".to_string(), "support".to_string()];
+    let session = store(SessionLimit::Unlimited, Arc::new(MemoryBackend))
+        .create_session(alice, client("Mozilla/5.0 (X11; Linux x86_64)"));
+
+    backend
+        .apply(&[SessionOp::Upsert(Box::new(session.clone()))])
+        .unwrap();
+    let loaded = backend.load().unwrap();
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].id, session.id);
+    assert_eq!(loaded[0].device_id, session.device_id);
+    assert_eq!(loaded[0].user.refresh_token, session.user.refresh_token);
+    assert_eq!(loaded[0].client.user_agent, session.client.user_agent);
+    assert_eq!(loaded[0].user.roles, session.user.roles);
+
+    backend
+        .apply(&[SessionOp::Remove(session.id.clone())])
+        .unwrap();
+    assert!(backend.load().unwrap().is_empty());
+}
+
+#[test]
+fn test_sqlite_backend_keeps_aal_and_migrates_old_databases() {
+    let path = std::env::temp_dir().join(format!("lapp-sessions-{}.db", uuid::Uuid::new_v4()));
+    // Schema from before the aal column
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch(
+            "CREATE TABLE sessions (session_id TEXT PRIMARY KEY, device_id TEXT NOT NULL,
+                user_id TEXT NOT NULL, email TEXT NOT NULL, username TEXT NOT NULL,
+                role TEXT NOT NULL, access_token TEXT NOT NULL, refresh_token TEXT NOT NULL,
+                expires_at INTEGER NOT NULL, created_at INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL, ip TEXT, user_agent TEXT);
+             INSERT INTO sessions VALUES ('old', 'device', 'bob', 'bob@example.com', 'bob',
+                'authenticated', 'access', 'refresh', 9999999999, 0, 0, NULL, NULL);",
+        )
+        .unwrap();
+
+    let backend = SqliteBackend::open(path.to_str().unwrap()).unwrap();
+    let session = store(SessionLimit::Unlimited, Arc::new(MemoryBackend)).create_session_with_aal(
+        user("alice"),
+        client("laptop"),
+        Aal::Aal2,
+    );
+    backend
+        .apply(&[SessionOp::Upsert(Box::new(session.clone()))])
+        .unwrap();
+    let loaded = backend.load().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let aal = |id: &str| loaded.iter().find(|s| s.id == id).map(|s| s.aal);
+    assert_eq!(aal("old"), Some(Aal::Aal1));
+    assert_eq!(aal(&session.id), Some(Aal::Aal2));
+}
+
+#[test]
+fn test_file_backend_restores_sessions_on_restart() {
+    let path = std::env::temp_dir().join(format!("lapp-sessions-{}.csv", uuid::Uuid::new_v4()));
+
+    let first = store(SessionLimit::Unlimited, Arc::new(FileBackend::new(&path)));
+    let session = first.create_session(user("alice"), client("laptop"));
+    first.shutdown();
+
+    let restarted = store(SessionLimit::Unlimited, Arc::new(FileBackend::new(&path)));
+    let restored = restarted.get_session(&session.id).map(|s| s.user);
+    cleanup(&path);
+
+    assert_eq!(restored.map(|u| u.id), Some("alice".to_string()));
+}
+
+#[test]
+fn test_flushed_journal_survives_crash_without_compaction() {
+    let path = std::env::temp_dir().join(format!("lapp-sessions-{}.csv", uuid::Uuid::new_v4()));
+
+    let first = store(SessionLimit::Unlimited, Arc::new(FileBackend::new(&path)));
+    let kept = first.create_session(user("alice"), client("laptop"));
+    let removed = first.create_session(user("bob"), client("phone"));
+    first.delete_session(&removed.id);
+    assert_eq!(first.flush(), 2);
+    drop(first); // no shutdown: only the journal has the changes
+
+    let restarted = store(SessionLimit::Unlimited, Arc::new(FileBackend::new(&path)));
+    let alice = restarted.get_session(&kept.id);
+    let bob = restarted.get_session(&removed.id);
+    cleanup(&path);
+
+    assert!(alice.is_some());
+    assert!(bob.is_none());
+}
+
+#[test]
+fn test_unflushed_changes_are_not_written() {
+    let path = std::env::temp_dir().join(format!("lapp-sessions-{}.csv", uuid::Uuid::new_v4()));
+
+    let first = store(SessionLimit::Unlimited, Arc::new(FileBackend::new(&path)));
+    let session = first.create_session(user("alice"), client("laptop"));
+    drop(first);
+
+    let restarted = store(SessionLimit::Unlimited, Arc::new(FileBackend::new(&path)));
+    let restored = restarted.get_session(&session.id).map(|s| s.user);
+    cleanup(&path);
+
+    assert!(restored.is_none());
+}
+
+fn cleanup(path: &std::path::Path) {
+    let mut journal = path.as_os_str().to_owned();
+    journal.push(".journal");
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(journal);
+}
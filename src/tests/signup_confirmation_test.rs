@@ -0,0 +1,125 @@
+use super::support::{PASSWORD, RATE_LIMITED_EMAIL, TOKEN_HASH_OK, USER_ID, app, fake_supabase};
+use crate::api;
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+fn register(email: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/register")
+        .set_json(json!({ "email": email, "password": PASSWORD, "username": "newbie" }))
+}
+
+fn confirm(token_hash: &str) -> test::TestRequest {
+    test::TestRequest::get().uri(&format!(
+        "/auth/confirm?token_hash={}&type=signup",
+        token_hash
+    ))
+}
+
+#[actix_web::test]
+async fn test_signup_pending_confirmation_returns_accepted_without_cookie() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, register("pending@example.com").to_request()).await;
+
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    assert!(resp.headers().get(header::SET_COOKIE).is_none());
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["email"], "pending@example.com");
+}
+
+#[actix_web::test]
+async fn test_signup_without_confirmation_logs_in() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, register("user@example.com").to_request()).await;
+
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert!(resp.headers().get(header::SET_COOKIE).is_some());
+}
+
+#[actix_web::test]
+async fn test_resend_confirmation_response_is_uniform() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let mut bodies = Vec::new();
+    for email in ["pending@example.com", RATE_LIMITED_EMAIL] {
+        let req = test::TestRequest::post()
+            .uri("/auth/resend-confirmation")
+            .set_json(json!({ "email": email }))
+            .to_request();
+        let resp = test::call_service(&svc, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        bodies.push(test::read_body(resp).await);
+    }
+
+    assert_eq!(bodies[0], bodies[1]);
+}
+
+#[actix_web::test]
+async fn test_confirmation_link_creates_session() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, confirm(TOKEN_HASH_OK).to_request()).await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::SET_COOKIE).is_some());
+    assert_eq!(state.auth.sessions().list_user_sessions(USER_ID).len(), 1);
+}
+
+#[actix_web::test]
+async fn test_invalid_confirmation_link_is_rejected() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let resp = test::call_service(&svc, confirm("expired").to_request()).await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "AUTH_INVALID_OTP");
+}
+
+#[actix_web::test]
+async fn test_confirmation_redirects_browser_when_configured() {
+    let (url, _) = fake_supabase();
+    let mut state = app(&url);
+    state.config.email_confirm_redirect = "https://app.example.com/welcome".to_string();
+    let svc = service!(state);
+
+    let ok = test::call_service(&svc, confirm(TOKEN_HASH_OK).to_request()).await;
+    assert_eq!(ok.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        ok.headers().get(header::LOCATION).unwrap(),
+        "https://app.example.com/welcome"
+    );
+    assert!(ok.headers().get(header::SET_COOKIE).is_some());
+
+    let failed = test::call_service(&svc, confirm("expired").to_request()).await;
+    assert_eq!(failed.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        failed.headers().get(header::LOCATION).unwrap(),
+        "https://app.example.com/welcome?error=AUTH_INVALID_OTP"
+    );
+    assert!(failed.headers().get(header::SET_COOKIE).is_none());
+}
@@ -0,0 +1,103 @@
+use super::support::{CODE_OK, PASSWORD, PHONE_OK, USER_ID, app, fake_supabase, jwt, user};
+use crate::api;
+use crate::domain::ClientInfo;
+use crate::shared::time::now_secs;
+use actix_web::cookie::Cookie;
+use actix_web::http::{StatusCode, header};
+use actix_web::{App as ActixApp, test, web};
+use serde_json::{Value, json};
+
+macro_rules! service {
+    ($state:expr) => {
+        test::init_service(
+            ActixApp::new()
+                .app_data(web::Data::new($state.clone()))
+                .configure(api::init),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+async fn test_register_rejects_invalid_phone() {
+    let state = app("http://127.0.0.1:1");
+    let svc = service!(state);
+
+    for (country_code, number) in [(json!("+33"), json!("12")), (json!("+33"), Value::Null)] {
+        let req = test::TestRequest::post()
+            .uri("/auth/register")
+            .set_json(json!({
+                "email": "user@example.com",
+                "password": PASSWORD,
+                "username": "newbie",
+                "phone_country_code": country_code,
+                "phone_number": number,
+            }))
+            .to_request();
+        let resp = test::call_service(&svc, req).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[actix_web::test]
+async fn test_sms_login_creates_session() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let svc = service!(state);
+
+    let sent = test::TestRequest::post()
+        .uri("/auth/sms-otp")
+        .set_json(json!({ "phone": "+33 6 12 34 56 78" }))
+        .to_request();
+    assert_eq!(
+        test::call_service(&svc, sent).await.status(),
+        StatusCode::OK
+    );
+
+    let verify = |code: &str| {
+        test::TestRequest::post()
+            .uri("/auth/verify-sms-otp")
+            .set_json(json!({ "phone": "0033 612 345 678", "code": code }))
+            .to_request()
+    };
+    let resp = test::call_service(&svc, verify(CODE_OK)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::SET_COOKIE).is_some());
+
+    let resp = test::call_service(&svc, verify("000000")).await;
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "AUTH_INVALID_OTP");
+}
+
+#[actix_web::test]
+async fn test_phone_verification_defaults_to_registered_number() {
+    let (url, _) = fake_supabase();
+    let state = app(&url);
+    let mut alice = user(USER_ID, "authenticated");
+    alice.access_token = jwt(now_secs() + 3600, true);
+    let session = state
+        .auth
+        .sessions()
+        .create_session(alice, ClientInfo::default());
+    let svc = service!(state);
+
+    let req = test::TestRequest::post()
+        .uri("/user/phone")
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&svc, req).await;
+    assert_eq!(body["phone"], PHONE_OK);
+
+    let req = test::TestRequest::post()
+        .uri("/user/phone/verify")
+        .cookie(Cookie::new("session_id", session.secret.clone()))
+        .set_json(json!({ "phone": body["phone"], "code": CODE_OK }))
+        .to_request();
+    let resp = test::call_service(&svc, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // The session adopted the tokens issued by the verification
+    let updated = state.auth.sessions().get_session(&session.id).unwrap();
+    assert_eq!(updated.user.access_token, "access-new");
+}
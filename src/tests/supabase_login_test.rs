@@ -20,7 +20,7 @@ async fn test_supabase_login_real() {
             println!("==============================");
 
             // we just check the response is not empty
-            assert!(!response.access_token.is_empty());
+            assert!(!response.user.access_token.is_empty());
         }
         Err(err) => panic!("Supabase login failed: {:?}", err),
     }
@@ -0,0 +1,513 @@
+//! Shared test helpers
+
+use crate::app::App;
+use crate::apps::CollectionApp;
+use crate::config::Config;
+use crate::domain::{AppModule, SessionLimit, SessionPolicy, SessionStore, User, WriteBehind};
+use crate::infrastructure::session::MemoryBackend;
+use crate::infrastructure::{AuthProviderKind, SessionBackendKind};
+use crate::services::{
+    AuthService, AuthzService, EntitlementService, PasskeyService, UserAdminService,
+};
+use crate::shared::time::now_secs;
+use actix_web::{App as ActixApp, HttpRequest, HttpResponse, HttpServer, web};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Config pointing Supabase at `sp_url`, with in-memory sessions
+pub fn config(sp_url: &str) -> Config {
+    Config {
+        ip: "127.0.0.1".to_string(),
+        port: "0".to_string(),
+        auth_provider: AuthProviderKind::Supabase,
+        local_auth_db: ":memory:".to_string(),
+        sp_id: "test".to_string(),
+        sp_url: sp_url.to_string(),
+        sp_anon: "anon-key".to_string(),
+        sp_service_role: "service-role-key".to_string(),
+        secure_http: "false".to_string(),
+        sp_jwt_secret: String::new(),
+        sp_jwks: String::new(),
+        sp_jwt_audience: "authenticated".to_string(),
+        sp_jwt_roles: "authenticated".to_string(),
+        password_reset_redirect: String::new(),
+        email_confirm_redirect: String::new(),
+        oauth_providers: "github,google".to_string(),
+        oauth_callback_url: String::new(),
+        oauth_redirect: String::new(),
+        webauthn_rp_id: "localhost".to_string(),
+        webauthn_rp_name: "LAPP".to_string(),
+        webauthn_origins: "http://localhost:3000".to_string(),
+        passkey_db: ":memory:".to_string(),
+        roles_db: ":memory:".to_string(),
+        entitlements_db: ":memory:".to_string(),
+        login_window: 900,
+        login_account_attempts: 5,
+        login_ip_attempts: 50,
+        login_lockout: 900,
+        login_delay_after: 2,
+        login_throttle_db: ":memory:".to_string(),
+        audit_log: ":memory:".to_string(),
+        session_idle_ttl: 3600,
+        session_absolute_ttl: 86400,
+        session_reap_interval: 300,
+        session_refresh_margin: 300,
+        session_refresh_interval: 60,
+        session_limit: SessionLimit::Unlimited,
+        session_backend: SessionBackendKind::Memory,
+        session_file: String::new(),
+        session_db: String::new(),
+        session_flush_interval: 1,
+        session_flush_batch: 256,
+        session_compact_after: 10_000,
+        session_encryption_keys: String::new(),
+        session_encryption_key_id: String::new(),
+    }
+}
+
+/// Application state for handler tests, Supabase at `sp_url`
+pub fn app(sp_url: &str) -> App {
+    app_with(config(sp_url))
+}
+
+/// Application state for handler tests with a custom configuration
+pub fn app_with(cfg: Config) -> App {
+    let sessions = SessionStore::new(
+        SessionPolicy {
+            idle_ttl: cfg.session_idle_ttl,
+            absolute_ttl: cfg.session_absolute_ttl,
+            limit: cfg.session_limit,
+        },
+        Arc::new(MemoryBackend),
+        WriteBehind::default(),
+    );
+
+    let auth = AuthService::new(&cfg, sessions);
+    let collection = CollectionApp::new();
+    App {
+        name: "LAPP".to_string(),
+        version: "test".to_string(),
+        passkeys: PasskeyService::from_config(&cfg).unwrap(),
+        authz: AuthzService::from_config(&cfg).unwrap(),
+        entitlements: EntitlementService::from_config(&cfg, vec![collection.info().clone()])
+            .unwrap(),
+        users: UserAdminService::from_config(&cfg, auth.audit().clone()),
+        auth,
+        config: cfg,
+        collection,
+    }
+}
+
+/// A user whose access token is valid for another hour
+pub fn user(id: &str, role: &str) -> User {
+    User {
+        id: id.to_string(),
+        email: format!("{}@example.com", id),
+        username: id.to_string(),
+        role: role.to_string(),
+        roles: Vec::new(),
+        access_token: "access".to_string(),
+        refresh_token: "refresh".to_string(),
+        expires_at: now_secs() + 3600,
+    }
+}
+
+// ============================================================================
+// FAKE SUPABASE
+// ============================================================================
+
+/// Password accepted by the fake Supabase
+pub const PASSWORD: &str = "correct-horse";
+
+/// Refresh token accepted by the fake Supabase
+pub const REFRESH_OK: &str = "refresh-ok";
+
+/// One-time token hash and email code accepted by the fake Supabase
+pub const TOKEN_HASH_OK: &str = "hash-ok";
+pub const CODE_OK: &str = "123456";
+
+/// OAuth authorization code accepted by the fake Supabase (with any 43-character verifier)
+pub const OAUTH_CODE_OK: &str = "oauth-code-ok";
+
+/// Verified phone number of `USER_ID`, the fake Supabase accepts `CODE_OK` for it
+pub const PHONE_OK: &str = "+33612345678";
+
+/// Password the fake Supabase refuses as too weak
+pub const WEAK_PASSWORD: &str = "weakweak";
+
+/// Email already used by another account, the fake Supabase refuses to move to it
+pub const TAKEN_EMAIL: &str = "taken@example.com";
+
+/// Email the fake Supabase answers with 429
+pub const RATE_LIMITED_EMAIL: &str = "limited@example.com";
+
+/// Password logins for this email, and this one-time token hash, return a user with the
+/// verified TOTP factor `FACTOR_ID`
+pub const MFA_EMAIL: &str = "mfa@example.com";
+pub const MFA_TOKEN_HASH: &str = "hash-mfa";
+pub const FACTOR_ID: &str = "factor-1";
+
+/// ID of the fake Supabase's one account - a UUID like every Supabase user ID
+pub const USER_ID: &str = "5b0c6a52-1f3e-4d8a-9c27-7e4f1d2b8a60";
+
+/// JWT-shaped token expiring at `exp`; the fake Supabase accepts it when `valid`
+pub fn jwt(exp: u64, valid: bool) -> String {
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+    let payload = URL_SAFE_NO_PAD.encode(json!({ "sub": USER_ID, "exp": exp }).to_string());
+    format!(
+        "eyJhbGciOiJIUzI1NiJ9.{}.{}",
+        payload,
+        if valid { "valid" } else { "forged" }
+    )
+}
+
+fn session_json() -> Value {
+    json!({
+        "access_token": "access-new",
+        "token_type": "bearer",
+        "expires_in": 3600,
+        "expires_at": now_secs() + 3600,
+        "refresh_token": "refresh-new",
+        "user": user_json()
+    })
+}
+
+fn user_json() -> Value {
+    json!({
+        "id": USER_ID,
+        "email": "user@example.com",
+        "role": "authenticated",
+        "aud": "authenticated",
+        "app_metadata": {},
+        // Phone as stored before E.164 normalization
+        "user_metadata": { "username": "user", "phone_country_code": "+33", "phone_number": "06 12 34 56 78" }
+    })
+}
+
+/// POST /auth/v1/token - password, refresh_token and pkce grants
+async fn token_endpoint(
+    calls: web::Data<AtomicUsize>,
+    query: web::Query<HashMap<String, String>>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    // Give concurrent callers time to pile up
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let accepted = match query.get("grant_type").map(String::as_str) {
+        Some("password") => body["password"] == PASSWORD,
+        Some("refresh_token") => body["refresh_token"] == REFRESH_OK,
+        Some("pkce") => {
+            body["auth_code"] == OAUTH_CODE_OK
+                && body["code_verifier"]
+                    .as_str()
+                    .is_some_and(|v| v.len() == 43)
+        }
+        _ => false,
+    };
+    if !accepted {
+        return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+    }
+
+    if body["email"] == MFA_EMAIL {
+        return HttpResponse::Ok().json(mfa_session_json());
+    }
+    HttpResponse::Ok().json(session_json())
+}
+
+/// Session of `MFA_EMAIL`, whose user lists its factors
+fn mfa_session_json() -> Value {
+    let mut session = session_json();
+    session["user"]["email"] = json!(MFA_EMAIL);
+    session["user"]["factors"] = json!([
+        { "id": FACTOR_ID, "factor_type": "totp", "status": "verified", "friendly_name": "Phone" },
+        { "id": "factor-unverified", "factor_type": "totp", "status": "unverified" }
+    ]);
+    session
+}
+
+/// POST /auth/v1/signup - emails starting with `pending` need confirmation (no session)
+async fn signup_endpoint(calls: web::Data<AtomicUsize>, body: web::Json<Value>) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    let email = body["email"].as_str().unwrap_or_default();
+    if email.starts_with("pending") {
+        let mut user = user_json();
+        user["email"] = json!(email);
+        user["confirmation_sent_at"] = json!("2024-01-01T00:00:00Z");
+        return HttpResponse::Ok().json(user);
+    }
+    HttpResponse::Ok().json(session_json())
+}
+
+/// POST /auth/v1/recover, /resend and /otp - always 200 like Supabase, except for the rate-limited email
+async fn recover_endpoint(calls: web::Data<AtomicUsize>, body: web::Json<Value>) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if body["email"] == RATE_LIMITED_EMAIL {
+        return HttpResponse::TooManyRequests().json(json!({ "msg": "rate limited" }));
+    }
+    HttpResponse::Ok().json(json!({}))
+}
+
+/// POST /auth/v1/verify - accepts `TOKEN_HASH_OK`, or `CODE_OK` for user@example.com / `PHONE_OK`
+/// `MFA_TOKEN_HASH` signs `MFA_EMAIL` in
+async fn verify_endpoint(calls: web::Data<AtomicUsize>, body: web::Json<Value>) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if body["token_hash"] == MFA_TOKEN_HASH {
+        return HttpResponse::Ok().json(mfa_session_json());
+    }
+    let accepted = body["token_hash"] == TOKEN_HASH_OK
+        || ((body["email"] == "user@example.com" || body["phone"] == PHONE_OK)
+            && body["token"] == CODE_OK);
+    if !accepted {
+        return HttpResponse::Forbidden().json(json!({ "error_code": "otp_expired" }));
+    }
+    HttpResponse::Ok().json(session_json())
+}
+
+/// PUT /auth/v1/user - password, phone or email update, refuses `WEAK_PASSWORD` and `TAKEN_EMAIL`
+async fn update_user_endpoint(
+    calls: web::Data<AtomicUsize>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if body["password"] == WEAK_PASSWORD {
+        return HttpResponse::UnprocessableEntity().json(json!({ "error_code": "weak_password" }));
+    }
+    if body["email"] == TAKEN_EMAIL {
+        return HttpResponse::UnprocessableEntity().json(json!({ "error_code": "email_exists" }));
+    }
+    HttpResponse::Ok().json(user_json())
+}
+
+/// POST /auth/v1/factors - TOTP enrollment
+async fn enroll_factor_endpoint(calls: web::Data<AtomicUsize>) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    HttpResponse::Ok().json(json!({
+        "id": "factor-new",
+        "type": "totp",
+        "totp": {
+            "qr_code": "data:image/svg+xml;utf-8,<svg/>",
+            "secret": "JBSWY3DPEHPK3PXP",
+            "uri": "otpauth://totp/lapp:user@example.com?secret=JBSWY3DPEHPK3PXP"
+        }
+    }))
+}
+
+/// POST /auth/v1/factors/{id}/challenge
+async fn challenge_endpoint(calls: web::Data<AtomicUsize>) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    HttpResponse::Ok().json(json!({ "id": "challenge-1", "expires_at": now_secs() + 300 }))
+}
+
+/// POST /auth/v1/factors/{id}/verify - accepts `CODE_OK`
+async fn verify_factor_endpoint(
+    calls: web::Data<AtomicUsize>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if body["challenge_id"] != "challenge-1" || body["code"] != CODE_OK {
+        return HttpResponse::UnprocessableEntity()
+            .json(json!({ "error_code": "mfa_verification_failed" }));
+    }
+    HttpResponse::Ok().json(session_json())
+}
+
+/// DELETE /auth/v1/factors/{id} - only `FACTOR_ID` exists
+async fn unenroll_factor_endpoint(
+    calls: web::Data<AtomicUsize>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if path.as_str() != FACTOR_ID {
+        return HttpResponse::NotFound().json(json!({ "error_code": "mfa_factor_not_found" }));
+    }
+    HttpResponse::Ok().json(json!({ "id": FACTOR_ID }))
+}
+
+/// Whether the request carries the service role key
+fn is_admin(req: &HttpRequest) -> bool {
+    req.headers()
+        .get("Authorization")
+        .is_some_and(|v| v == "Bearer service-role-key")
+}
+
+/// GET /auth/v1/admin/users/{id} - only `USER_ID` exists
+async fn admin_user_endpoint(
+    calls: web::Data<AtomicUsize>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if !is_admin(&req) {
+        return HttpResponse::Forbidden().json(json!({ "msg": "not admin" }));
+    }
+    if path.as_str() != USER_ID {
+        return HttpResponse::NotFound().json(json!({ "error_code": "user_not_found" }));
+    }
+    HttpResponse::Ok().json(user_json())
+}
+
+/// GET /auth/v1/admin/users - a single page holding `USER_ID`
+async fn admin_list_users_endpoint(
+    calls: web::Data<AtomicUsize>,
+    req: HttpRequest,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if !is_admin(&req) {
+        return HttpResponse::Forbidden().json(json!({ "msg": "not admin" }));
+    }
+    let users = if query.get("page").map(String::as_str) == Some("1") {
+        vec![user_json()]
+    } else {
+        Vec::new()
+    };
+    HttpResponse::Ok()
+        .insert_header(("x-total-count", "1"))
+        .json(json!({ "users": users, "aud": "authenticated" }))
+}
+
+/// PUT /auth/v1/admin/users/{id} - metadata merge and bans of `USER_ID`
+async fn admin_update_user_endpoint(
+    calls: web::Data<AtomicUsize>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if !is_admin(&req) {
+        return HttpResponse::Forbidden().json(json!({ "msg": "not admin" }));
+    }
+    if path.as_str() != USER_ID {
+        return HttpResponse::NotFound().json(json!({ "error_code": "user_not_found" }));
+    }
+
+    let mut user = user_json();
+    for key in ["app_metadata", "user_metadata"] {
+        if let Some(update) = body[key].as_object() {
+            for (k, v) in update {
+                user[key][k] = v.clone();
+            }
+        }
+    }
+    match body["ban_duration"].as_str() {
+        Some("none") => user["banned_until"] = Value::Null,
+        Some(_) => user["banned_until"] = json!("2125-01-01T00:00:00Z"),
+        None => {}
+    }
+    HttpResponse::Ok().json(user)
+}
+
+/// DELETE /auth/v1/admin/users/{id} - only `USER_ID` exists
+async fn admin_delete_user_endpoint(
+    calls: web::Data<AtomicUsize>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if !is_admin(&req) {
+        return HttpResponse::Forbidden().json(json!({ "msg": "not admin" }));
+    }
+    if path.as_str() != USER_ID {
+        return HttpResponse::NotFound().json(json!({ "error_code": "user_not_found" }));
+    }
+    HttpResponse::Ok().json(json!({}))
+}
+
+/// POST /auth/v1/admin/generate_link - links redeem with `TOKEN_HASH_OK`
+async fn generate_link_endpoint(calls: web::Data<AtomicUsize>, req: HttpRequest) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if !is_admin(&req) {
+        return HttpResponse::Forbidden().json(json!({ "msg": "not admin" }));
+    }
+    let mut link = user_json();
+    link["hashed_token"] = json!(TOKEN_HASH_OK);
+    link["verification_type"] = json!("magiclink");
+    HttpResponse::Ok().json(link)
+}
+
+/// POST /auth/v1/logout
+async fn logout_endpoint() -> HttpResponse {
+    HttpResponse::NoContent().finish()
+}
+
+/// GET /auth/v1/user - accepts tokens built by `jwt(_, true)`
+async fn user_endpoint(calls: web::Data<AtomicUsize>, req: HttpRequest) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+
+    let valid = req
+        .headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.ends_with(".valid"));
+    if !valid {
+        return HttpResponse::Unauthorized().json(json!({ "msg": "invalid JWT" }));
+    }
+    HttpResponse::Ok().json(user_json())
+}
+
+/// Start a fake Supabase auth server, returning its base URL and request counter
+/// Must be called from an actix runtime (`#[actix_web::test]`)
+pub fn fake_supabase() -> (String, Arc<AtomicUsize>) {
+    let calls = web::Data::new(AtomicUsize::new(0));
+    let counter = calls.clone().into_inner();
+
+    let server = HttpServer::new(move || {
+        ActixApp::new()
+            .app_data(calls.clone())
+            .route("/auth/v1/token", web::post().to(token_endpoint))
+            .route("/auth/v1/user", web::get().to(user_endpoint))
+            .route("/auth/v1/user", web::put().to(update_user_endpoint))
+            .route("/auth/v1/signup", web::post().to(signup_endpoint))
+            .route("/auth/v1/recover", web::post().to(recover_endpoint))
+            .route("/auth/v1/resend", web::post().to(recover_endpoint))
+            .route("/auth/v1/otp", web::post().to(recover_endpoint))
+            .route("/auth/v1/verify", web::post().to(verify_endpoint))
+            .route("/auth/v1/logout", web::post().to(logout_endpoint))
+            .route(
+                "/auth/v1/admin/users",
+                web::get().to(admin_list_users_endpoint),
+            )
+            .route(
+                "/auth/v1/admin/users/{id}",
+                web::get().to(admin_user_endpoint),
+            )
+            .route(
+                "/auth/v1/admin/users/{id}",
+                web::put().to(admin_update_user_endpoint),
+            )
+            .route(
+                "/auth/v1/admin/users/{id}",
+                web::delete().to(admin_delete_user_endpoint),
+            )
+            .route(
+                "/auth/v1/admin/generate_link",
+                web::post().to(generate_link_endpoint),
+            )
+            .route("/auth/v1/factors", web::post().to(enroll_factor_endpoint))
+            .route(
+                "/auth/v1/factors/{id}/challenge",
+                web::post().to(challenge_endpoint),
+            )
+            .route(
+                "/auth/v1/factors/{id}/verify",
+                web::post().to(verify_factor_endpoint),
+            )
+            .route(
+                "/auth/v1/factors/{id}",
+                web::delete().to(unenroll_factor_endpoint),
+            )
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let addr = server.addrs()[0];
+    actix_web::rt::spawn(server.run());
+
+    (format!("http://{}", addr), counter)
+}
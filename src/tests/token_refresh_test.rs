@@ -0,0 +1,138 @@
+use super::support::{REFRESH_OK, USER_ID, config, fake_supabase};
+use crate::domain::{ClientInfo, SessionPolicy, SessionStore, User, WriteBehind};
+use crate::infrastructure::session::MemoryBackend;
+use crate::services::AuthService;
+use crate::shared::time::now_secs;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+fn auth(sp_url: &str) -> AuthService {
+    let store = SessionStore::new(
+        SessionPolicy::default(),
+        Arc::new(MemoryBackend),
+        WriteBehind::default(),
+    );
+    AuthService::new(&config(sp_url), store)
+}
+
+fn user(refresh_token: &str, expires_in: i64) -> User {
+    User {
+        id: USER_ID.to_string(),
+        email: "user@example.com".to_string(),
+        username: "user".to_string(),
+        role: "authenticated".to_string(),
+        roles: Vec::new(),
+        access_token: "access-old".to_string(),
+        refresh_token: refresh_token.to_string(),
+        expires_at: now_secs().saturating_add_signed(expires_in),
+    }
+}
+
+#[actix_web::test]
+async fn test_fresh_token_is_not_refreshed() {
+    let (url, calls) = fake_supabase();
+    let auth = auth(&url);
+    let session = auth
+        .sessions()
+        .create_session(user(REFRESH_OK, 3600), ClientInfo::default());
+
+    let current = auth.current_session(&session.id).await.unwrap();
+
+    assert_eq!(current.user.access_token, "access-old");
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
+
+#[actix_web::test]
+async fn test_token_near_expiry_is_refreshed_and_stored() {
+    let (url, _) = fake_supabase();
+    let auth = auth(&url);
+    let session = auth
+        .sessions()
+        .create_session(user(REFRESH_OK, 60), ClientInfo::default());
+
+    let current = auth.current_session(&session.id).await.unwrap();
+    let stored = auth.sessions().get_session(&session.id).unwrap();
+
+    assert_eq!(current.user.access_token, "access-new");
+    assert_eq!(stored.user.refresh_token, "refresh-new");
+    assert_eq!(stored.device_id, session.device_id);
+}
+
+#[actix_web::test]
+async fn test_rejected_refresh_invalidates_session() {
+    let (url, _) = fake_supabase();
+    let auth = auth(&url);
+    let session = auth
+        .sessions()
+        .create_session(user("refresh-revoked", 60), ClientInfo::default());
+
+    assert!(auth.current_session(&session.id).await.is_none());
+    assert!(auth.sessions().get_session(&session.id).is_none());
+}
+
+#[actix_web::test]
+async fn test_concurrent_requests_refresh_once() {
+    let (url, calls) = fake_supabase();
+    let auth = auth(&url);
+    let session = auth
+        .sessions()
+        .create_session(user(REFRESH_OK, 60), ClientInfo::default());
+
+    let (a, b, c) = tokio::join!(
+        auth.current_session(&session.id),
+        auth.current_session(&session.id),
+        auth.current_session(&session.id),
+    );
+
+    assert!(
+        [a, b, c]
+            .iter()
+            .all(|s| s.as_ref().unwrap().user.access_token == "access-new")
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[actix_web::test]
+async fn test_refresh_does_not_keep_idle_session_alive() {
+    let (url, _) = fake_supabase();
+    let store = SessionStore::new(
+        SessionPolicy {
+            idle_ttl: 3,
+            ..SessionPolicy::default()
+        },
+        Arc::new(MemoryBackend),
+        WriteBehind::default(),
+    );
+    let auth = AuthService::new(&config(&url), store);
+    let session = auth
+        .sessions()
+        .create_session(user(REFRESH_OK, 60), ClientInfo::default());
+
+    // What the background refresher does, while the client stays away
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    let refreshed = auth.refresh_session(&session.id).await.unwrap();
+    assert_eq!(refreshed.user.access_token, "access-new");
+    assert_eq!(refreshed.last_seen, session.last_seen);
+
+    tokio::time::sleep(Duration::from_millis(2000)).await;
+    assert!(auth.sessions().get_session(&session.id).is_none());
+}
+
+#[actix_web::test]
+async fn test_unreachable_supabase_keeps_session() {
+    // Nothing listens on port 1
+    let auth = auth("http://127.0.0.1:1");
+    let valid = auth
+        .sessions()
+        .create_session(user(REFRESH_OK, 60), ClientInfo::default());
+    let expired = auth
+        .sessions()
+        .create_session(user(REFRESH_OK, -60), ClientInfo::default());
+
+    // Token still usable: served as-is, refresh retried later
+    assert!(auth.current_session(&valid.id).await.is_some());
+    // Token dead: refused for now, but the session survives for a later retry
+    assert!(auth.current_session(&expired.id).await.is_none());
+    assert!(auth.sessions().get_session(&expired.id).is_some());
+}